@@ -1,5 +1,11 @@
 use super::routes;
+use crate::auth::{access_control::init_access_control, init_authentication};
 use crate::db::db::{run_migrations, ConfigMonkeyDb};
+use crate::db::notifications::{listen_for_notifications, Notifier};
+use crate::services::reencryptions_service::resume_reencryption;
+use crate::services::webhooks_service::start_webhook_deliveries;
+use crate::shared::secrets::init_secrets;
+use crate::shared::webhooks::init_webhooks;
 use rocket::{catchers, fairing::AdHoc, figment::Figment, routes, Build, Rocket};
 use rocket_db_pools::Database;
 
@@ -7,18 +13,77 @@ pub fn rocket_from_config(figment: Figment) -> Rocket<Build> {
     rocket::custom(figment)
         .attach(ConfigMonkeyDb::init())
         .attach(AdHoc::try_on_ignite("SQLx Migrations", run_migrations))
+        .attach(AdHoc::try_on_ignite(
+            "JWT Authentication",
+            init_authentication,
+        ))
+        .attach(AdHoc::try_on_ignite(
+            "Role-based access control",
+            init_access_control,
+        ))
+        .attach(AdHoc::try_on_ignite("Secret values", init_secrets))
+        .attach(AdHoc::on_liftoff("Resume re-encryption", |rocket| {
+            Box::pin(resume_reencryption(rocket))
+        }))
+        .attach(AdHoc::try_on_ignite("Webhooks", init_webhooks))
+        .manage(Notifier::new())
+        .attach(AdHoc::on_liftoff("Postgres notifications", |rocket| {
+            Box::pin(listen_for_notifications(rocket))
+        }))
+        .attach(AdHoc::on_liftoff("Webhook deliveries", |rocket| {
+            Box::pin(start_webhook_deliveries(rocket))
+        }))
         .mount(
             "/",
             routes![
+                routes::v1::tenants_routes::create_tenant,
+                routes::v1::tenants_routes::get_tenants,
+                routes::v1::api_keys_routes::create_api_key,
+                routes::v1::api_keys_routes::get_api_keys,
+                routes::v1::api_keys_routes::revoke_api_key,
+                routes::v1::reencryptions_routes::start_reencryption,
+                routes::v1::reencryptions_routes::get_reencryption,
+                routes::v1::audit_routes::get_audit_events,
+                routes::v1::events_routes::get_domain_events,
+                routes::v1::grants_routes::create_grant,
+                routes::v1::grants_routes::get_grants,
+                routes::v1::grants_routes::delete_grant,
                 routes::v1::domains_routes::create_domain,
                 routes::v1::domains_routes::get_domains,
                 routes::v1::domains_routes::delete_domain,
+                routes::v1::domains_routes::set_domain_parent,
+                routes::v1::domains_routes::render_domain,
+                routes::v1::domains_routes::export_domain,
+                routes::v1::domains_routes::import_domain,
+                routes::v1::domains_routes::promote_domain,
+                routes::v1::webhooks_routes::create_webhook,
+                routes::v1::webhooks_routes::get_webhooks,
+                routes::v1::webhooks_routes::delete_webhook,
+                routes::v1::webhooks_routes::get_webhook_deliveries,
+                routes::v1::environments_routes::create_environment,
+                routes::v1::environments_routes::get_environments,
+                routes::v1::environments_routes::delete_environment,
                 routes::v1::configs_routes::create_config,
                 routes::v1::configs_routes::get_configs,
                 routes::v1::configs_routes::get_config,
                 routes::v1::configs_routes::delete_config,
                 routes::v1::versions_routes::create_version,
                 routes::v1::versions_routes::get_versions,
+                routes::v1::versions_routes::get_version,
+                routes::v1::versions_routes::diff_versions,
+                routes::v1::versions_routes::rollback_version,
+                routes::v1::versions_routes::get_value,
+                routes::v1::versions_routes::get_secret_value,
+                routes::v1::versions_routes::get_value_property,
+                routes::v1::versions_routes::set_value_property,
+                routes::v1::versions_routes::delete_value_property,
+                routes::v1::versions_routes::create_environment_version,
+                routes::v1::versions_routes::get_environment_versions,
+                routes::v1::versions_routes::get_environment_version,
+                routes::v1::versions_routes::diff_environment_versions,
+                routes::v1::versions_routes::rollback_environment_version,
+                routes::v1::versions_routes::get_environment_value,
+                routes::v1::versions_routes::get_environment_secret_value,
             ],
         )
         .register(
@@ -26,6 +91,8 @@ pub fn rocket_from_config(figment: Figment) -> Rocket<Build> {
             catchers![
                 routes::v1::errors::default_catcher,
                 routes::v1::errors::not_found,
+                routes::v1::errors::unauthorized,
+                routes::v1::errors::forbidden,
                 routes::v1::errors::bad_request
             ],
         )
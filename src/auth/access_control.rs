@@ -0,0 +1,56 @@
+use rocket::{error, fairing, serde::Deserialize, Build, Rocket};
+
+use crate::models::principal::Principal;
+
+/// Role-based access control settings, read from the `rbac` key of the rocket configuration
+#[derive(Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct AccessControlConfig {
+    /// Every principal is allowed to do anything unless enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Principals allowed to do anything in every tenant, such as creating tenants and
+    /// granting the first roles
+    #[serde(default)]
+    pub administrators: Vec<String>,
+}
+
+/// Decides what the principal of a request is allowed to do
+pub struct AccessControl {
+    enabled: bool,
+    administrators: Vec<String>,
+}
+
+impl AccessControl {
+    /// Resolve the principal of a request from the name it identifies itself with, if any
+    pub fn principal(&self, name: Option<&str>) -> Principal {
+        match name {
+            _ if !self.enabled => Principal::Unrestricted,
+            Some(name) if self.administrators.iter().any(|admin| admin == name) => {
+                Principal::Unrestricted
+            }
+            Some(name) => Principal::Named(name.to_string()),
+            None => Principal::Anonymous,
+        }
+    }
+}
+
+/// Load the access control settings and manage the resulting access control
+pub async fn init_access_control(rocket: Rocket<Build>) -> fairing::Result {
+    let config = match rocket
+        .figment()
+        .extract_inner::<AccessControlConfig>("rbac")
+    {
+        Ok(config) => config,
+        Err(err) if err.missing() => AccessControlConfig::default(),
+        Err(err) => {
+            error!("Invalid access control settings: {}", err);
+            return Err(rocket);
+        }
+    };
+
+    Ok(rocket.manage(AccessControl {
+        enabled: config.enabled,
+        administrators: config.administrators,
+    }))
+}
@@ -0,0 +1,146 @@
+use jsonwebtoken::{
+    decode, decode_header,
+    errors::ErrorKind,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm},
+    Algorithm, DecodingKey, Validation,
+};
+use rocket::serde::{json::from_str, Deserialize};
+use std::fs;
+
+use super::{AuthConfig, AuthError};
+
+/// Claims of a verified token
+#[derive(Deserialize, Debug, Clone)]
+#[serde(crate = "rocket::serde")]
+pub struct Claims {
+    pub sub: String,
+    /// Tenant the token is bound to, the default one when missing
+    #[serde(default)]
+    pub tenant: Option<String>,
+}
+
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+/// Verifies bearer tokens against the keys listed in the authentication settings
+pub struct JwtVerifier {
+    keys: Vec<VerificationKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtVerifier {
+    pub fn from_config(config: &AuthConfig) -> Result<JwtVerifier, String> {
+        let mut keys = vec![];
+        for secret in &config.secrets {
+            keys.push(VerificationKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: DecodingKey::from_secret(secret.as_bytes()),
+            });
+        }
+        for path in &config.public_keys {
+            let pem = fs::read(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            keys.push(
+                from_pem(pem.as_slice()).map_err(|err| format!("{}: {}", path.display(), err))?,
+            );
+        }
+        if let Some(path) = &config.jwks {
+            let jwks =
+                fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            let jwks: JwkSet =
+                from_str(jwks.as_str()).map_err(|err| format!("{}: {}", path.display(), err))?;
+            for jwk in &jwks.keys {
+                keys.push(from_jwk(jwk).map_err(|err| format!("{}: {}", path.display(), err))?);
+            }
+        }
+
+        if keys.is_empty() {
+            return Err(String::from(
+                "Authentication is enabled but no secret, public key or JWKS is configured",
+            ));
+        }
+        Ok(JwtVerifier {
+            keys,
+            issuer: config.issuer.clone(),
+            audience: config.audience.clone(),
+        })
+    }
+
+    /// Verify the signature and claims of a token against every key matching its algorithm and key id
+    pub fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let header = decode_header(token).map_err(|_| AuthError::InvalidToken)?;
+
+        let mut error = AuthError::InvalidToken;
+        let candidates = self.keys.iter().filter(|key| {
+            key.algorithm == header.alg
+                && (key.kid.is_none() || header.kid.is_none() || key.kid == header.kid)
+        });
+        for candidate in candidates {
+            match decode::<Claims>(token, &candidate.key, &self.validation(candidate.algorithm)) {
+                Ok(data) => return Ok(data.claims),
+                // Claims are only validated once the signature matches
+                Err(err) if *err.kind() == ErrorKind::ExpiredSignature => {
+                    error = AuthError::ExpiredToken
+                }
+                Err(_) => (),
+            }
+        }
+        Err(error)
+    }
+
+    fn validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        validation
+    }
+}
+
+fn from_pem(pem: &[u8]) -> Result<VerificationKey, String> {
+    if let Ok(key) = DecodingKey::from_rsa_pem(pem) {
+        return Ok(VerificationKey {
+            kid: None,
+            algorithm: Algorithm::RS256,
+            key,
+        });
+    }
+    match DecodingKey::from_ec_pem(pem) {
+        Ok(key) => Ok(VerificationKey {
+            kid: None,
+            algorithm: Algorithm::ES256,
+            key,
+        }),
+        Err(_) => Err(String::from("not an RSA or EC public key in PEM format")),
+    }
+}
+
+fn from_jwk(jwk: &Jwk) -> Result<VerificationKey, String> {
+    let algorithm = match (&jwk.common.key_algorithm, &jwk.algorithm) {
+        (Some(KeyAlgorithm::RS256), AlgorithmParameters::RSA(_))
+        | (None, AlgorithmParameters::RSA(_)) => Algorithm::RS256,
+        (Some(KeyAlgorithm::ES256), AlgorithmParameters::EllipticCurve(params))
+        | (None, AlgorithmParameters::EllipticCurve(params))
+            if params.curve == EllipticCurve::P256 =>
+        {
+            Algorithm::ES256
+        }
+        _ => return Err(String::from("only RS256 and ES256 keys are supported")),
+    };
+    match DecodingKey::from_jwk(jwk) {
+        Ok(key) => Ok(VerificationKey {
+            kid: jwk.common.key_id.clone(),
+            algorithm,
+            key,
+        }),
+        Err(err) => Err(err.to_string()),
+    }
+}
@@ -0,0 +1,110 @@
+use rocket::{error, fairing, serde::Deserialize, Build, Rocket};
+use std::path::PathBuf;
+
+use self::jwt::{Claims, JwtVerifier};
+
+pub mod access_control;
+pub mod jwt;
+
+/// Authentication settings, read from the `auth` key of the rocket configuration
+#[derive(Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct AuthConfig {
+    /// Requests are not authenticated unless enabled
+    #[serde(default)]
+    pub enabled: bool,
+    /// Shared secrets that HS256 tokens may be signed with
+    #[serde(default)]
+    pub secrets: Vec<String>,
+    /// PEM files holding the RSA or P-256 public keys that RS256 and ES256 tokens may be signed with
+    #[serde(default)]
+    pub public_keys: Vec<PathBuf>,
+    /// Local JWKS file holding the RSA or P-256 public keys that RS256 and ES256 tokens may be signed with
+    pub jwks: Option<PathBuf>,
+    /// Expected `iss` claim, when tokens are checked against an issuer
+    pub issuer: Option<String>,
+    /// Expected `aud` claim, when tokens are checked against an audience
+    pub audience: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken,
+    ExpiredToken,
+    InvalidApiKey,
+    TenantMismatch,
+}
+
+impl AuthError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            AuthError::MissingToken => "missing_token",
+            AuthError::InvalidToken => "invalid_token",
+            AuthError::ExpiredToken => "expired_token",
+            AuthError::InvalidApiKey => "invalid_api_key",
+            AuthError::TenantMismatch => "tenant_mismatch",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            AuthError::MissingToken => {
+                "The request must carry a bearer token in its Authorization header"
+            }
+            AuthError::InvalidToken => {
+                "The bearer token is malformed, or its signature or claims could not be verified"
+            }
+            AuthError::ExpiredToken => "The bearer token has expired",
+            AuthError::InvalidApiKey => "The api key is malformed, unknown, revoked or expired",
+            AuthError::TenantMismatch => {
+                "The X-Tenant header names another tenant than the one of the credentials"
+            }
+        }
+    }
+}
+
+/// Verifies the credentials of incoming requests, when authentication is enabled
+pub enum Authenticator {
+    Disabled,
+    Jwt(JwtVerifier),
+}
+
+impl Authenticator {
+    /// Authenticate a request by the value of its `Authorization` header. Requests are
+    /// anonymous when authentication is disabled
+    pub fn authenticate(&self, authorization: Option<&str>) -> Result<Option<Claims>, AuthError> {
+        match self {
+            Authenticator::Disabled => Ok(None),
+            Authenticator::Jwt(verifier) => {
+                let token = authorization
+                    .and_then(|authorization| authorization.strip_prefix("Bearer "))
+                    .ok_or(AuthError::MissingToken)?;
+                verifier.verify(token.trim()).map(Some)
+            }
+        }
+    }
+}
+
+/// Load the authentication settings and manage the resulting authenticator. Rocket fails to
+/// ignite when authentication is enabled without any usable key
+pub async fn init_authentication(rocket: Rocket<Build>) -> fairing::Result {
+    let config = match rocket.figment().extract_inner::<AuthConfig>("auth") {
+        Ok(config) => config,
+        Err(err) if err.missing() => AuthConfig::default(),
+        Err(err) => {
+            error!("Invalid authentication settings: {}", err);
+            return Err(rocket);
+        }
+    };
+
+    if !config.enabled {
+        return Ok(rocket.manage(Authenticator::Disabled));
+    }
+    match JwtVerifier::from_config(&config) {
+        Ok(verifier) => Ok(rocket.manage(Authenticator::Jwt(verifier))),
+        Err(err) => {
+            error!("Failed to load the authentication keys: {}", err);
+            Err(rocket)
+        }
+    }
+}
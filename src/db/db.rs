@@ -1,6 +1,7 @@
 use rocket::{error, fairing, Build, Rocket};
 use rocket_db_pools::sqlx::{self};
 use rocket_db_pools::Database;
+use sqlx::{Postgres, Transaction};
 
 #[derive(Database)]
 #[database("postgres_configmonkey")]
@@ -18,3 +19,15 @@ pub async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
         None => Err(rocket),
     }
 }
+
+/// Commit the transaction, or roll it back when the changes are only being previewed
+pub async fn finish_transaction(
+    tx: Transaction<'_, Postgres>,
+    dry_run: bool,
+) -> Result<(), sqlx::Error> {
+    if dry_run {
+        tx.rollback().await
+    } else {
+        tx.commit().await
+    }
+}
@@ -0,0 +1,9 @@
+alter type value_type add value 'object';
+alter type value_type add value 'array';
+alter type value_type add value 'null';
+
+-- Scalars were stored as their text representation, so strings need to be
+-- quoted while numbers and booleans are already valid json
+alter table versions
+    alter column value type jsonb
+    using case when type = 'string' then to_jsonb(value) else value::jsonb end;
@@ -0,0 +1,2 @@
+-- Version number a rollback was restored from
+alter table versions add column restored_from int null;
@@ -0,0 +1,16 @@
+create table environments (
+    id uuid default uuid_generate_v4() primary key,
+    domain_id uuid not null,
+    slug varchar not null,
+    created_at timestamptz not null default now(),
+    constraint environments_fk_domains foreign key(domain_id) references domains(id) on delete cascade,
+    constraint environments_unique_slug unique(domain_id, slug)
+);
+
+-- Versions without an environment belong to the domain itself. Each environment
+-- keeps its own history, so version numbers are only unique within it
+alter table versions add column environment_id uuid null;
+alter table versions add constraint versions_fk_environments foreign key(environment_id) references environments(id) on delete cascade;
+alter table versions drop constraint values_unique_version;
+create unique index versions_unique_version on versions(config_id, version) where environment_id is null;
+create unique index versions_unique_environment_version on versions(config_id, environment_id, version) where environment_id is not null;
@@ -0,0 +1,3 @@
+-- Domains inherit the configs of their parent domain that they do not define themselves
+alter table domains add column parent_id uuid null;
+alter table domains add constraint domains_fk_parent foreign key(parent_id) references domains(id);
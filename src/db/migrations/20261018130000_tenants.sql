@@ -0,0 +1,23 @@
+create table tenants (
+    id uuid default uuid_generate_v4() primary key,
+    slug varchar not null,
+    created_at timestamptz not null default now(),
+    constraint tenants_unique_slug unique(slug)
+);
+
+-- Domains created before tenants existed belong to the default tenant
+insert into tenants(slug) values('default');
+
+alter table domains add column tenant_id uuid null;
+update domains set tenant_id = (select id from tenants where slug = 'default');
+alter table domains alter column tenant_id set not null;
+alter table domains add constraint domains_fk_tenants foreign key(tenant_id) references tenants(id);
+
+-- Slugs are only unique within a tenant
+alter table domains drop constraint domains_unique_slug;
+alter table domains add constraint domains_unique_slug unique(tenant_id, slug);
+
+-- A domain can only inherit from a domain of its own tenant
+alter table domains add constraint domains_unique_tenant_id unique(tenant_id, id);
+alter table domains drop constraint domains_fk_parent;
+alter table domains add constraint domains_fk_parent foreign key(tenant_id, parent_id) references domains(tenant_id, id);
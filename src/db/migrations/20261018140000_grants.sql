@@ -0,0 +1,16 @@
+create type role as enum ('reader', 'writer', 'admin');
+
+-- Roles granted to a principal over a whole tenant, or over a single domain when it is set.
+-- Roles are ordered, each one allowing everything the previous ones do
+create table grants (
+    id uuid default uuid_generate_v4() primary key,
+    tenant_id uuid not null,
+    domain_id uuid null,
+    principal varchar not null,
+    role role not null,
+    created_at timestamptz not null default now(),
+    constraint grants_fk_tenants foreign key(tenant_id) references tenants(id) on delete cascade,
+    constraint grants_fk_domains foreign key(tenant_id, domain_id) references domains(tenant_id, id) on delete cascade
+);
+create unique index grants_unique_tenant_principal on grants(tenant_id, principal) where domain_id is null;
+create unique index grants_unique_domain_principal on grants(domain_id, principal) where domain_id is not null;
@@ -0,0 +1,17 @@
+-- Keys that machine clients authenticate with, acting on the tenant they belong to.
+-- Only a hash of the secret is stored, while the prefix identifies the key
+create table api_keys (
+    id uuid default uuid_generate_v4() primary key,
+    tenant_id uuid not null,
+    name varchar not null,
+    prefix varchar not null,
+    secret_hash varchar not null,
+    scopes role[] not null,
+    expires_at timestamptz null,
+    revoked_at timestamptz null,
+    created_at timestamptz not null default now(),
+    constraint api_keys_fk_tenants foreign key(tenant_id) references tenants(id) on delete cascade,
+    constraint api_keys_unique_prefix unique(prefix)
+);
+-- Names can be reused once the key holding them is revoked
+create unique index api_keys_unique_name on api_keys(tenant_id, name) where revoked_at is null;
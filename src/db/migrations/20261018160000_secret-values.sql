@@ -0,0 +1,3 @@
+-- Secret values are stored as a json string holding the encrypted envelope,
+-- i.e. the base64 of the nonce followed by the ciphertext
+alter type value_type add value 'secret';
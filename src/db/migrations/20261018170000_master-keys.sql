@@ -0,0 +1,20 @@
+-- Id of the master key a secret value is encrypted with, null for values stored in the clear.
+-- Secrets stored so far were encrypted with the single master key, now known as `default`
+alter table versions add column key_id varchar null;
+update versions set key_id = 'default' where type = 'secret';
+
+create type reencryption_status as enum ('running', 'completed', 'failed');
+
+-- Jobs re-encrypting every secret value under the active master key, one batch at a time.
+-- At most one job runs at once
+create table reencryptions (
+    id uuid default uuid_generate_v4() primary key,
+    key_id varchar not null,
+    status reencryption_status not null default 'running',
+    total integer not null,
+    processed integer not null default 0,
+    created_at timestamptz not null default now(),
+    updated_at timestamptz not null default now(),
+    finished_at timestamptz null
+);
+create unique index reencryptions_unique_running on reencryptions((true)) where status = 'running';
@@ -0,0 +1,29 @@
+create type audit_action as enum ('domain_created', 'domain_deleted', 'config_created', 'config_deleted', 'version_created');
+
+-- Append-only trail of the changes made to domains, configs and versions. Targets are kept by
+-- slug and key rather than referenced, so that events outlive what they refer to
+create table audit_events (
+    id uuid default uuid_generate_v4() primary key,
+    tenant_id uuid not null,
+    actor varchar not null,
+    action audit_action not null,
+    domain varchar not null,
+    key varchar null,
+    environment varchar null,
+    before_hash varchar null,
+    after_hash varchar null,
+    request_id varchar null,
+    -- Unlike now(), tells apart the events recorded within the same transaction
+    created_at timestamptz not null default clock_timestamp(),
+    constraint audit_events_fk_tenants foreign key(tenant_id) references tenants(id)
+);
+create index audit_events_tenant_created_at on audit_events(tenant_id, created_at);
+
+create function reject_audit_event_changes() returns trigger as $$
+begin
+    raise exception 'audit events cannot be changed or removed';
+end;
+$$ language plpgsql;
+
+create trigger audit_events_append_only before update or delete on audit_events
+    for each row execute function reject_audit_event_changes();
@@ -0,0 +1,3 @@
+-- Who created each version and why. Versions created before are left without an author
+alter table versions add column author varchar null;
+alter table versions add column message text null;
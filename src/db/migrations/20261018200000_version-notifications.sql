@@ -0,0 +1,11 @@
+-- Notify listeners of every new version, with the key of its config as payload.
+-- Notifications are only delivered once the transaction inserting the version commits
+create function notify_version_created() returns trigger as $$
+begin
+    perform pg_notify('version_created', (select key from configs where id = new.config_id));
+    return new;
+end;
+$$ language plpgsql;
+
+create trigger versions_notify_created after insert on versions
+    for each row execute function notify_version_created();
@@ -0,0 +1,53 @@
+create type domain_event_kind as enum ('config_created', 'config_deleted', 'version_created');
+
+-- Changes to the configs of each domain, for clients to follow as a stream they can resume.
+-- Events of a domain are appended one transaction at a time, so that they are committed in
+-- the order of their ids and a client that saw an event never misses the ones before it
+create table domain_events (
+    id bigserial primary key,
+    domain_id uuid not null,
+    kind domain_event_kind not null,
+    key varchar(512) not null,
+    -- Version created, which is no longer linked once its config is deleted
+    version_id uuid null,
+    version int null,
+    environment varchar(512) null,
+    created_at timestamptz not null default now(),
+    constraint domain_events_fk_domains foreign key(domain_id) references domains(id) on delete cascade,
+    constraint domain_events_fk_versions foreign key(version_id) references versions(id) on delete set null
+);
+create index domain_events_domain_id on domain_events(domain_id, id);
+
+create function append_domain_event(domain_id uuid, kind domain_event_kind, key varchar, version_id uuid, version int, environment varchar) returns void as $$
+begin
+    perform pg_advisory_xact_lock(hashtext('domain_events:' || domain_id::text));
+    insert into domain_events(domain_id, kind, key, version_id, version, environment)
+        values (domain_id, kind, key, version_id, version, environment);
+    perform pg_notify('domain_event', domain_id::text);
+end;
+$$ language plpgsql;
+
+create function record_config_event() returns trigger as $$
+begin
+    if tg_op = 'INSERT' then
+        perform append_domain_event(new.domain_id, 'config_created', new.key, null, null, null);
+    else
+        perform append_domain_event(old.domain_id, 'config_deleted', old.key, null, null, null);
+    end if;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger configs_record_event after insert or delete on configs
+    for each row execute function record_config_event();
+
+create function record_version_event() returns trigger as $$
+begin
+    perform append_domain_event(c.domain_id, 'version_created', c.key, new.id, new.version, e.slug)
+    from configs c left join environments e on e.id = new.environment_id where c.id = new.config_id;
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger versions_record_event after insert on versions
+    for each row execute function record_version_event();
@@ -0,0 +1,46 @@
+-- Endpoints notified of the changes to the configs of a domain, filtered by kind of change and
+-- optionally by key. Payloads are signed with the secret shared with the endpoint
+create table webhooks (
+    id uuid default uuid_generate_v4() primary key,
+    domain_id uuid not null,
+    url varchar not null,
+    events domain_event_kind[] not null,
+    keys varchar[] null,
+    secret varchar not null,
+    created_at timestamptz not null default now(),
+    constraint webhooks_fk_domains foreign key(domain_id) references domains(id) on delete cascade
+);
+
+create type webhook_delivery_status as enum ('pending', 'delivered', 'failed');
+
+-- Outbox of the events to deliver to each webhook, enqueued within the transaction making the
+-- change so that none is lost, and log of how their delivery went
+create table webhook_deliveries (
+    id uuid default uuid_generate_v4() primary key,
+    webhook_id uuid not null,
+    event_id bigint not null,
+    status webhook_delivery_status not null default 'pending',
+    attempts integer not null default 0,
+    next_attempt_at timestamptz not null default now(),
+    -- Outcome of the latest attempt
+    response_status integer null,
+    error text null,
+    created_at timestamptz not null default now(),
+    delivered_at timestamptz null,
+    constraint webhook_deliveries_fk_webhooks foreign key(webhook_id) references webhooks(id) on delete cascade,
+    constraint webhook_deliveries_fk_domain_events foreign key(event_id) references domain_events(id) on delete cascade
+);
+create index webhook_deliveries_pending on webhook_deliveries(next_attempt_at) where status = 'pending';
+create index webhook_deliveries_webhook_id on webhook_deliveries(webhook_id, created_at);
+
+create function enqueue_webhook_deliveries() returns trigger as $$
+begin
+    insert into webhook_deliveries(webhook_id, event_id)
+    select w.id, new.id from webhooks w
+    where w.domain_id = new.domain_id and new.kind = any(w.events) and (w.keys is null or new.key = any(w.keys));
+    return null;
+end;
+$$ language plpgsql;
+
+create trigger domain_events_enqueue_webhook_deliveries after insert on domain_events
+    for each row execute function enqueue_webhook_deliveries();
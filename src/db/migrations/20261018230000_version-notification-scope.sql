@@ -0,0 +1,15 @@
+-- Notify listeners of every new version with its tenant, domain, environment and key as a JSON
+-- payload, so that they can tell whether it is one they are waiting for
+create or replace function notify_version_created() returns trigger as $$
+begin
+    perform pg_notify('version_created', json_build_object(
+        'tenant', t.slug,
+        'domain_id', c.domain_id,
+        'environment_id', new.environment_id,
+        'key', c.key
+    )::text)
+    from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id
+    where c.id = new.config_id;
+    return new;
+end;
+$$ language plpgsql;
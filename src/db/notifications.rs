@@ -0,0 +1,134 @@
+use rocket::{
+    error, info,
+    serde::{json::from_str, Deserialize},
+    tokio::{
+        self,
+        sync::broadcast::{self, Receiver, Sender},
+        time::sleep,
+    },
+    Orbit, Rocket,
+};
+use rocket_db_pools::Database;
+use sqlx::{postgres::PgListener, Error, PgPool};
+use std::time::Duration;
+
+use super::db::ConfigMonkeyDb;
+
+/// Channel Postgres notifies the configs that got a new version on, as [`VersionCreated`] payloads
+pub const VERSION_CREATED_CHANNEL: &str = "version_created";
+
+/// Channel Postgres notifies the ids of the domains that got a new event on
+pub const DOMAIN_EVENT_CHANNEL: &str = "domain_event";
+
+/// Channels relayed to the subscribers of the notifier
+const CHANNELS: [&str; 2] = [VERSION_CREATED_CHANNEL, DOMAIN_EVENT_CHANNEL];
+
+/// Notifications kept for subscribers that are slow to receive them
+const CAPACITY: usize = 1024;
+
+/// A notification sent by Postgres on one of the [`CHANNELS`]
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
+}
+
+impl Notification {
+    pub fn is(&self, channel: &str, payload: &str) -> bool {
+        self.channel == channel && self.payload == payload
+    }
+
+    /// Config that got a new version, when notified on [`VERSION_CREATED_CHANNEL`]
+    pub fn version_created(&self) -> Option<VersionCreated> {
+        if self.channel != VERSION_CREATED_CHANNEL {
+            return None;
+        }
+        from_str(self.payload.as_str()).ok()
+    }
+}
+
+/// Payload of the notifications sent on [`VERSION_CREATED_CHANNEL`]
+#[derive(Deserialize, Debug)]
+#[serde(crate = "rocket::serde")]
+pub struct VersionCreated {
+    pub tenant: String,
+    pub domain_id: String,
+    pub environment_id: Option<String>,
+    pub key: String,
+}
+
+/// Broadcasts the notifications sent by Postgres. Subscribers are sent `None` when
+/// notifications may have been missed, such as while the listening connection is being
+/// re-established
+#[derive(Clone)]
+pub struct Notifier(Sender<Option<Notification>>);
+
+impl Notifier {
+    pub fn new() -> Notifier {
+        let (sender, _) = broadcast::channel(CAPACITY);
+        Notifier(sender)
+    }
+
+    pub fn subscribe(&self) -> Receiver<Option<Notification>> {
+        self.0.subscribe()
+    }
+
+    fn notify(&self, notification: Option<Notification>) {
+        // Nobody may be listening
+        let _ = self.0.send(notification);
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Notifier::new()
+    }
+}
+
+/// Start listening for notifications on a dedicated connection, for as long as the app runs
+pub async fn listen_for_notifications(rocket: &Rocket<Orbit>) {
+    let (Some(db), Some(notifier)) = (ConfigMonkeyDb::fetch(rocket), rocket.state::<Notifier>())
+    else {
+        return;
+    };
+    let pool: &PgPool = db;
+
+    let mut listener = match PgListener::connect_with(pool).await {
+        Ok(listener) => listener,
+        Err(err) => {
+            error!(
+                "[listen_for_notifications] Error connecting listener: {:?}",
+                err
+            );
+            return;
+        }
+    };
+    if let Err(err) = listener.listen_all(CHANNELS).await {
+        error!("[listen_for_notifications] Error listening: {:?}", err);
+        return;
+    }
+    info!("Listening for notifications");
+    tokio::spawn(relay_notifications(listener, notifier.clone()));
+}
+
+async fn relay_notifications(mut listener: PgListener, notifier: Notifier) {
+    loop {
+        match listener.try_recv().await {
+            Ok(Some(notification)) => notifier.notify(Some(Notification {
+                channel: notification.channel().to_string(),
+                payload: notification.payload().to_string(),
+            })),
+            // The connection was lost, and is re-established on the next call
+            Ok(None) => notifier.notify(None),
+            Err(Error::PoolClosed) => return,
+            Err(err) => {
+                error!(
+                    "[relay_notifications] Error receiving notification: {:?}",
+                    err
+                );
+                notifier.notify(None);
+                sleep(Duration::from_secs(1)).await;
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub mod app;
+pub mod auth;
 pub mod db;
 pub mod models;
 pub mod repos;
@@ -1,15 +1,5 @@
-use rocket::{Build, Config, Rocket};
-
-#[macro_use]
-extern crate rocket;
-
-mod app;
-mod db;
-mod models;
-mod repos;
-mod routes;
-mod services;
-mod shared;
+use configmonkey::app;
+use rocket::{launch, Build, Config, Rocket};
 
 #[launch]
 fn rocket() -> Rocket<Build> {
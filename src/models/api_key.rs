@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+
+use super::principal::Role;
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: String,
+    /// Slug of the tenant the key acts on
+    pub tenant: String,
+    pub name: String,
+    /// Public part of the key, which identifies it
+    pub prefix: String,
+    /// SHA-256 hash of the secret part of the key
+    pub secret_hash: String,
+    /// Roles the key can exercise over the tenant
+    pub scopes: Vec<Role>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
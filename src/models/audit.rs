@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+
+/// Who a change is made on behalf of, along with the request it is made in
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub name: String,
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    DomainCreated,
+    DomainDeleted,
+    ConfigCreated,
+    ConfigDeleted,
+    VersionCreated,
+}
+
+impl AuditAction {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            AuditAction::DomainCreated => "domain_created",
+            AuditAction::DomainDeleted => "domain_deleted",
+            AuditAction::ConfigCreated => "config_created",
+            AuditAction::ConfigDeleted => "config_deleted",
+            AuditAction::VersionCreated => "version_created",
+        }
+    }
+}
+
+/// A change to record in the audit log
+#[derive(Debug)]
+pub struct AuditChange {
+    pub action: AuditAction,
+    pub domain: String,
+    pub key: Option<String>,
+    pub environment: Option<String>,
+    /// SHA-256 of the value before and after the change, for new versions
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+}
+
+impl AuditChange {
+    pub fn domain(action: AuditAction, domain: &str) -> AuditChange {
+        AuditChange {
+            action,
+            domain: domain.to_string(),
+            key: None,
+            environment: None,
+            before_hash: None,
+            after_hash: None,
+        }
+    }
+
+    pub fn config(action: AuditAction, domain: &str, key: &str) -> AuditChange {
+        AuditChange {
+            key: Some(key.to_string()),
+            ..AuditChange::domain(action, domain)
+        }
+    }
+}
+
+/// A change recorded in the audit log
+#[derive(Debug)]
+pub struct AuditEvent {
+    pub id: String,
+    pub actor: String,
+    pub action: AuditAction,
+    pub domain: String,
+    pub key: Option<String>,
+    pub environment: Option<String>,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Criteria audit events are looked up by. Events match all of the criteria that are set
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub domain: Option<String>,
+    pub key: Option<String>,
+    pub actor: Option<String>,
+    /// Only events recorded from then on
+    pub since: Option<DateTime<Utc>>,
+}
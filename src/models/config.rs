@@ -1,13 +1,47 @@
 use std::fmt::{self, Display};
 
 use chrono::{DateTime, Utc};
+use rocket::serde::json::{serde_json::Map, Value};
 
-#[derive(Debug)]
+/// Placeholder shown in place of secret values
+pub const REDACTED: &str = "********";
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ConfigValue {
     String(String),
     Boolean(bool),
     Float(f64),
     Integer(i64),
+    Object(Map<String, Value>),
+    Array(Vec<Value>),
+    Null,
+    /// Encrypted value, redacted wherever the value is shown
+    Secret(SealedValue),
+}
+
+/// A value encrypted under one of the master keys
+#[derive(Debug, Clone, PartialEq)]
+pub struct SealedValue {
+    /// Id of the master key the value is encrypted with
+    pub key_id: String,
+    /// Base64 of the nonce followed by the ciphertext
+    pub envelope: String,
+}
+
+impl ConfigValue {
+    /// Name of the value type, as stored in the database
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ConfigValue::String(_) => "string",
+            ConfigValue::Boolean(_) => "boolean",
+            ConfigValue::Float(_) => "float",
+            ConfigValue::Integer(_) => "integer",
+            ConfigValue::Object(_) => "object",
+            ConfigValue::Array(_) => "array",
+            ConfigValue::Null => "null",
+            ConfigValue::Secret(_) => "secret",
+        }
+    }
 }
 
 impl Display for ConfigValue {
@@ -17,6 +51,46 @@ impl Display for ConfigValue {
             ConfigValue::Boolean(b) => write!(f, "{}", b),
             ConfigValue::Float(n) => write!(f, "{}", n),
             ConfigValue::Integer(n) => write!(f, "{}", n),
+            ConfigValue::Object(o) => write!(f, "{}", Value::Object(o.clone())),
+            ConfigValue::Array(a) => write!(f, "{}", Value::Array(a.clone())),
+            ConfigValue::Null => write!(f, "null"),
+            ConfigValue::Secret(_) => write!(f, "{}", REDACTED),
+        }
+    }
+}
+
+impl From<ConfigValue> for Value {
+    fn from(config_value: ConfigValue) -> Value {
+        match config_value {
+            ConfigValue::String(v) => Value::from(v),
+            ConfigValue::Boolean(v) => Value::from(v),
+            ConfigValue::Float(v) => Value::from(v),
+            ConfigValue::Integer(v) => Value::from(v),
+            ConfigValue::Object(v) => Value::Object(v),
+            ConfigValue::Array(v) => Value::Array(v),
+            ConfigValue::Null => Value::Null,
+            ConfigValue::Secret(_) => Value::from(REDACTED),
+        }
+    }
+}
+
+impl From<Value> for ConfigValue {
+    fn from(value: Value) -> ConfigValue {
+        match value {
+            Value::Number(v) => {
+                if v.is_f64() {
+                    ConfigValue::Float(v.as_f64().unwrap())
+                } else if v.is_i64() {
+                    ConfigValue::Integer(v.as_i64().unwrap())
+                } else {
+                    ConfigValue::Integer(v.as_u64().unwrap() as i64)
+                }
+            }
+            Value::String(v) => ConfigValue::String(v),
+            Value::Bool(v) => ConfigValue::Boolean(v),
+            Value::Object(v) => ConfigValue::Object(v),
+            Value::Array(v) => ConfigValue::Array(v),
+            Value::Null => ConfigValue::Null,
         }
     }
 }
@@ -26,9 +100,47 @@ pub struct ConfigVersion {
     pub id: String,
     pub version: i32,
     pub value: ConfigValue,
+    pub restored_from: Option<i32>,
+    /// Actor the version was created by, unknown for versions predating authorship
+    pub author: Option<String>,
+    /// Why the version was created, as told by its author
+    pub message: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+/// A config version alongside the key of the config it belongs to
+#[derive(Debug)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub version: ConfigVersion,
+}
+
+/// Differences between two config values
+#[derive(Debug)]
+pub struct ValueDiff {
+    /// Previous and new type names, when the type changed
+    pub type_change: Option<(&'static str, &'static str)>,
+    /// Previous and new values, when at least one of them is a scalar and they differ
+    pub value_change: Option<(ConfigValue, ConfigValue)>,
+    /// JSON Patch (RFC 6902) operations, when both values are objects or arrays
+    pub patch: Option<Vec<Value>>,
+}
+
+/// A config version along with the domain holding it, which can be an ancestor of the domain
+/// the version was looked up in
+#[derive(Debug)]
+pub struct ResolvedVersion {
+    pub domain: String,
+    pub version: ConfigVersion,
+}
+
+/// A new version, along with how it differs from the version it replaces, if any
+#[derive(Debug)]
+pub struct VersionChange {
+    pub version: ConfigVersion,
+    pub diff: Option<ValueDiff>,
+}
+
 #[derive(Debug)]
 pub struct Config {
     pub id: String,
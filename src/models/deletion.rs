@@ -0,0 +1,6 @@
+/// What a deletion removes, including the records that cascade from it
+#[derive(Debug, Default)]
+pub struct DeletionSummary {
+    pub configs: i64,
+    pub versions: i64,
+}
@@ -2,5 +2,7 @@ use chrono::{DateTime, Utc};
 pub struct Domain {
     pub id: String,
     pub slug: String,
+    /// Slug of the domain this one inherits configs from
+    pub parent: Option<String>,
     pub created_at: DateTime<Utc>,
 }
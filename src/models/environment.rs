@@ -0,0 +1,8 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Debug)]
+pub struct Environment {
+    pub id: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
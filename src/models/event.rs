@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+
+use super::config::ConfigValue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DomainEventKind {
+    ConfigCreated,
+    ConfigDeleted,
+    VersionCreated,
+}
+
+impl DomainEventKind {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            DomainEventKind::ConfigCreated => "config_created",
+            DomainEventKind::ConfigDeleted => "config_deleted",
+            DomainEventKind::VersionCreated => "version_created",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<DomainEventKind> {
+        match name {
+            "config_created" => Some(DomainEventKind::ConfigCreated),
+            "config_deleted" => Some(DomainEventKind::ConfigDeleted),
+            "version_created" => Some(DomainEventKind::VersionCreated),
+            _ => None,
+        }
+    }
+}
+
+/// A change to the configs of a domain, as streamed to the clients following it
+#[derive(Debug)]
+pub struct DomainEvent {
+    /// Position of the event, increasing with every change
+    pub id: i64,
+    pub kind: DomainEventKind,
+    pub key: String,
+    /// Environment and number of the created versions
+    pub environment: Option<String>,
+    pub version: Option<i32>,
+    /// Value of the created versions, until their config is deleted
+    pub value: Option<ConfigValue>,
+    pub created_at: DateTime<Utc>,
+}
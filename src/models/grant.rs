@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+
+use super::principal::Role;
+
+#[derive(Debug)]
+pub struct Grant {
+    pub id: String,
+    pub principal: String,
+    pub role: Role,
+    /// Slug of the domain the role is granted over, or none when granted over the whole tenant
+    pub domain: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
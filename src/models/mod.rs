@@ -1,3 +1,13 @@
+pub mod api_key;
+pub mod audit;
 pub mod config;
+pub mod deletion;
 pub mod domain;
+pub mod environment;
+pub mod event;
+pub mod grant;
 pub mod list;
+pub mod principal;
+pub mod reencryption;
+pub mod tenant;
+pub mod webhook;
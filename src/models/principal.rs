@@ -0,0 +1,42 @@
+/// Roles that can be granted to a principal, from the least to the most privileged.
+/// Each role allows everything the previous ones do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Reader,
+    Writer,
+    Admin,
+}
+
+impl Role {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Role::Reader => "reader",
+            Role::Writer => "writer",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Role> {
+        match name {
+            "reader" => Some(Role::Reader),
+            "writer" => Some(Role::Writer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Identity a request acts as
+#[derive(Debug, Clone)]
+pub enum Principal {
+    /// Allowed to do anything, either because access control is disabled or because the
+    /// principal is one of the configured administrators
+    Unrestricted,
+    /// Only allowed to do what the roles granted to it permit
+    Named(String),
+    /// Machine client authenticated with an API key, allowed to exercise up to the given role
+    /// over the tenant of the key
+    ApiKey { name: String, scope: Role },
+    /// Not allowed to do anything
+    Anonymous,
+}
@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReencryptionStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl ReencryptionStatus {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ReencryptionStatus::Running => "running",
+            ReencryptionStatus::Completed => "completed",
+            ReencryptionStatus::Failed => "failed",
+        }
+    }
+}
+
+/// A job re-encrypting the stored values under a master key
+#[derive(Debug)]
+pub struct Reencryption {
+    pub id: String,
+    /// Id of the master key the values are re-encrypted with
+    pub key_id: String,
+    pub status: ReencryptionStatus,
+    /// Number of values that were not encrypted with the key when the job started
+    pub total: i32,
+    /// Number of values re-encrypted so far
+    pub processed: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
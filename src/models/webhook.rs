@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+
+use super::event::{DomainEvent, DomainEventKind};
+
+/// An endpoint notified of the changes to the configs of a domain
+#[derive(Debug)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Kinds of changes the endpoint is notified of
+    pub events: Vec<DomainEventKind>,
+    /// Keys of the configs the endpoint is notified of, or all of them
+    pub keys: Option<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl WebhookDeliveryStatus {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            WebhookDeliveryStatus::Pending => "pending",
+            WebhookDeliveryStatus::Delivered => "delivered",
+            WebhookDeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+/// An event to deliver to a webhook, along with how its delivery went so far
+#[derive(Debug)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub event_id: i64,
+    pub kind: DomainEventKind,
+    pub key: String,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    /// When the event is to be delivered again, while it is pending
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    /// HTTP status the endpoint answered the latest attempt with, if it answered
+    pub response_status: Option<i32>,
+    /// Why the latest attempt failed
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+/// A delivery claimed for an attempt, with everything needed to make it
+#[derive(Debug)]
+pub struct DueDelivery {
+    pub id: String,
+    /// Attempts made so far, this one included
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+    pub domain: String,
+    pub event: DomainEvent,
+}
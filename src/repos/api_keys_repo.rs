@@ -0,0 +1,176 @@
+use std::borrow::Cow;
+
+use crate::models::{api_key::ApiKey, principal::Role};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum ApiKeysRepoError {
+    AlreadyExists,
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct ApiKeyEntity {
+    pub id: Uuid,
+    pub tenant: String,
+    pub name: String,
+    pub prefix: String,
+    pub secret_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn map_sqlx_error(error: Error) -> ApiKeysRepoError {
+    match error {
+        Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => ApiKeysRepoError::AlreadyExists,
+            _ => ApiKeysRepoError::Unknown,
+        },
+        Error::RowNotFound => ApiKeysRepoError::NotFound,
+        _ => ApiKeysRepoError::Unknown,
+    }
+}
+
+fn to_api_key(api_key: ApiKeyEntity) -> ApiKey {
+    ApiKey {
+        id: api_key.id.to_string(),
+        tenant: api_key.tenant,
+        name: api_key.name,
+        prefix: api_key.prefix,
+        secret_hash: api_key.secret_hash,
+        scopes: api_key
+            .scopes
+            .iter()
+            .filter_map(|scope| Role::from_name(scope))
+            .collect(),
+        expires_at: api_key.expires_at,
+        revoked_at: api_key.revoked_at,
+        created_at: api_key.created_at,
+    }
+}
+
+/// Store a new key of a tenant. Only the hash of its secret is kept
+pub async fn create_api_key(
+    db: &mut PgConnection,
+    tenant: &str,
+    name: &str,
+    prefix: &str,
+    secret_hash: &str,
+    scopes: &[Role],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<ApiKey, ApiKeysRepoError> {
+    let scopes: Vec<&str> = scopes.iter().map(|scope| scope.name()).collect();
+    let create_api_key_result = sqlx::query_as::<_, ApiKeyEntity>(
+        "with api_key as (insert into api_keys(tenant_id, name, prefix, secret_hash, scopes, expires_at) select t.id, $2, $3, $4, $5::role[], $6 from tenants t where t.slug = $1 \
+        returning id, name, prefix, secret_hash, scopes, expires_at, revoked_at, created_at) \
+        select k.id, $1 as tenant, k.name, k.prefix, k.secret_hash, k.scopes::varchar[] as scopes, k.expires_at, k.revoked_at, k.created_at from api_key k",
+    )
+    .bind(tenant)
+    .bind(name)
+    .bind(prefix)
+    .bind(secret_hash)
+    .bind(scopes)
+    .bind(expires_at)
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_api_key_result {
+        Ok(api_key) => Ok(to_api_key(api_key)),
+        Err(err) => {
+            error!("[create_api_key] Error creating api key: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the keys of a tenant, revoked ones included
+pub async fn get_api_keys(
+    db: &mut PgConnection,
+    tenant: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<ApiKey>, ApiKeysRepoError> {
+    let get_api_keys_result = sqlx::query_as::<_, ApiKeyEntity>(
+        "select k.id, t.slug as tenant, k.name, k.prefix, k.secret_hash, k.scopes::varchar[] as scopes, k.expires_at, k.revoked_at, k.created_at from api_keys k join tenants t on t.id = k.tenant_id \
+        where t.slug = $1 order by k.name, k.created_at limit $2 offset $3",
+    )
+    .bind(tenant)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_api_keys_result {
+        Ok(api_keys) => Ok(api_keys.into_iter().map(to_api_key).collect()),
+        Err(err) => {
+            error!("[get_api_keys] Error retrieving api keys: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve a key of any tenant by its prefix
+pub async fn get_api_key_by_prefix(
+    db: &mut PgConnection,
+    prefix: &str,
+) -> Result<ApiKey, ApiKeysRepoError> {
+    let get_api_key_result = sqlx::query_as::<_, ApiKeyEntity>(
+        "select k.id, t.slug as tenant, k.name, k.prefix, k.secret_hash, k.scopes::varchar[] as scopes, k.expires_at, k.revoked_at, k.created_at from api_keys k join tenants t on t.id = k.tenant_id \
+        where k.prefix = $1",
+    )
+    .bind(prefix)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_api_key_result {
+        Ok(api_key) => Ok(to_api_key(api_key)),
+        Err(Error::RowNotFound) => Err(ApiKeysRepoError::NotFound),
+        Err(err) => {
+            error!(
+                "[get_api_key_by_prefix] Error retrieving api key: {:?}",
+                err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Revoke the active key of a tenant with the given name. Revoked keys are kept, but can no
+/// longer be used
+pub async fn revoke_api_key(
+    db: &mut PgConnection,
+    tenant: &str,
+    name: &str,
+) -> Result<(), ApiKeysRepoError> {
+    let result = sqlx::query(
+        "update api_keys k set revoked_at = now() from tenants t where t.id = k.tenant_id and t.slug = $1 and k.name = $2 and k.revoked_at is null",
+    )
+    .bind(tenant)
+    .bind(name)
+    .execute(&mut *db)
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                error!("Api key {} not found", name);
+                return Err(ApiKeysRepoError::NotFound);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            error!(
+                "[revoke_api_key] Error revoking api key {}: {:?}",
+                name, err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
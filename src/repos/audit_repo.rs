@@ -0,0 +1,141 @@
+use crate::models::audit::{Actor, AuditAction, AuditChange, AuditEvent, AuditFilter};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum AuditRepoError {
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct AuditEventEntity {
+    pub id: Uuid,
+    pub actor: String,
+    pub action: AuditActionEntity,
+    pub domain: String,
+    pub key: Option<String>,
+    pub environment: Option<String>,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::Type, Debug)]
+#[sqlx(type_name = "audit_action", rename_all = "snake_case")]
+enum AuditActionEntity {
+    DomainCreated,
+    DomainDeleted,
+    ConfigCreated,
+    ConfigDeleted,
+    VersionCreated,
+}
+
+fn map_sqlx_error(error: Error) -> AuditRepoError {
+    match error {
+        Error::RowNotFound => AuditRepoError::NotFound,
+        _ => AuditRepoError::Unknown,
+    }
+}
+
+fn to_action_entity(action: AuditAction) -> AuditActionEntity {
+    match action {
+        AuditAction::DomainCreated => AuditActionEntity::DomainCreated,
+        AuditAction::DomainDeleted => AuditActionEntity::DomainDeleted,
+        AuditAction::ConfigCreated => AuditActionEntity::ConfigCreated,
+        AuditAction::ConfigDeleted => AuditActionEntity::ConfigDeleted,
+        AuditAction::VersionCreated => AuditActionEntity::VersionCreated,
+    }
+}
+
+fn to_audit_event(event: AuditEventEntity) -> AuditEvent {
+    AuditEvent {
+        id: event.id.to_string(),
+        actor: event.actor,
+        action: match event.action {
+            AuditActionEntity::DomainCreated => AuditAction::DomainCreated,
+            AuditActionEntity::DomainDeleted => AuditAction::DomainDeleted,
+            AuditActionEntity::ConfigCreated => AuditAction::ConfigCreated,
+            AuditActionEntity::ConfigDeleted => AuditAction::ConfigDeleted,
+            AuditActionEntity::VersionCreated => AuditAction::VersionCreated,
+        },
+        domain: event.domain,
+        key: event.key,
+        environment: event.environment,
+        before_hash: event.before_hash,
+        after_hash: event.after_hash,
+        request_id: event.request_id,
+        created_at: event.created_at,
+    }
+}
+
+/// Append a change made by an actor to the audit log of a tenant
+pub async fn create_audit_event(
+    db: &mut PgConnection,
+    tenant: &str,
+    actor: &Actor,
+    change: AuditChange,
+) -> Result<(), AuditRepoError> {
+    let create_result = sqlx::query(
+        "insert into audit_events(tenant_id, actor, action, domain, key, environment, before_hash, after_hash, request_id) \
+        select t.id, $2, $3, $4, $5, $6, $7, $8, $9 from tenants t where t.slug = $1",
+    )
+    .bind(tenant)
+    .bind(actor.name.as_str())
+    .bind(to_action_entity(change.action))
+    .bind(change.domain)
+    .bind(change.key)
+    .bind(change.environment)
+    .bind(change.before_hash)
+    .bind(change.after_hash)
+    .bind(actor.request_id.as_deref())
+    .execute(&mut *db)
+    .await;
+
+    match create_result {
+        // Nothing is inserted when the tenant does not exist
+        Ok(result) if result.rows_affected() == 0 => Err(AuditRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[create_audit_event] Error recording event: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the events of a tenant matching the filter, from the oldest to the most recent
+pub async fn get_audit_events(
+    db: &mut PgConnection,
+    tenant: &str,
+    filter: &AuditFilter,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<AuditEvent>, AuditRepoError> {
+    let get_result = sqlx::query_as::<_, AuditEventEntity>(
+        "select e.id, e.actor, e.action, e.domain, e.key, e.environment, e.before_hash, e.after_hash, e.request_id, e.created_at \
+        from audit_events e join tenants t on t.id = e.tenant_id \
+        where t.slug = $1 and ($2::varchar is null or e.domain = $2) and ($3::varchar is null or e.key = $3) \
+        and ($4::varchar is null or e.actor = $4) and ($5::timestamptz is null or e.created_at >= $5) \
+        order by e.created_at, e.id limit $6 offset $7",
+    )
+    .bind(tenant)
+    .bind(filter.domain.as_deref())
+    .bind(filter.key.as_deref())
+    .bind(filter.actor.as_deref())
+    .bind(filter.since)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(events) => Ok(events.into_iter().map(to_audit_event).collect()),
+        Err(err) => {
+            error!("[get_audit_events] Error retrieving events: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
@@ -4,7 +4,7 @@ use crate::models::config::Config;
 use chrono::{DateTime, Utc};
 use rocket::error;
 use rocket_db_pools::sqlx::{self};
-use sqlx::{pool::PoolConnection, types::Uuid, Error, Postgres};
+use sqlx::{types::Uuid, Error, PgConnection};
 
 #[derive(Debug)]
 pub enum ConfigsRepoError {
@@ -33,15 +33,17 @@ fn map_sqlx_error(error: Error) -> ConfigsRepoError {
 }
 
 pub async fn create_config(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    tenant: &str,
     domain_id: &str,
     key: &str,
 ) -> Result<Config, ConfigsRepoError> {
     let create_config_result = sqlx::query_as::<_, ConfigEntity>(
         "insert into configs(domain_id, key) \
-                        values($1::uuid, $2) \
+                        select d.id, $3 from domains d join tenants t on t.id = d.tenant_id where t.slug = $1 and d.id = $2::uuid \
                         returning id, key, created_at",
     )
+    .bind(tenant)
     .bind(domain_id)
     .bind(key)
     .fetch_one(&mut *db)
@@ -61,14 +63,17 @@ pub async fn create_config(
 }
 
 pub async fn get_configs(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    tenant: &str,
     domain_id: &str,
     limit: i32,
     offset: i32,
 ) -> Result<Vec<Config>, ConfigsRepoError> {
     let get_configs_result = sqlx::query_as::<_, ConfigEntity>(
-        "select id, key, created_at from configs where domain_id = $1::uuid limit $2 offset $3",
+        "select c.id, c.key, c.created_at from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id \
+        where t.slug = $1 and c.domain_id = $2::uuid limit $3 offset $4",
     )
+    .bind(tenant)
     .bind(domain_id)
     .bind(limit)
     .bind(offset)
@@ -95,13 +100,16 @@ pub async fn get_configs(
 }
 
 pub async fn get_config(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    tenant: &str,
     domain_id: &str,
     key: &str,
 ) -> Result<Config, ConfigsRepoError> {
     let get_config_result = sqlx::query_as::<_, ConfigEntity>(
-        "select id, key, created_at from configs where domain_id = $1::uuid and key = $2",
+        "select c.id, c.key, c.created_at from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id \
+        where t.slug = $1 and c.domain_id = $2::uuid and c.key = $3",
     )
+    .bind(tenant)
     .bind(domain_id)
     .bind(key)
     .fetch_one(&mut *db)
@@ -121,13 +129,17 @@ pub async fn get_config(
 }
 
 pub async fn delete_config(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    tenant: &str,
     config_id: &str,
 ) -> Result<(), ConfigsRepoError> {
-    let result = sqlx::query("delete from configs where id = $1::uuid")
-        .bind(config_id)
-        .execute(&mut *db)
-        .await;
+    let result = sqlx::query(
+        "delete from configs c using domains d, tenants t where d.id = c.domain_id and t.id = d.tenant_id and t.slug = $1 and c.id = $2::uuid",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .execute(&mut *db)
+    .await;
 
     match result {
         Ok(result) => {
@@ -5,7 +5,7 @@ use rocket_db_pools::{
     sqlx::{self, types::Uuid},
     Connection,
 };
-use sqlx::{Error, Postgres, pool::PoolConnection};
+use sqlx::{Error, PgConnection};
 use std::borrow::Cow;
 
 #[derive(Debug)]
@@ -14,12 +14,14 @@ pub enum DomainsRepoError {
     DuplicateSlug,
     NotFound,
     NotEmpty,
+    HasChildren,
 }
 
 #[derive(sqlx::FromRow, Debug)]
 struct DomainEntity {
     pub id: Uuid,
     pub slug: String,
+    pub parent: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -28,6 +30,9 @@ fn map_sqlx_error(error: Error) -> DomainsRepoError {
         Error::Database(err) => match err.code() {
             // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
             Some(Cow::Borrowed("23505")) => DomainsRepoError::DuplicateSlug,
+            Some(Cow::Borrowed("23503")) if err.constraint() == Some("domains_fk_parent") => {
+                DomainsRepoError::HasChildren
+            }
             Some(Cow::Borrowed("23503")) => DomainsRepoError::NotEmpty,
             _ => DomainsRepoError::Unknown,
         },
@@ -36,26 +41,36 @@ fn map_sqlx_error(error: Error) -> DomainsRepoError {
     }
 }
 
-/// Create a new domain
+fn to_domain(domain: DomainEntity) -> Domain {
+    Domain {
+        id: domain.id.to_string(),
+        slug: domain.slug,
+        parent: domain.parent,
+        created_at: domain.created_at,
+    }
+}
+
+/// Create a new domain in a tenant, optionally inheriting from a parent domain of the same tenant
 pub async fn create_domain(
-    mut db: Connection<ConfigMonkeyDb>,
+    db: &mut PgConnection,
+    tenant: &str,
     slug: &str,
+    parent_id: Option<&str>,
 ) -> Result<Domain, DomainsRepoError> {
     let result = sqlx::query_as::<_, DomainEntity>(
-        "insert into domains(slug) values($1) returning id, slug, created_at",
+        "with domain as (insert into domains(tenant_id, slug, parent_id) select t.id, $2, $3::uuid from tenants t where t.slug = $1 returning id, slug, parent_id, created_at) \
+        select d.id, d.slug, p.slug as parent, d.created_at from domain d left join domains p on p.id = d.parent_id",
     )
+    .bind(tenant)
     .bind(slug)
-    .fetch_one(&mut *db)
+    .bind(parent_id)
+    .fetch_one(db)
     .await;
 
     match result {
         Ok(domain) => {
             debug!("Successfully created domain: {:?}", domain);
-            Ok(Domain {
-                id: domain.id.to_string(),
-                slug: domain.slug,
-                created_at: domain.created_at,
-            })
+            Ok(to_domain(domain))
         }
         Err(err) => {
             error!(
@@ -67,15 +82,18 @@ pub async fn create_domain(
     }
 }
 
-/// Retrieve the list of domains
+/// Retrieve the list of domains of a tenant
 pub async fn get_domains(
     mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
     limit: i32,
     offset: i32,
 ) -> Result<Vec<Domain>, DomainsRepoError> {
     let domains_result = sqlx::query_as::<_, DomainEntity>(
-        "select id, slug, created_at from domains limit $1 offset $2",
+        "select d.id, d.slug, p.slug as parent, d.created_at from domains d join tenants t on t.id = d.tenant_id left join domains p on p.id = d.parent_id \
+        where t.slug = $1 limit $2 offset $3",
     )
+    .bind(tenant)
     .bind(limit)
     .bind(offset)
     .fetch_all(&mut *db)
@@ -86,11 +104,7 @@ pub async fn get_domains(
             debug!("Successfully retrieved domains: {:?}", domains);
             let mut result = vec![];
             for domain in domains {
-                result.push(Domain {
-                    id: domain.id.to_string(),
-                    slug: domain.slug,
-                    created_at: domain.created_at,
-                })
+                result.push(to_domain(domain))
             }
             Ok(result)
         }
@@ -103,23 +117,22 @@ pub async fn get_domains(
 
 /// Retrieve domain by slug
 pub async fn get_domain_by_slug(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    tenant: &str,
     domain_slug: &str,
 ) -> Result<Domain, DomainsRepoError> {
     let domain_result = sqlx::query_as::<_, DomainEntity>(
-        "select id, slug, created_at from domains where slug = $1",
+        "select d.id, d.slug, p.slug as parent, d.created_at from domains d join tenants t on t.id = d.tenant_id left join domains p on p.id = d.parent_id \
+        where t.slug = $1 and d.slug = $2",
     )
+    .bind(tenant)
     .bind(domain_slug)
-    .fetch_one( db)
+    .fetch_one(db)
     .await;
     match domain_result {
         Ok(domain) => {
             debug!("Successfully retrieved domain: {:?}", domain);
-            Ok(Domain {
-                id: domain.id.to_string(),
-                slug: domain.slug,
-                created_at: domain.created_at,
-            })
+            Ok(to_domain(domain))
         }
         Err(err) => {
             error!("Error retrieving domain. Error: {:?}", err);
@@ -128,14 +141,105 @@ pub async fn get_domain_by_slug(
     }
 }
 
+/// Ancestors followed when resolving a chain of domains. Cycles are rejected when parents are
+/// set, this only bounds the recursion should one ever be stored
+const MAX_CHAIN_DEPTH: i32 = 64;
+
+/// Retrieve a domain followed by its ancestors, from its parent up to the root domain
+pub async fn get_domain_chain(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_slug: &str,
+) -> Result<Vec<Domain>, DomainsRepoError> {
+    let chain_result = sqlx::query_as::<_, DomainEntity>(
+        "with recursive chain as ( \
+            select d.id, d.tenant_id, d.slug, d.parent_id, d.created_at, 0 as depth from domains d join tenants t on t.id = d.tenant_id where t.slug = $1 and d.slug = $2 \
+            union all \
+            select d.id, d.tenant_id, d.slug, d.parent_id, d.created_at, c.depth + 1 from domains d join chain c on d.id = c.parent_id and d.tenant_id = c.tenant_id where c.depth < $3 \
+        ) \
+        select c.id, c.slug, p.slug as parent, c.created_at from chain c left join domains p on p.id = c.parent_id order by c.depth",
+    )
+    .bind(tenant)
+    .bind(domain_slug)
+    .bind(MAX_CHAIN_DEPTH)
+    .fetch_all(db)
+    .await;
+
+    match chain_result {
+        Ok(domains) => Ok(domains.into_iter().map(to_domain).collect()),
+        Err(err) => {
+            error!("Error retrieving domain chain. Error: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Serialize changes to the parents of the domains of a tenant until the end of the transaction,
+/// so that each one checks for cycles against the ones committed before it
+pub async fn lock_domain_parents(
+    db: &mut PgConnection,
+    tenant: &str,
+) -> Result<(), DomainsRepoError> {
+    let lock_result = sqlx::query("select t.id from tenants t where t.slug = $1 for no key update")
+        .bind(tenant)
+        .fetch_one(&mut *db)
+        .await;
+
+    match lock_result {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[lock_domain_parents] Error locking tenant: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Set or clear the parent of a domain
+pub async fn set_domain_parent(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: &str,
+    parent_id: Option<&str>,
+) -> Result<(), DomainsRepoError> {
+    let result = sqlx::query(
+        "update domains d set parent_id = $3::uuid from tenants t where t.id = d.tenant_id and t.slug = $1 and d.id = $2::uuid",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(parent_id)
+    .execute(db)
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                error!("Domain {} not found", domain_id);
+                return Err(DomainsRepoError::NotFound);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            error!(
+                "Error setting parent of domain {}. Error: {:?}",
+                domain_id, err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
 pub async fn delete_domain(
-    mut db: Connection<ConfigMonkeyDb>,
+    db: &mut PgConnection,
+    tenant: &str,
     slug: &str,
 ) -> Result<(), DomainsRepoError> {
-    let result = sqlx::query("delete from domains where slug = $1")
-        .bind(slug)
-        .execute(&mut *db)
-        .await;
+    let result = sqlx::query(
+        "delete from domains d using tenants t where t.id = d.tenant_id and t.slug = $1 and d.slug = $2",
+    )
+    .bind(tenant)
+    .bind(slug)
+    .execute(db)
+    .await;
 
     match result {
         Ok(result) => {
@@ -0,0 +1,154 @@
+use std::borrow::Cow;
+
+use crate::models::environment::Environment;
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum EnvironmentsRepoError {
+    AlreadyExists,
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct EnvironmentEntity {
+    pub id: Uuid,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn map_sqlx_error(error: Error) -> EnvironmentsRepoError {
+    match error {
+        Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => EnvironmentsRepoError::AlreadyExists,
+            _ => EnvironmentsRepoError::Unknown,
+        },
+        Error::RowNotFound => EnvironmentsRepoError::NotFound,
+        _ => EnvironmentsRepoError::Unknown,
+    }
+}
+
+fn to_environment(environment: EnvironmentEntity) -> Environment {
+    Environment {
+        id: environment.id.to_string(),
+        slug: environment.slug,
+        created_at: environment.created_at,
+    }
+}
+
+pub async fn create_environment(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: &str,
+    slug: &str,
+) -> Result<Environment, EnvironmentsRepoError> {
+    let create_environment_result = sqlx::query_as::<_, EnvironmentEntity>(
+        "insert into environments(domain_id, slug) \
+        select d.id, $3 from domains d join tenants t on t.id = d.tenant_id where t.slug = $1 and d.id = $2::uuid \
+        returning id, slug, created_at",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(slug)
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_environment_result {
+        Ok(environment) => Ok(to_environment(environment)),
+        Err(err) => {
+            error!("[create_environment] Error creating environment: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn get_environments(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<Environment>, EnvironmentsRepoError> {
+    let get_environments_result = sqlx::query_as::<_, EnvironmentEntity>(
+        "select e.id, e.slug, e.created_at from environments e join domains d on d.id = e.domain_id join tenants t on t.id = d.tenant_id \
+        where t.slug = $1 and e.domain_id = $2::uuid order by e.slug limit $3 offset $4",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_environments_result {
+        Ok(environments) => Ok(environments.into_iter().map(to_environment).collect()),
+        Err(err) => {
+            error!(
+                "[get_environments] Error retrieving environments: {:?}",
+                err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn get_environment(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: &str,
+    slug: &str,
+) -> Result<Environment, EnvironmentsRepoError> {
+    let get_environment_result = sqlx::query_as::<_, EnvironmentEntity>(
+        "select e.id, e.slug, e.created_at from environments e join domains d on d.id = e.domain_id join tenants t on t.id = d.tenant_id \
+        where t.slug = $1 and e.domain_id = $2::uuid and e.slug = $3",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(slug)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_environment_result {
+        Ok(environment) => Ok(to_environment(environment)),
+        Err(err) => {
+            error!("[get_environment] Error retrieving environment: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Delete an environment along with the versions it holds
+pub async fn delete_environment(
+    db: &mut PgConnection,
+    tenant: &str,
+    environment_id: &str,
+) -> Result<(), EnvironmentsRepoError> {
+    let result = sqlx::query(
+        "delete from environments e using domains d, tenants t where d.id = e.domain_id and t.id = d.tenant_id and t.slug = $1 and e.id = $2::uuid",
+    )
+    .bind(tenant)
+    .bind(environment_id)
+    .execute(&mut *db)
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                error!(
+                    "[delete_environment] Environment not found: {}",
+                    environment_id
+                );
+                return Err(EnvironmentsRepoError::NotFound);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            error!("[delete_environment] Error deleting environment: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
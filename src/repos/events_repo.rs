@@ -0,0 +1,133 @@
+use crate::{
+    models::event::{DomainEvent, DomainEventKind},
+    repos::versions_repo::{to_config_value, ValueTypeEntity},
+    shared::secrets::SecretsCipher,
+};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::JsonValue, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum EventsRepoError {
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub(crate) struct DomainEventEntity {
+    pub id: i64,
+    pub kind: DomainEventKindEntity,
+    pub key: String,
+    pub environment: Option<String>,
+    pub version: Option<i32>,
+    pub value: Option<JsonValue>,
+    pub r#type: Option<ValueTypeEntity>,
+    pub key_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::Type, Debug)]
+#[sqlx(type_name = "domain_event_kind", rename_all = "snake_case")]
+pub(crate) enum DomainEventKindEntity {
+    ConfigCreated,
+    ConfigDeleted,
+    VersionCreated,
+}
+
+fn map_sqlx_error(error: Error) -> EventsRepoError {
+    match error {
+        Error::RowNotFound => EventsRepoError::NotFound,
+        _ => EventsRepoError::Unknown,
+    }
+}
+
+pub(crate) fn to_event_kind(kind: DomainEventKindEntity) -> DomainEventKind {
+    match kind {
+        DomainEventKindEntity::ConfigCreated => DomainEventKind::ConfigCreated,
+        DomainEventKindEntity::ConfigDeleted => DomainEventKind::ConfigDeleted,
+        DomainEventKindEntity::VersionCreated => DomainEventKind::VersionCreated,
+    }
+}
+
+pub(crate) fn to_domain_event(
+    secrets: &SecretsCipher,
+    event: DomainEventEntity,
+) -> Result<DomainEvent, EventsRepoError> {
+    let value = match (event.r#type, event.value) {
+        (Some(value_type), Some(value)) => {
+            match to_config_value(secrets, value_type, value, event.key_id) {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    error!(
+                        "[to_domain_event] Error decrypting the value of event {}: {:?}",
+                        event.id, err
+                    );
+                    return Err(EventsRepoError::Unknown);
+                }
+            }
+        }
+        _ => None,
+    };
+    Ok(DomainEvent {
+        id: event.id,
+        kind: to_event_kind(event.kind),
+        key: event.key,
+        environment: event.environment,
+        version: event.version,
+        value,
+        created_at: event.created_at,
+    })
+}
+
+/// Retrieve the id of the latest event of a domain, if it has any
+pub async fn get_last_event_id(
+    db: &mut PgConnection,
+    domain_id: &str,
+) -> Result<Option<i64>, EventsRepoError> {
+    let get_result = sqlx::query_scalar::<_, Option<i64>>(
+        "select max(e.id) from domain_events e where e.domain_id = $1::uuid",
+    )
+    .bind(domain_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(id) => Ok(id),
+        Err(err) => {
+            error!("[get_last_event_id] Error retrieving event id: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the events of a domain that follow the given one, from the oldest to the most recent
+pub async fn get_domain_events(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    domain_id: &str,
+    after_id: i64,
+    limit: i32,
+) -> Result<Vec<DomainEvent>, EventsRepoError> {
+    let get_result = sqlx::query_as::<_, DomainEventEntity>(
+        "select e.id, e.kind, e.key, e.environment, e.version, v.value, v.type, v.key_id, e.created_at \
+        from domain_events e left join versions v on v.id = e.version_id \
+        where e.domain_id = $1::uuid and e.id > $2 order by e.id limit $3",
+    )
+    .bind(domain_id)
+    .bind(after_id)
+    .bind(limit)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(events) => events
+            .into_iter()
+            .map(|event| to_domain_event(secrets, event))
+            .collect(),
+        Err(err) => {
+            error!("[get_domain_events] Error retrieving events: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
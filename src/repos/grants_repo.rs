@@ -0,0 +1,187 @@
+use std::borrow::Cow;
+
+use crate::models::{grant::Grant, principal::Role};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum GrantsRepoError {
+    AlreadyExists,
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct GrantEntity {
+    pub id: Uuid,
+    pub principal: String,
+    pub role: RoleEntity,
+    pub domain: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::Type, Debug)]
+#[sqlx(type_name = "role", rename_all = "lowercase")]
+enum RoleEntity {
+    Reader,
+    Writer,
+    Admin,
+}
+
+fn map_sqlx_error(error: Error) -> GrantsRepoError {
+    match error {
+        Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => GrantsRepoError::AlreadyExists,
+            _ => GrantsRepoError::Unknown,
+        },
+        Error::RowNotFound => GrantsRepoError::NotFound,
+        _ => GrantsRepoError::Unknown,
+    }
+}
+
+fn to_role_entity(role: Role) -> RoleEntity {
+    match role {
+        Role::Reader => RoleEntity::Reader,
+        Role::Writer => RoleEntity::Writer,
+        Role::Admin => RoleEntity::Admin,
+    }
+}
+
+fn to_role(role: RoleEntity) -> Role {
+    match role {
+        RoleEntity::Reader => Role::Reader,
+        RoleEntity::Writer => Role::Writer,
+        RoleEntity::Admin => Role::Admin,
+    }
+}
+
+fn to_grant(grant: GrantEntity) -> Grant {
+    Grant {
+        id: grant.id.to_string(),
+        principal: grant.principal,
+        role: to_role(grant.role),
+        domain: grant.domain,
+        created_at: grant.created_at,
+    }
+}
+
+/// Grant a role to a principal over a domain, or over the whole tenant when no domain is given
+pub async fn create_grant(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: Option<&str>,
+    principal: &str,
+    role: Role,
+) -> Result<Grant, GrantsRepoError> {
+    let create_grant_result = sqlx::query_as::<_, GrantEntity>(
+        "with grant_row as (insert into grants(tenant_id, domain_id, principal, role) select t.id, $2::uuid, $3, $4 from tenants t where t.slug = $1 \
+        returning id, domain_id, principal, role, created_at) \
+        select g.id, g.principal, g.role, d.slug as domain, g.created_at from grant_row g left join domains d on d.id = g.domain_id",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(principal)
+    .bind(to_role_entity(role))
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_grant_result {
+        Ok(grant) => Ok(to_grant(grant)),
+        Err(err) => {
+            error!("[create_grant] Error creating grant: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the grants over a domain, or over the whole tenant when no domain is given
+pub async fn get_grants(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: Option<&str>,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<Grant>, GrantsRepoError> {
+    let get_grants_result = sqlx::query_as::<_, GrantEntity>(
+        "select g.id, g.principal, g.role, d.slug as domain, g.created_at from grants g join tenants t on t.id = g.tenant_id left join domains d on d.id = g.domain_id \
+        where t.slug = $1 and g.domain_id is not distinct from $2::uuid order by g.principal limit $3 offset $4",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_grants_result {
+        Ok(grants) => Ok(grants.into_iter().map(to_grant).collect()),
+        Err(err) => {
+            error!("[get_grants] Error retrieving grants: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Revoke the role of a principal over a domain, or over the whole tenant when no domain is given
+pub async fn delete_grant(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: Option<&str>,
+    principal: &str,
+) -> Result<(), GrantsRepoError> {
+    let result = sqlx::query(
+        "delete from grants g using tenants t where t.id = g.tenant_id and t.slug = $1 and g.domain_id is not distinct from $2::uuid and g.principal = $3",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(principal)
+    .execute(&mut *db)
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() == 0 {
+                error!(
+                    "[delete_grant] Grant not found for principal: {}",
+                    principal
+                );
+                return Err(GrantsRepoError::NotFound);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            error!("[delete_grant] Error deleting grant: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the most privileged role of a principal over a domain, taking the roles granted over
+/// the whole tenant into account. Without a domain, only the roles granted over the tenant count
+pub async fn get_role(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_id: Option<&str>,
+    principal: &str,
+) -> Result<Option<Role>, GrantsRepoError> {
+    let get_role_result = sqlx::query_scalar::<_, Option<RoleEntity>>(
+        "select max(g.role) from grants g join tenants t on t.id = g.tenant_id \
+        where t.slug = $1 and g.principal = $3 and (g.domain_id is null or g.domain_id = $2::uuid)",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .bind(principal)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_role_result {
+        Ok(role) => Ok(role.map(to_role)),
+        Err(err) => {
+            error!("[get_role] Error retrieving role: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
@@ -1,3 +1,11 @@
+pub mod api_keys_repo;
+pub mod audit_repo;
 pub mod configs_repo;
 pub mod domains_repo;
+pub mod environments_repo;
+pub mod events_repo;
+pub mod grants_repo;
+pub mod reencryptions_repo;
+pub mod tenants_repo;
 pub mod versions_repo;
+pub mod webhooks_repo;
@@ -0,0 +1,182 @@
+use std::borrow::Cow;
+
+use crate::models::reencryption::{Reencryption, ReencryptionStatus};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum ReencryptionsRepoError {
+    AlreadyRunning,
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct ReencryptionEntity {
+    pub id: Uuid,
+    pub key_id: String,
+    pub status: ReencryptionStatusEntity,
+    pub total: i32,
+    pub processed: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::Type, Debug)]
+#[sqlx(type_name = "reencryption_status", rename_all = "lowercase")]
+enum ReencryptionStatusEntity {
+    Running,
+    Completed,
+    Failed,
+}
+
+fn map_sqlx_error(error: Error) -> ReencryptionsRepoError {
+    match error {
+        Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => ReencryptionsRepoError::AlreadyRunning,
+            _ => ReencryptionsRepoError::Unknown,
+        },
+        Error::RowNotFound => ReencryptionsRepoError::NotFound,
+        _ => ReencryptionsRepoError::Unknown,
+    }
+}
+
+fn to_status_entity(status: ReencryptionStatus) -> ReencryptionStatusEntity {
+    match status {
+        ReencryptionStatus::Running => ReencryptionStatusEntity::Running,
+        ReencryptionStatus::Completed => ReencryptionStatusEntity::Completed,
+        ReencryptionStatus::Failed => ReencryptionStatusEntity::Failed,
+    }
+}
+
+fn to_reencryption(reencryption: ReencryptionEntity) -> Reencryption {
+    Reencryption {
+        id: reencryption.id.to_string(),
+        key_id: reencryption.key_id,
+        status: match reencryption.status {
+            ReencryptionStatusEntity::Running => ReencryptionStatus::Running,
+            ReencryptionStatusEntity::Completed => ReencryptionStatus::Completed,
+            ReencryptionStatusEntity::Failed => ReencryptionStatus::Failed,
+        },
+        total: reencryption.total,
+        processed: reencryption.processed,
+        created_at: reencryption.created_at,
+        updated_at: reencryption.updated_at,
+        finished_at: reencryption.finished_at,
+    }
+}
+
+/// Start a new job, unless another one is still running
+pub async fn create_reencryption(
+    db: &mut PgConnection,
+    key_id: &str,
+    total: i32,
+) -> Result<Reencryption, ReencryptionsRepoError> {
+    let create_result = sqlx::query_as::<_, ReencryptionEntity>(
+        "insert into reencryptions(key_id, total) values($1, $2) \
+        returning id, key_id, status, total, processed, created_at, updated_at, finished_at",
+    )
+    .bind(key_id)
+    .bind(total)
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_result {
+        Ok(reencryption) => Ok(to_reencryption(reencryption)),
+        Err(err) => {
+            error!("[create_reencryption] Error creating job: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn get_reencryption(
+    db: &mut PgConnection,
+    id: &str,
+) -> Result<Reencryption, ReencryptionsRepoError> {
+    let get_result = sqlx::query_as::<_, ReencryptionEntity>(
+        "select id, key_id, status, total, processed, created_at, updated_at, finished_at from reencryptions where id = $1::uuid",
+    )
+    .bind(id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(reencryption) => Ok(to_reencryption(reencryption)),
+        Err(err) => {
+            error!("[get_reencryption] Error retrieving job: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the job that is still running, if any
+pub async fn get_running_reencryption(
+    db: &mut PgConnection,
+) -> Result<Option<Reencryption>, ReencryptionsRepoError> {
+    let get_result = sqlx::query_as::<_, ReencryptionEntity>(
+        "select id, key_id, status, total, processed, created_at, updated_at, finished_at from reencryptions where status = 'running'",
+    )
+    .fetch_optional(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(reencryption) => Ok(reencryption.map(to_reencryption)),
+        Err(err) => {
+            error!("[get_running_reencryption] Error retrieving job: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Count more values as processed by a running job
+pub async fn add_processed(
+    db: &mut PgConnection,
+    id: &str,
+    processed: i32,
+) -> Result<(), ReencryptionsRepoError> {
+    let update_result = sqlx::query(
+        "update reencryptions set processed = processed + $2, updated_at = now() where id = $1::uuid and status = 'running'",
+    )
+    .bind(id)
+    .bind(processed)
+    .execute(&mut *db)
+    .await;
+
+    match update_result {
+        Ok(result) if result.rows_affected() == 0 => Err(ReencryptionsRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[add_processed] Error updating job: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Mark a running job as completed or failed
+pub async fn finish_reencryption(
+    db: &mut PgConnection,
+    id: &str,
+    status: ReencryptionStatus,
+) -> Result<(), ReencryptionsRepoError> {
+    let update_result = sqlx::query(
+        "update reencryptions set status = $2, updated_at = now(), finished_at = now() where id = $1::uuid and status = 'running'",
+    )
+    .bind(id)
+    .bind(to_status_entity(status))
+    .execute(&mut *db)
+    .await;
+
+    match update_result {
+        Ok(result) if result.rows_affected() == 0 => Err(ReencryptionsRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[finish_reencryption] Error updating job: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
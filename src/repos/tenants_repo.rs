@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+
+use crate::models::tenant::Tenant;
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum TenantsRepoError {
+    AlreadyExists,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct TenantEntity {
+    pub id: Uuid,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+fn map_sqlx_error(error: Error) -> TenantsRepoError {
+    match error {
+        Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => TenantsRepoError::AlreadyExists,
+            _ => TenantsRepoError::Unknown,
+        },
+        _ => TenantsRepoError::Unknown,
+    }
+}
+
+fn to_tenant(tenant: TenantEntity) -> Tenant {
+    Tenant {
+        id: tenant.id.to_string(),
+        slug: tenant.slug,
+        created_at: tenant.created_at,
+    }
+}
+
+pub async fn create_tenant(db: &mut PgConnection, slug: &str) -> Result<Tenant, TenantsRepoError> {
+    let create_tenant_result = sqlx::query_as::<_, TenantEntity>(
+        "insert into tenants(slug) values($1) returning id, slug, created_at",
+    )
+    .bind(slug)
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_tenant_result {
+        Ok(tenant) => Ok(to_tenant(tenant)),
+        Err(err) => {
+            error!("[create_tenant] Error creating tenant: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn get_tenants(
+    db: &mut PgConnection,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<Tenant>, TenantsRepoError> {
+    let get_tenants_result = sqlx::query_as::<_, TenantEntity>(
+        "select id, slug, created_at from tenants order by slug limit $1 offset $2",
+    )
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_tenants_result {
+        Ok(tenants) => Ok(tenants.into_iter().map(to_tenant).collect()),
+        Err(err) => {
+            error!("[get_tenants] Error retrieving tenants: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
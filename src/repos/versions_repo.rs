@@ -1,36 +1,69 @@
-use crate::models::config::{ConfigValue, ConfigVersion};
+use std::borrow::Cow;
+
+use crate::{
+    models::config::{ConfigEntry, ConfigValue, ConfigVersion, SealedValue},
+    shared::secrets::{SecretsCipher, SecretsError},
+};
 use chrono::{DateTime, Utc};
 use rocket::error;
 use rocket_db_pools::sqlx::{self};
-use sqlx::{pool::PoolConnection, types::Uuid, Error, Postgres};
+use sqlx::{
+    types::{JsonValue, Uuid},
+    Error, PgConnection,
+};
 
 #[derive(Debug)]
 pub enum VersionsRepoError {
     NotFound,
+    AlreadyExists,
     Unknown,
 }
 
 #[derive(sqlx::FromRow, Debug)]
 struct VersionEntity {
     pub id: Uuid,
-    pub value: String,
+    pub value: JsonValue,
     pub r#type: ValueTypeEntity,
     pub version: i32,
+    pub restored_from: Option<i32>,
+    pub key_id: Option<String>,
+    pub author: Option<String>,
+    pub message: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(sqlx::FromRow, Debug)]
+struct StaleValueEntity {
+    pub id: Uuid,
+    pub value: JsonValue,
+    pub key_id: Option<String>,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct ConfigEntryEntity {
+    pub key: String,
+    #[sqlx(flatten)]
+    pub version: VersionEntity,
+}
+
 #[derive(sqlx::Type, Debug)]
 #[sqlx(type_name = "value_type", rename_all = "lowercase")]
-enum ValueTypeEntity {
+pub(crate) enum ValueTypeEntity {
     String,
     Boolean,
     Float,
     Integer,
+    Object,
+    Array,
+    Null,
+    Secret,
 }
 
 fn map_sqlx_error(error: Error) -> VersionsRepoError {
     match error {
         Error::Database(err) => match err.code() {
+            // Postgres code for unique_violation: https://www.postgresql.org/docs/current/errcodes-appendix.html
+            Some(Cow::Borrowed("23505")) => VersionsRepoError::AlreadyExists,
             _ => VersionsRepoError::Unknown,
         },
         Error::RowNotFound => VersionsRepoError::NotFound,
@@ -38,67 +71,193 @@ fn map_sqlx_error(error: Error) -> VersionsRepoError {
     }
 }
 
-fn to_value_type_entity(config_value: ConfigValue) -> ValueTypeEntity {
+fn to_value_type_entity(config_value: &ConfigValue) -> ValueTypeEntity {
     match config_value {
         ConfigValue::Boolean(_) => ValueTypeEntity::Boolean,
         ConfigValue::String(_) => ValueTypeEntity::String,
         ConfigValue::Float(_) => ValueTypeEntity::Float,
         ConfigValue::Integer(_) => ValueTypeEntity::Integer,
+        ConfigValue::Object(_) => ValueTypeEntity::Object,
+        ConfigValue::Array(_) => ValueTypeEntity::Array,
+        ConfigValue::Null => ValueTypeEntity::Null,
+        ConfigValue::Secret(_) => ValueTypeEntity::Secret,
     }
 }
 
-fn to_config_value(value_type: ValueTypeEntity, value: String) -> ConfigValue {
-    match value_type {
-        ValueTypeEntity::String => ConfigValue::String(value),
-        ValueTypeEntity::Boolean => ConfigValue::Boolean(value.parse::<bool>().unwrap()),
-        ValueTypeEntity::Float => ConfigValue::Float(value.parse::<f64>().unwrap()),
-        ValueTypeEntity::Integer => ConfigValue::Integer(value.parse::<i64>().unwrap()),
+fn to_json_value(config_value: &ConfigValue) -> JsonValue {
+    match config_value {
+        ConfigValue::String(v) => JsonValue::from(v.as_str()),
+        ConfigValue::Boolean(v) => JsonValue::from(*v),
+        ConfigValue::Float(v) => JsonValue::from(*v),
+        ConfigValue::Integer(v) => JsonValue::from(*v),
+        ConfigValue::Object(v) => JsonValue::Object(v.clone()),
+        ConfigValue::Array(v) => JsonValue::Array(v.clone()),
+        ConfigValue::Null => JsonValue::Null,
+        ConfigValue::Secret(v) => JsonValue::from(v.envelope.as_str()),
+    }
+}
+
+/// Encode a config value the way it is stored, along with the id of the master key it is
+/// encrypted with. Secrets come sealed already, while other values are sealed with the active
+/// master key, and only stored in the clear when there is none
+fn to_stored_value(
+    secrets: &SecretsCipher,
+    config_value: &ConfigValue,
+) -> Result<(JsonValue, Option<String>), VersionsRepoError> {
+    match config_value {
+        ConfigValue::Secret(v) => Ok((to_json_value(config_value), Some(v.key_id.clone()))),
+        _ if secrets.active_key().is_none() => Ok((to_json_value(config_value), None)),
+        _ => match secrets.seal(&to_json_value(config_value)) {
+            Ok(sealed_value) => Ok((
+                JsonValue::from(sealed_value.envelope),
+                Some(sealed_value.key_id),
+            )),
+            Err(err) => {
+                error!("[to_stored_value] Error encrypting value: {:?}", err);
+                Err(VersionsRepoError::Unknown)
+            }
+        },
+    }
+}
+
+/// Decode a stored value. Secrets are kept sealed, to be revealed on demand only, while other
+/// values encrypted with a master key are decrypted
+pub(crate) fn to_config_value(
+    secrets: &SecretsCipher,
+    value_type: ValueTypeEntity,
+    value: JsonValue,
+    key_id: Option<String>,
+) -> Result<ConfigValue, SecretsError> {
+    let value = match (&value_type, &key_id) {
+        (ValueTypeEntity::Secret, _) | (_, None) => value,
+        (_, Some(key_id)) => secrets.open(&SealedValue {
+            key_id: key_id.clone(),
+            envelope: value.as_str().unwrap().to_string(),
+        })?,
+    };
+    Ok(match value_type {
+        ValueTypeEntity::String => ConfigValue::String(value.as_str().unwrap().to_string()),
+        ValueTypeEntity::Boolean => ConfigValue::Boolean(value.as_bool().unwrap()),
+        ValueTypeEntity::Float => ConfigValue::Float(value.as_f64().unwrap()),
+        ValueTypeEntity::Integer => ConfigValue::Integer(value.as_i64().unwrap()),
+        ValueTypeEntity::Object => ConfigValue::Object(value.as_object().unwrap().to_owned()),
+        ValueTypeEntity::Array => ConfigValue::Array(value.as_array().unwrap().to_owned()),
+        ValueTypeEntity::Null => ConfigValue::Null,
+        ValueTypeEntity::Secret => ConfigValue::Secret(SealedValue {
+            key_id: key_id.unwrap(),
+            envelope: value.as_str().unwrap().to_string(),
+        }),
+    })
+}
+
+fn to_config_version(
+    secrets: &SecretsCipher,
+    version: VersionEntity,
+) -> Result<ConfigVersion, VersionsRepoError> {
+    let value = match to_config_value(secrets, version.r#type, version.value, version.key_id) {
+        Ok(value) => value,
+        Err(err) => {
+            error!(
+                "[to_config_version] Error decrypting version {}: {:?}",
+                version.id, err
+            );
+            return Err(VersionsRepoError::Unknown);
+        }
+    };
+    Ok(ConfigVersion {
+        id: version.id.to_string(),
+        version: version.version,
+        value,
+        restored_from: version.restored_from,
+        author: version.author,
+        message: version.message,
+        created_at: version.created_at,
+    })
+}
+
+/// Lock a config until the end of the transaction, so that versions of it are created one at a
+/// time and each one is based on the latest version the previous one left
+pub async fn lock_versions(
+    db: &mut PgConnection,
+    tenant: &str,
+    config_id: &str,
+) -> Result<(), VersionsRepoError> {
+    let lock_result = sqlx::query(
+        "select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id \
+        where c.id = $2::uuid and t.slug = $1 for no key update of c",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match lock_result {
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[lock_versions] Error locking config: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
     }
 }
 
+/// Create a new version of a config. Versions without an environment belong to the domain itself
+#[allow(clippy::too_many_arguments)]
 pub async fn create_version(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
     config_id: &str,
+    environment_id: Option<&str>,
     config_value: ConfigValue,
+    author: &str,
+    message: Option<&str>,
 ) -> Result<ConfigVersion, VersionsRepoError> {
+    let (value, key_id) = to_stored_value(secrets, &config_value)?;
     let create_version_result =  sqlx::query_as::<_, VersionEntity>(
-              "with latest_version as (select version from versions where config_id = $1::uuid order by version desc limit 1) \
-              insert into versions(config_id, value, type, version) \
-              values($1::uuid, $2, $3::value_type, coalesce((select version from latest_version), 0) + 1) \
-              returning id, value, type, version, created_at",
+              "with latest_version as (select version from versions where config_id = $2::uuid and environment_id is not distinct from $5::uuid order by version desc limit 1) \
+              insert into versions(config_id, value, type, version, environment_id, key_id, author, message) \
+              select $2::uuid, $3, $4::value_type, coalesce((select version from latest_version), 0) + 1, $5::uuid, $6, $7, $8 \
+              where $2::uuid in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1) \
+              returning id, value, type, version, restored_from, key_id, author, message, created_at",
             )
+            .bind(tenant)
             .bind(config_id)
-            .bind(config_value.to_string())
-            .bind(to_value_type_entity(config_value))
+            .bind(value)
+            .bind(to_value_type_entity(&config_value))
+            .bind(environment_id)
+            .bind(key_id)
+            .bind(author)
+            .bind(message)
             .fetch_one(&mut *db)
             .await;
 
     match create_version_result {
         Err(err) => {
             error!("[create_version] Error inserting value: {:?}", err);
-            return Err(map_sqlx_error(err));
+            Err(map_sqlx_error(err))
         }
-        Ok(value) => Ok(ConfigVersion {
-            id: value.id.to_string(),
-            version: value.version,
-            value: to_config_value(value.r#type, value.value),
-            created_at: value.created_at,
-        }),
+        Ok(value) => to_config_version(secrets, value),
     }
 }
 
 pub async fn get_versions(
-    db: &mut PoolConnection<Postgres>,
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
     config_id: &str,
+    environment_id: Option<&str>,
     limit: i32,
     offset: i32,
 ) -> Result<Vec<ConfigVersion>, VersionsRepoError> {
     let get_versions_result = sqlx::query_as::<_, VersionEntity>(
-        "select id, value, type, version, created_at from versions where config_id = $1::uuid order by version desc limit $2 offset $3",
+        "select id, value, type, version, restored_from, key_id, author, message, created_at from versions where config_id = $2::uuid and environment_id is not distinct from $5::uuid \
+        and config_id in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1) order by version desc limit $3 offset $4",
     )
+    .bind(tenant)
     .bind(config_id)
     .bind(limit)
     .bind(offset)
+    .bind(environment_id)
     .fetch_all(&mut *db)
     .await;
 
@@ -106,12 +265,7 @@ pub async fn get_versions(
         Ok(versions) => {
             let mut result = vec![];
             for version in versions {
-                result.push(ConfigVersion {
-                    id: version.id.to_string(),
-                    version: version.version,
-                    value: to_config_value(version.r#type, version.value),
-                    created_at: version.created_at,
-                })
+                result.push(to_config_version(secrets, version)?)
             }
             Ok(result)
         }
@@ -121,3 +275,244 @@ pub async fn get_versions(
         }
     }
 }
+
+pub async fn get_latest_version(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    config_id: &str,
+    environment_id: Option<&str>,
+) -> Result<ConfigVersion, VersionsRepoError> {
+    let get_version_result = sqlx::query_as::<_, VersionEntity>(
+        "select id, value, type, version, restored_from, key_id, author, message, created_at from versions where config_id = $2::uuid and environment_id is not distinct from $3::uuid \
+        and config_id in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1) order by version desc limit 1",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .bind(environment_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_version_result {
+        Ok(version) => to_config_version(secrets, version),
+        Err(err) => {
+            error!("[get_latest_version] Error retrieving version: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Count the versions of a config, across all environments
+pub async fn count_versions(
+    db: &mut PgConnection,
+    tenant: &str,
+    config_id: &str,
+) -> Result<i64, VersionsRepoError> {
+    let count_result = sqlx::query_scalar::<_, i64>(
+        "select count(*) from versions where config_id = $2::uuid and config_id in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1)",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match count_result {
+        Ok(count) => Ok(count),
+        Err(err) => {
+            error!("[count_versions] Error counting versions: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn get_version(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    config_id: &str,
+    environment_id: Option<&str>,
+    version: i32,
+) -> Result<ConfigVersion, VersionsRepoError> {
+    let get_version_result = sqlx::query_as::<_, VersionEntity>(
+        "select id, value, type, version, restored_from, key_id, author, message, created_at from versions where config_id = $2::uuid and environment_id is not distinct from $4::uuid and version = $3 \
+        and config_id in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1)",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .bind(version)
+    .bind(environment_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_version_result {
+        Ok(version) => to_config_version(secrets, version),
+        Err(err) => {
+            error!("[get_version] Error retrieving version: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+pub async fn rollback_version(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    config_id: &str,
+    environment_id: Option<&str>,
+    source_version: i32,
+    author: &str,
+) -> Result<ConfigVersion, VersionsRepoError> {
+    let rollback_version_result = sqlx::query_as::<_, VersionEntity>(
+        "with latest_version as (select version from versions where config_id = $2::uuid and environment_id is not distinct from $4::uuid order by version desc limit 1) \
+        insert into versions(config_id, value, type, version, restored_from, environment_id, key_id, author) \
+        select config_id, value, type, coalesce((select version from latest_version), 0) + 1, version, environment_id, key_id, $5 \
+        from versions where config_id = $2::uuid and environment_id is not distinct from $4::uuid and version = $3 \
+        and config_id in (select c.id from configs c join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id where t.slug = $1) \
+        returning id, value, type, version, restored_from, key_id, author, message, created_at",
+    )
+    .bind(tenant)
+    .bind(config_id)
+    .bind(source_version)
+    .bind(environment_id)
+    .bind(author)
+    .fetch_one(&mut *db)
+    .await;
+
+    match rollback_version_result {
+        Ok(version) => to_config_version(secrets, version),
+        Err(err) => {
+            error!("[rollback_version] Error restoring version: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the latest version of every config in a domain, outside of any environment.
+/// Configs without versions are left out
+pub async fn get_latest_versions(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    domain_id: &str,
+) -> Result<Vec<ConfigEntry>, VersionsRepoError> {
+    let get_versions_result = sqlx::query_as::<_, ConfigEntryEntity>(
+        "select distinct on (c.key) c.key, v.id, v.value, v.type, v.version, v.restored_from, v.key_id, v.author, v.message, v.created_at \
+        from configs c join versions v on v.config_id = c.id join domains d on d.id = c.domain_id join tenants t on t.id = d.tenant_id \
+        where t.slug = $1 and c.domain_id = $2::uuid and v.environment_id is null \
+        order by c.key, v.version desc",
+    )
+    .bind(tenant)
+    .bind(domain_id)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_versions_result {
+        Ok(entries) => {
+            let mut result = vec![];
+            for entry in entries {
+                result.push(ConfigEntry {
+                    key: entry.key,
+                    version: to_config_version(secrets, entry.version)?,
+                })
+            }
+            Ok(result)
+        }
+        Err(err) => {
+            error!("[get_latest_versions] Error retrieving versions: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Count the values of every tenant that are not encrypted with the given master key,
+/// including the ones stored in the clear
+pub async fn count_stale_values(
+    db: &mut PgConnection,
+    key_id: &str,
+) -> Result<i64, VersionsRepoError> {
+    let count_result = sqlx::query_scalar::<_, i64>(
+        "select count(*) from versions where key_id is distinct from $1",
+    )
+    .bind(key_id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match count_result {
+        Ok(count) => Ok(count),
+        Err(err) => {
+            error!("[count_stale_values] Error counting values: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve and lock a batch of values, of any tenant, that are not encrypted with the given
+/// master key, decrypted. Values locked by concurrent batches are skipped
+pub async fn lock_stale_values(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    key_id: &str,
+    limit: i32,
+) -> Result<Vec<(String, JsonValue)>, VersionsRepoError> {
+    let lock_result = sqlx::query_as::<_, StaleValueEntity>(
+        "select id, value, key_id from versions where key_id is distinct from $1 \
+        order by id limit $2 for update skip locked",
+    )
+    .bind(key_id)
+    .bind(limit)
+    .fetch_all(&mut *db)
+    .await;
+
+    let stale_values = match lock_result {
+        Ok(stale_values) => stale_values,
+        Err(err) => {
+            error!("[lock_stale_values] Error retrieving values: {:?}", err);
+            return Err(map_sqlx_error(err));
+        }
+    };
+    let mut result = vec![];
+    for stale_value in stale_values {
+        let value = match stale_value.key_id {
+            Some(key_id) => secrets.open(&SealedValue {
+                key_id,
+                envelope: stale_value.value.as_str().unwrap().to_string(),
+            }),
+            None => Ok(stale_value.value),
+        };
+        match value {
+            Ok(value) => result.push((stale_value.id.to_string(), value)),
+            Err(err) => {
+                error!(
+                    "[lock_stale_values] Error decrypting version {}: {:?}",
+                    stale_value.id, err
+                );
+                return Err(VersionsRepoError::Unknown);
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Replace a value with the same value encrypted under another master key
+pub async fn update_value(
+    db: &mut PgConnection,
+    version_id: &str,
+    sealed_value: &SealedValue,
+) -> Result<(), VersionsRepoError> {
+    let update_result =
+        sqlx::query("update versions set value = $2, key_id = $3 where id = $1::uuid")
+            .bind(version_id)
+            .bind(JsonValue::from(sealed_value.envelope.as_str()))
+            .bind(sealed_value.key_id.as_str())
+            .execute(&mut *db)
+            .await;
+
+    match update_result {
+        Ok(result) if result.rows_affected() == 0 => Err(VersionsRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[update_value] Error updating value: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use crate::{
+    models::{
+        event::DomainEventKind,
+        webhook::{DueDelivery, Webhook, WebhookDelivery, WebhookDeliveryStatus},
+    },
+    repos::events_repo::{
+        to_domain_event, to_event_kind, DomainEventEntity, DomainEventKindEntity,
+    },
+    shared::secrets::SecretsCipher,
+};
+use chrono::{DateTime, Utc};
+use rocket::error;
+use rocket_db_pools::sqlx::{self};
+use sqlx::{types::Uuid, Error, PgConnection};
+
+#[derive(Debug)]
+pub enum WebhooksRepoError {
+    NotFound,
+    Unknown,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct WebhookEntity {
+    pub id: Uuid,
+    pub url: String,
+    pub events: Vec<String>,
+    pub keys: Option<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct WebhookDeliveryEntity {
+    pub id: Uuid,
+    pub event_id: i64,
+    pub kind: DomainEventKindEntity,
+    pub key: String,
+    pub status: WebhookDeliveryStatusEntity,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct DueDeliveryEntity {
+    pub delivery_id: Uuid,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+    pub domain: String,
+    #[sqlx(flatten)]
+    pub event: DomainEventEntity,
+}
+
+#[derive(sqlx::Type, Debug)]
+#[sqlx(type_name = "webhook_delivery_status", rename_all = "lowercase")]
+enum WebhookDeliveryStatusEntity {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+fn map_sqlx_error(error: Error) -> WebhooksRepoError {
+    match error {
+        Error::RowNotFound => WebhooksRepoError::NotFound,
+        _ => WebhooksRepoError::Unknown,
+    }
+}
+
+fn to_status_entity(status: WebhookDeliveryStatus) -> WebhookDeliveryStatusEntity {
+    match status {
+        WebhookDeliveryStatus::Pending => WebhookDeliveryStatusEntity::Pending,
+        WebhookDeliveryStatus::Delivered => WebhookDeliveryStatusEntity::Delivered,
+        WebhookDeliveryStatus::Failed => WebhookDeliveryStatusEntity::Failed,
+    }
+}
+
+fn to_webhook(webhook: WebhookEntity) -> Webhook {
+    Webhook {
+        id: webhook.id.to_string(),
+        url: webhook.url,
+        events: webhook
+            .events
+            .iter()
+            .filter_map(|kind| DomainEventKind::from_name(kind))
+            .collect(),
+        keys: webhook.keys,
+        created_at: webhook.created_at,
+    }
+}
+
+fn to_webhook_delivery(delivery: WebhookDeliveryEntity) -> WebhookDelivery {
+    let status = match delivery.status {
+        WebhookDeliveryStatusEntity::Pending => WebhookDeliveryStatus::Pending,
+        WebhookDeliveryStatusEntity::Delivered => WebhookDeliveryStatus::Delivered,
+        WebhookDeliveryStatusEntity::Failed => WebhookDeliveryStatus::Failed,
+    };
+    WebhookDelivery {
+        id: delivery.id.to_string(),
+        event_id: delivery.event_id,
+        kind: to_event_kind(delivery.kind),
+        key: delivery.key,
+        status,
+        attempts: delivery.attempts,
+        next_attempt_at: match status {
+            WebhookDeliveryStatus::Pending => Some(delivery.next_attempt_at),
+            _ => None,
+        },
+        response_status: delivery.response_status,
+        error: delivery.error,
+        created_at: delivery.created_at,
+        delivered_at: delivery.delivered_at,
+    }
+}
+
+fn to_due_delivery(
+    secrets: &SecretsCipher,
+    delivery: DueDeliveryEntity,
+) -> Result<DueDelivery, WebhooksRepoError> {
+    let event = match to_domain_event(secrets, delivery.event) {
+        Ok(event) => event,
+        Err(_) => return Err(WebhooksRepoError::Unknown),
+    };
+    Ok(DueDelivery {
+        id: delivery.delivery_id.to_string(),
+        attempts: delivery.attempts,
+        url: delivery.url,
+        secret: delivery.secret,
+        domain: delivery.domain,
+        event,
+    })
+}
+
+/// Register a webhook on a domain
+pub async fn create_webhook(
+    db: &mut PgConnection,
+    domain_id: &str,
+    url: &str,
+    events: &[DomainEventKind],
+    keys: Option<&[String]>,
+    secret: &str,
+) -> Result<Webhook, WebhooksRepoError> {
+    let events: Vec<&str> = events.iter().map(|kind| kind.name()).collect();
+    let create_result = sqlx::query_as::<_, WebhookEntity>(
+        "insert into webhooks(domain_id, url, events, keys, secret) values ($1::uuid, $2, $3::domain_event_kind[], $4, $5) \
+        returning id, url, events::varchar[] as events, keys, created_at",
+    )
+    .bind(domain_id)
+    .bind(url)
+    .bind(events)
+    .bind(keys)
+    .bind(secret)
+    .fetch_one(&mut *db)
+    .await;
+
+    match create_result {
+        Ok(webhook) => Ok(to_webhook(webhook)),
+        Err(err) => {
+            error!("[create_webhook] Error creating webhook: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the webhooks of a domain, from the oldest to the most recent
+pub async fn get_webhooks(
+    db: &mut PgConnection,
+    domain_id: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<Webhook>, WebhooksRepoError> {
+    let get_result = sqlx::query_as::<_, WebhookEntity>(
+        "select w.id, w.url, w.events::varchar[] as events, w.keys, w.created_at from webhooks w \
+        where w.domain_id = $1::uuid order by w.created_at, w.id limit $2 offset $3",
+    )
+    .bind(domain_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(webhooks) => Ok(webhooks.into_iter().map(to_webhook).collect()),
+        Err(err) => {
+            error!("[get_webhooks] Error retrieving webhooks: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve a webhook of a domain
+pub async fn get_webhook(
+    db: &mut PgConnection,
+    domain_id: &str,
+    id: &str,
+) -> Result<Webhook, WebhooksRepoError> {
+    let get_result = sqlx::query_as::<_, WebhookEntity>(
+        "select w.id, w.url, w.events::varchar[] as events, w.keys, w.created_at from webhooks w \
+        where w.domain_id = $1::uuid and w.id = $2::uuid",
+    )
+    .bind(domain_id)
+    .bind(id)
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(webhook) => Ok(to_webhook(webhook)),
+        Err(Error::RowNotFound) => Err(WebhooksRepoError::NotFound),
+        Err(err) => {
+            error!("[get_webhook] Error retrieving webhook: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Delete a webhook of a domain, along with its deliveries
+pub async fn delete_webhook(
+    db: &mut PgConnection,
+    domain_id: &str,
+    id: &str,
+) -> Result<(), WebhooksRepoError> {
+    let delete_result =
+        sqlx::query("delete from webhooks w where w.domain_id = $1::uuid and w.id = $2::uuid")
+            .bind(domain_id)
+            .bind(id)
+            .execute(&mut *db)
+            .await;
+
+    match delete_result {
+        Ok(result) if result.rows_affected() == 0 => Err(WebhooksRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!("[delete_webhook] Error deleting webhook: {:?}", err);
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Retrieve the deliveries of a webhook, from the most recent to the oldest
+pub async fn get_webhook_deliveries(
+    db: &mut PgConnection,
+    webhook_id: &str,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<WebhookDelivery>, WebhooksRepoError> {
+    let get_result = sqlx::query_as::<_, WebhookDeliveryEntity>(
+        "select d.id, d.event_id, e.kind, e.key, d.status, d.attempts, d.next_attempt_at, d.response_status, d.error, d.created_at, d.delivered_at \
+        from webhook_deliveries d join domain_events e on e.id = d.event_id \
+        where d.webhook_id = $1::uuid order by d.event_id desc limit $2 offset $3",
+    )
+    .bind(webhook_id)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(deliveries) => Ok(deliveries.into_iter().map(to_webhook_delivery).collect()),
+        Err(err) => {
+            error!(
+                "[get_webhook_deliveries] Error retrieving deliveries: {:?}",
+                err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Claim the pending deliveries that are due, counting an attempt for each of them. They are
+/// pushed back by the given lease, after which they are attempted again unless their attempt
+/// was recorded in the meantime, so that the deliveries of a crashed app are not lost.
+/// Deliveries whose event cannot be decrypted are failed instead of being returned
+pub async fn claim_due_deliveries(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    limit: i32,
+    lease_ms: i64,
+) -> Result<Vec<DueDelivery>, WebhooksRepoError> {
+    let claim_result = sqlx::query_as::<_, DueDeliveryEntity>(
+        "with due as (select d.id from webhook_deliveries d where d.status = 'pending' and d.next_attempt_at <= now() \
+        order by d.next_attempt_at limit $1 for update skip locked), \
+        claimed as (update webhook_deliveries d set attempts = d.attempts + 1, next_attempt_at = now() + $2 * interval '1 millisecond' \
+        from due where d.id = due.id returning d.id, d.webhook_id, d.event_id, d.attempts) \
+        select c.id as delivery_id, c.attempts, w.url, w.secret, dom.slug as domain, \
+        e.id, e.kind, e.key, e.environment, e.version, v.value, v.type, v.key_id, e.created_at \
+        from claimed c join webhooks w on w.id = c.webhook_id join domain_events e on e.id = c.event_id \
+        join domains dom on dom.id = e.domain_id left join versions v on v.id = e.version_id order by e.id",
+    )
+    .bind(limit)
+    .bind(lease_ms as f64)
+    .fetch_all(&mut *db)
+    .await;
+
+    let deliveries = match claim_result {
+        Ok(deliveries) => deliveries,
+        Err(err) => {
+            error!(
+                "[claim_due_deliveries] Error claiming deliveries: {:?}",
+                err
+            );
+            return Err(map_sqlx_error(err));
+        }
+    };
+    // A delivery whose event cannot be decrypted would never succeed, so it is failed on its
+    // own rather than holding back the rest of the batch
+    let mut due_deliveries = vec![];
+    for delivery in deliveries {
+        let id = delivery.delivery_id.to_string();
+        match to_due_delivery(secrets, delivery) {
+            Ok(due_delivery) => due_deliveries.push(due_delivery),
+            Err(_) => {
+                error!(
+                    "[claim_due_deliveries] Failing delivery {}, whose event cannot be decrypted",
+                    id
+                );
+                record_delivery_attempt(
+                    db,
+                    id.as_str(),
+                    WebhookDeliveryStatus::Failed,
+                    None,
+                    Some("The value of the event could not be decrypted"),
+                    0,
+                )
+                .await?;
+            }
+        }
+    }
+    Ok(due_deliveries)
+}
+
+/// Record the outcome of an attempt. Deliveries left pending are attempted again after the
+/// given delay
+pub async fn record_delivery_attempt(
+    db: &mut PgConnection,
+    id: &str,
+    status: WebhookDeliveryStatus,
+    response_status: Option<i32>,
+    error: Option<&str>,
+    retry_in_ms: i64,
+) -> Result<(), WebhooksRepoError> {
+    let record_result = sqlx::query(
+        "update webhook_deliveries set status = $2, response_status = $3, error = $4, \
+        next_attempt_at = now() + $5 * interval '1 millisecond', \
+        delivered_at = case when $2 = 'delivered'::webhook_delivery_status then now() end \
+        where id = $1::uuid",
+    )
+    .bind(id)
+    .bind(to_status_entity(status))
+    .bind(response_status)
+    .bind(error)
+    .bind(retry_in_ms as f64)
+    .execute(&mut *db)
+    .await;
+
+    match record_result {
+        Ok(result) if result.rows_affected() == 0 => Err(WebhooksRepoError::NotFound),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            error!(
+                "[record_delivery_attempt] Error recording attempt: {:?}",
+                err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
+
+/// Time left until the next pending delivery is due, if there is any
+pub async fn get_next_attempt_in_ms(
+    db: &mut PgConnection,
+) -> Result<Option<i64>, WebhooksRepoError> {
+    let get_result = sqlx::query_scalar::<_, Option<f64>>(
+        "select (extract(epoch from min(d.next_attempt_at) - now()) * 1000)::float8 from webhook_deliveries d where d.status = 'pending'",
+    )
+    .fetch_one(&mut *db)
+    .await;
+
+    match get_result {
+        Ok(next) => Ok(next.map(|ms| ms.max(0.0) as i64)),
+        Err(err) => {
+            error!(
+                "[get_next_attempt_in_ms] Error retrieving next attempt: {:?}",
+                err
+            );
+            Err(map_sqlx_error(err))
+        }
+    }
+}
@@ -1 +1 @@
-pub mod v1;
\ No newline at end of file
+pub mod v1;
@@ -0,0 +1,174 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::api_key::ApiKey;
+use crate::models::principal::Principal;
+use crate::services::api_keys_service::{self, ApiKeysServiceError};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{delete, get, post};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+use super::guards::Tenant;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetApiKeyDto {
+    pub name: String,
+    /// Public part of the key, to tell keys apart
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateApiKeyDto {
+    pub name: String,
+    /// Roles the key can exercise over the tenant, each being one of `reader`, `writer` or `admin`
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreatedApiKeyDto {
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Plaintext key to send in the `X-Api-Key` header. It is only shown once
+    pub key: String,
+}
+
+fn to_http_status(error: &ApiKeysServiceError) -> Status {
+    match error {
+        ApiKeysServiceError::AlreadyExists => Status::Conflict,
+        ApiKeysServiceError::ApiKeyNotFound => Status::NotFound,
+        ApiKeysServiceError::TenantNotFound => Status::NotFound,
+        ApiKeysServiceError::InvalidSlug => Status::BadRequest,
+        ApiKeysServiceError::InvalidScopes => Status::BadRequest,
+        ApiKeysServiceError::InvalidExpiry => Status::BadRequest,
+        ApiKeysServiceError::InvalidApiKey => Status::Unauthorized,
+        ApiKeysServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+fn to_scope_names(api_key: &ApiKey) -> Vec<String> {
+    api_key
+        .scopes
+        .iter()
+        .map(|scope| scope.name().to_string())
+        .collect()
+}
+
+#[derive(Responder)]
+#[response(status = 201, content_type = "json")]
+pub struct CreateApiKeySuccess(Json<CreatedApiKeyDto>);
+
+#[post("/v1/api-keys", format = "application/json", data = "<input>")]
+pub async fn create_api_key(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    input: Json<CreateApiKeyDto>,
+) -> Result<CreateApiKeySuccess, RoutesError> {
+    let result = api_keys_service::create_api_key(
+        db,
+        tenant.slug(),
+        &principal,
+        input.name.as_str(),
+        &input.scopes,
+        input.expires_at,
+    )
+    .await;
+
+    match result {
+        Ok(created) => Ok(CreateApiKeySuccess(Json(CreatedApiKeyDto {
+            scopes: to_scope_names(&created.api_key),
+            name: created.api_key.name,
+            prefix: created.api_key.prefix,
+            expires_at: created.api_key.expires_at,
+            created_at: created.api_key.created_at,
+            key: created.key,
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetApiKeysResponse(Json<PaginatedListDto<GetApiKeyDto>>);
+
+#[get("/v1/api-keys?<limit>&<offset>")]
+pub async fn get_api_keys(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetApiKeysResponse, RoutesError> {
+    let result = api_keys_service::get_api_keys(db, tenant.slug(), &principal, limit, offset).await;
+
+    match result {
+        Ok(api_keys) => {
+            let mut result = vec![];
+            for api_key in api_keys.items {
+                result.push(GetApiKeyDto {
+                    scopes: to_scope_names(&api_key),
+                    name: api_key.name,
+                    prefix: api_key.prefix,
+                    expires_at: api_key.expires_at,
+                    revoked_at: api_key.revoked_at,
+                    created_at: api_key.created_at,
+                });
+            }
+            Ok(GetApiKeysResponse(Json(PaginatedListDto {
+                data: result,
+                pagination: PaginationDto {
+                    count: api_keys.count,
+                    offset: api_keys.offset,
+                    limit: api_keys.limit,
+                    next: api_keys.next_offset.map(|next_offset| {
+                        format!(
+                            "/v1/api-keys?limit={}&offset={}",
+                            api_keys.limit, next_offset
+                        )
+                    }),
+                    prev: api_keys.prev_offset.map(|prev_offset| {
+                        format!(
+                            "/v1/api-keys?limit={}&offset={}",
+                            api_keys.limit, prev_offset
+                        )
+                    }),
+                },
+            })))
+        }
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 204, content_type = "json")]
+pub struct RevokeApiKeySuccess(());
+
+#[delete("/v1/api-keys/<name>")]
+pub async fn revoke_api_key(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    name: &str,
+) -> Result<RevokeApiKeySuccess, RoutesError> {
+    let result = api_keys_service::revoke_api_key(db, tenant.slug(), &principal, name).await;
+
+    match result {
+        Ok(()) => Ok(RevokeApiKeySuccess(())),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
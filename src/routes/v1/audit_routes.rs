@@ -0,0 +1,126 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::audit::{AuditEvent, AuditFilter};
+use crate::models::principal::Principal;
+use crate::services::audit_service::{self, AuditServiceError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rocket::get;
+use rocket::http::{RawStr, Status};
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+use super::guards::Tenant;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetAuditEventDto {
+    pub id: String,
+    pub actor: String,
+    pub action: String,
+    pub domain: String,
+    pub key: Option<String>,
+    pub environment: Option<String>,
+    pub before_hash: Option<String>,
+    pub after_hash: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn to_http_status(error: &AuditServiceError) -> Status {
+    match error {
+        AuditServiceError::InvalidSince => Status::BadRequest,
+        AuditServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+fn to_audit_event_dto(event: AuditEvent) -> GetAuditEventDto {
+    GetAuditEventDto {
+        id: event.id,
+        actor: event.actor,
+        action: event.action.name().to_string(),
+        domain: event.domain,
+        key: event.key,
+        environment: event.environment,
+        before_hash: event.before_hash,
+        after_hash: event.after_hash,
+        request_id: event.request_id,
+        created_at: event.created_at,
+    }
+}
+
+/// Link to another page of events, keeping the criteria they are filtered by
+fn to_page_link(filter: &AuditFilter, limit: i32, offset: i32) -> String {
+    let mut link = format!("/v1/audit?limit={}&offset={}", limit, offset);
+    let since = filter
+        .since
+        .map(|since| since.to_rfc3339_opts(SecondsFormat::AutoSi, true));
+    for (name, value) in [
+        ("domain", filter.domain.as_deref()),
+        ("key", filter.key.as_deref()),
+        ("actor", filter.actor.as_deref()),
+        ("since", since.as_deref()),
+    ] {
+        if let Some(value) = value {
+            link.push_str(format!("&{}={}", name, RawStr::new(value).percent_encode()).as_str());
+        }
+    }
+    link
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetAuditEventsResponse(Json<PaginatedListDto<GetAuditEventDto>>);
+
+#[get("/v1/audit?<domain>&<key>&<actor>&<since>&<limit>&<offset>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_audit_events(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain: Option<String>,
+    key: Option<String>,
+    actor: Option<String>,
+    since: Option<&str>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetAuditEventsResponse, RoutesError> {
+    let since = match since.map(DateTime::parse_from_rfc3339) {
+        Some(Ok(since)) => Some(since.with_timezone(&Utc)),
+        Some(Err(_)) => {
+            let err = AuditServiceError::InvalidSince;
+            return Err(RoutesError(to_http_status(&err), err.code(), err.message()));
+        }
+        None => None,
+    };
+    let filter = AuditFilter {
+        domain,
+        key,
+        actor,
+        since,
+    };
+
+    let result =
+        audit_service::get_audit_events(db, tenant.slug(), &principal, &filter, limit, offset)
+            .await;
+
+    match result {
+        Ok(events) => Ok(GetAuditEventsResponse(Json(PaginatedListDto {
+            data: events.items.into_iter().map(to_audit_event_dto).collect(),
+            pagination: PaginationDto {
+                count: events.count,
+                offset: events.offset,
+                limit: events.limit,
+                next: events
+                    .next_offset
+                    .map(|next_offset| to_page_link(&filter, events.limit, next_offset)),
+                prev: events
+                    .prev_offset
+                    .map(|prev_offset| to_page_link(&filter, events.limit, prev_offset)),
+            },
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
@@ -1,5 +1,7 @@
 use crate::db::db::ConfigMonkeyDb;
 
+use crate::models::audit::Actor;
+use crate::models::principal::Principal;
 use crate::services::configs_service::{self, ConfigsServiceError};
 use chrono::{DateTime, Utc};
 use rocket::http::Status;
@@ -10,8 +12,9 @@ use rocket::serde::{json::Json, Deserialize, Serialize};
 use rocket::{delete, get, post};
 use rocket_db_pools::Connection;
 
-use super::dtos::{PaginatedListDto, PaginationDto};
+use super::dtos::{DeletionDto, PaginatedListDto, PaginationDto};
 use super::errors::RoutesError;
+use super::guards::Tenant;
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -32,33 +35,60 @@ fn to_http_status(error: &ConfigsServiceError) -> Status {
         ConfigsServiceError::ConfigNotFound => Status::NotFound,
         ConfigsServiceError::DomainNotFound => Status::NotFound,
         ConfigsServiceError::InvalidSlug => Status::BadRequest,
+        ConfigsServiceError::Forbidden => Status::Forbidden,
         _ => Status::InternalServerError,
     }
 }
 
 #[derive(Responder)]
-#[response(status = 201, content_type = "json")]
-pub struct CreateConfigSuccess(Json<GetConfigDto>);
+pub enum CreateConfigResponse {
+    #[response(status = 201, content_type = "json")]
+    Created(Json<GetConfigDto>),
+    /// Nothing was created, the body shows the config that would have been
+    #[response(status = 200, content_type = "json")]
+    DryRun(Json<GetConfigDto>),
+}
 
 #[post(
-    "/v1/configs/<domain_slug>",
+    "/v1/configs/<domain_slug>?<dry_run>",
     format = "application/json",
     data = "<input>"
 )]
 pub async fn create_config(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
     domain_slug: &str,
+    dry_run: Option<bool>,
     input: Json<CreateConfigDto>,
-) -> Result<CreateConfigSuccess, RoutesError> {
+) -> Result<CreateConfigResponse, RoutesError> {
     let key = input.key.as_str();
-
-    let result = configs_service::create_config(db, domain_slug, key).await;
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = configs_service::create_config(
+        db,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        key,
+        dry_run,
+    )
+    .await;
 
     match result {
-        Ok(config) => Ok(CreateConfigSuccess(Json(GetConfigDto {
-            key: config.key,
-            created_at: config.created_at,
-        }))),
+        Ok(config) => {
+            let dto = Json(GetConfigDto {
+                key: config.key,
+                created_at: config.created_at,
+            });
+            if dry_run {
+                Ok(CreateConfigResponse::DryRun(dto))
+            } else {
+                Ok(CreateConfigResponse::Created(dto))
+            }
+        }
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
     }
 }
@@ -70,17 +100,19 @@ pub struct GetConfigResponse(Json<GetConfigDto>);
 #[get("/v1/configs/<domain_slug>/<key>")]
 pub async fn get_config(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
     domain_slug: &str,
     key: &str,
 ) -> Result<GetConfigResponse, RoutesError> {
-    let result = configs_service::get_config(db, domain_slug, key).await;
-    return match result {
+    let result = configs_service::get_config(db, tenant.slug(), &principal, domain_slug, key).await;
+    match result {
         Ok(config) => Ok(GetConfigResponse(Json(GetConfigDto {
             key: config.key,
             created_at: config.created_at,
         }))),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
 }
 
 #[derive(Responder)]
@@ -90,12 +122,16 @@ pub struct GetConfigsResponse(Json<PaginatedListDto<GetConfigDto>>);
 #[get("/v1/configs/<domain_slug>?<limit>&<offset>")]
 pub async fn get_configs(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
     domain_slug: &str,
     limit: Option<i32>,
     offset: Option<i32>,
 ) -> Result<GetConfigsResponse, RoutesError> {
-    let result = configs_service::get_configs(db, domain_slug, limit, offset).await;
-    return match result {
+    let result =
+        configs_service::get_configs(db, tenant.slug(), &principal, domain_slug, limit, offset)
+            .await;
+    match result {
         Ok(configs) => {
             let mut result = vec![];
             for config in configs.items {
@@ -130,23 +166,47 @@ pub async fn get_configs(
             })))
         }
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
 }
 
 #[derive(Responder)]
-#[response(status = 204, content_type = "json")]
-pub struct DeleteConfigSuccess(());
+pub enum DeleteConfigResponse {
+    #[response(status = 204, content_type = "json")]
+    Deleted(()),
+    /// Nothing was deleted, the body shows what would have been
+    #[response(status = 200, content_type = "json")]
+    DryRun(Json<DeletionDto>),
+}
 
-#[delete("/v1/configs/<domain_slug>/<key>")]
+#[delete("/v1/configs/<domain_slug>/<key>?<dry_run>")]
 pub async fn delete_config(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
     domain_slug: &str,
     key: &str,
-) -> Result<DeleteConfigSuccess, RoutesError> {
-    let result = configs_service::delete_config(db, domain_slug, key).await;
+    dry_run: Option<bool>,
+) -> Result<DeleteConfigResponse, RoutesError> {
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = configs_service::delete_config(
+        db,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        key,
+        dry_run,
+    )
+    .await;
 
-    return match result {
-        Ok(()) => Ok(DeleteConfigSuccess(())),
+    match result {
+        Ok(deletion) if dry_run => Ok(DeleteConfigResponse::DryRun(Json(DeletionDto {
+            configs: deletion.configs,
+            versions: deletion.versions,
+        }))),
+        Ok(_) => Ok(DeleteConfigResponse::Deleted(())),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
 }
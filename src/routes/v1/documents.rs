@@ -0,0 +1,52 @@
+use std::io::Cursor;
+
+use rocket::{
+    http::{Accept, Status},
+    response::Responder,
+    serde::json::{serde_json::Map, Value},
+    Request, Response,
+};
+
+use crate::shared::formats::{find_format, DocumentFormat, FORMATS};
+
+use super::errors::RoutesError;
+
+/// Responds with a configuration document, serialized in the most preferred format
+/// listed in the `Accept` header. Requests without one get the default format
+pub struct DocumentResponse(pub Map<String, Value>);
+
+fn negotiate_format(accept: Option<&Accept>) -> Option<&'static dyn DocumentFormat> {
+    let Some(accept) = accept else {
+        return Some(FORMATS[0]);
+    };
+
+    let mut media_types = accept.iter().collect::<Vec<_>>();
+    media_types.sort_by(|a, b| b.weight_or(1.0).total_cmp(&a.weight_or(1.0)));
+    media_types
+        .into_iter()
+        .filter(|media_type| media_type.weight_or(1.0) > 0.0)
+        .find_map(|media_type| find_format(media_type.media_type()))
+}
+
+impl<'r> Responder<'r, 'static> for DocumentResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let Some(format) = negotiate_format(request.accept()) else {
+            return RoutesError(
+                Status::NotAcceptable,
+                "not_acceptable",
+                "None of the requested formats is supported",
+            )
+            .respond_to(request);
+        };
+
+        match format.serialize(&self.0) {
+            Ok(body) => Response::build()
+                .header(format.content_type())
+                .status(Status::Ok)
+                .sized_body(body.len(), Cursor::new(body))
+                .ok(),
+            Err(err) => RoutesError(Status::UnprocessableEntity, err.code(), err.message())
+                .respond_to(request),
+        }
+    }
+}
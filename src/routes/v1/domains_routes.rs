@@ -1,20 +1,32 @@
 use crate::db::db::ConfigMonkeyDb;
-use crate::services::domains_service::{self, DomainsServiceError};
+use crate::models::audit::Actor;
+use crate::models::config::{ConfigEntry, ConfigValue};
+use crate::models::principal::Principal;
+use crate::services::domains_service::{
+    self, DomainsServiceError, ImportReport, Promotion, PromotionStatus,
+};
+use crate::shared::formats::{find_format, FormatError};
+use crate::shared::secrets::SecretsCipher;
 use chrono::{DateTime, Utc};
-use rocket::http::Status;
+use rocket::data::{Data, Limits, ToByteUnit};
+use rocket::http::{ContentType, Status};
 use rocket::response::Responder;
-use rocket::serde::json::Json;
+use rocket::serde::json::{serde_json::Map, Json, Value};
 use rocket::serde::{Deserialize, Serialize};
-use rocket::{delete, get, post};
+use rocket::{delete, get, post, put, State};
 use rocket_db_pools::Connection;
 
-use super::dtos::{PaginatedListDto, PaginationDto};
+use super::documents::DocumentResponse;
+use super::dtos::{DeletionDto, PaginatedListDto, PaginationDto};
 use super::errors::RoutesError;
+use super::guards::Tenant;
+use super::versions_routes::{to_value_diff_dto, ValueDiffDto};
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct GetDomainDto {
     pub slug: String,
+    pub parent: Option<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -22,6 +34,55 @@ pub struct GetDomainDto {
 #[serde(crate = "rocket::serde")]
 pub struct CreateDomainDto {
     slug: String,
+    parent: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct SetDomainParentDto {
+    parent: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportFailureDto {
+    pub key: String,
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ImportReportDto {
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub failed: Vec<ImportFailureDto>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PromoteDomainDto {
+    to: String,
+    keys: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PromotionDto {
+    pub key: String,
+    /// One of `created`, `updated` or `unchanged`
+    pub status: String,
+    pub version: Option<i32>,
+    pub diff: Option<ValueDiffDto>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct PromotionReportDto {
+    pub from: String,
+    pub to: String,
+    pub configs: Vec<PromotionDto>,
 }
 
 fn to_http_status(error: &DomainsServiceError) -> Status {
@@ -30,6 +91,13 @@ fn to_http_status(error: &DomainsServiceError) -> Status {
         DomainsServiceError::InvalidSlug => Status::BadRequest,
         DomainsServiceError::NotFound => Status::NotFound,
         DomainsServiceError::NotEmpty => Status::UnprocessableEntity,
+        DomainsServiceError::HasChildren => Status::UnprocessableEntity,
+        DomainsServiceError::ParentNotFound => Status::UnprocessableEntity,
+        DomainsServiceError::ParentCycle => Status::UnprocessableEntity,
+        DomainsServiceError::TargetNotFound => Status::UnprocessableEntity,
+        DomainsServiceError::KeyNotFound => Status::UnprocessableEntity,
+        DomainsServiceError::TenantNotFound => Status::NotFound,
+        DomainsServiceError::Forbidden => Status::Forbidden,
         _ => Status::InternalServerError,
     }
 }
@@ -41,17 +109,29 @@ pub struct CreateDomainSuccess(Json<GetDomainDto>);
 #[post("/v1/domains", format = "application/json", data = "<input>")]
 pub async fn create_domain(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
     input: Json<CreateDomainDto>,
 ) -> Result<CreateDomainSuccess, RoutesError> {
-    let result = domains_service::create_domain(db, input.slug.as_str()).await;
+    let result = domains_service::create_domain(
+        db,
+        tenant.slug(),
+        &principal,
+        &actor,
+        input.slug.as_str(),
+        input.parent.as_deref(),
+    )
+    .await;
 
-    return match result {
+    match result {
         Ok(domain) => Ok(CreateDomainSuccess(Json(GetDomainDto {
             slug: domain.slug,
+            parent: domain.parent,
             created_at: domain.created_at,
         }))),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
 }
 
 #[derive(Responder)]
@@ -61,17 +141,20 @@ pub struct GetDomainsResponse(Json<PaginatedListDto<GetDomainDto>>);
 #[get("/v1/domains?<limit>&<offset>")]
 pub async fn get_domains(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
     limit: Option<i32>,
     offset: Option<i32>,
 ) -> Result<GetDomainsResponse, RoutesError> {
-    let result = domains_service::get_domains(db, limit, offset).await;
+    let result = domains_service::get_domains(db, tenant.slug(), &principal, limit, offset).await;
 
-    return match result {
+    match result {
         Ok(domains) => {
             let mut result = vec![];
             for domain in domains.items {
                 result.push(GetDomainDto {
                     slug: domain.slug,
+                    parent: domain.parent,
                     created_at: domain.created_at,
                 });
             }
@@ -101,22 +184,258 @@ pub async fn get_domains(
             })))
         }
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
 }
 
 #[derive(Responder)]
-#[response(status = 204, content_type = "json")]
-pub struct DeleteDomainSuccess(());
+pub enum DeleteDomainResponse {
+    #[response(status = 204, content_type = "json")]
+    Deleted(()),
+    /// Nothing was deleted, the body shows what would have been
+    #[response(status = 200, content_type = "json")]
+    DryRun(Json<DeletionDto>),
+}
 
-#[delete("/v1/domains/<slug>")]
+#[delete("/v1/domains/<slug>?<dry_run>")]
 pub async fn delete_domain(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    slug: &str,
+    dry_run: Option<bool>,
+) -> Result<DeleteDomainResponse, RoutesError> {
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result =
+        domains_service::delete_domain(db, tenant.slug(), &principal, &actor, slug, dry_run).await;
+
+    match result {
+        Ok(deletion) if dry_run => Ok(DeleteDomainResponse::DryRun(Json(DeletionDto {
+            configs: deletion.configs,
+            versions: deletion.versions,
+        }))),
+        Ok(_) => Ok(DeleteDomainResponse::Deleted(())),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct SetDomainParentResponse(Json<GetDomainDto>);
+
+#[put(
+    "/v1/domains/<slug>/parent",
+    format = "application/json",
+    data = "<input>"
+)]
+pub async fn set_domain_parent(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
     slug: &str,
-) -> Result<DeleteDomainSuccess, RoutesError> {
-    let result = domains_service::delete_domain(db, slug).await;
+    input: Json<SetDomainParentDto>,
+) -> Result<SetDomainParentResponse, RoutesError> {
+    let result = domains_service::set_domain_parent(
+        db,
+        tenant.slug(),
+        &principal,
+        slug,
+        input.parent.as_deref(),
+    )
+    .await;
 
-    return match result {
-        Ok(()) => Ok(DeleteDomainSuccess(())),
+    match result {
+        Ok(domain) => Ok(SetDomainParentResponse(Json(GetDomainDto {
+            slug: domain.slug,
+            parent: domain.parent,
+            created_at: domain.created_at,
+        }))),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+/// Build a configuration document mapping every key to its value
+fn to_document(entries: Vec<ConfigEntry>) -> Map<String, Value> {
+    let mut document = Map::new();
+    for entry in entries {
+        document.insert(entry.key, Value::from(entry.version.value));
+    }
+    document
+}
+
+#[get("/v1/domains/<slug>/render")]
+pub async fn render_domain(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    slug: &str,
+) -> Result<DocumentResponse, RoutesError> {
+    export_domain(db, tenant, principal, secrets, slug).await
+}
+
+#[get("/v1/domains/<slug>/export")]
+pub async fn export_domain(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    slug: &str,
+) -> Result<DocumentResponse, RoutesError> {
+    let result = domains_service::render_domain(db, secrets, tenant.slug(), &principal, slug).await;
+
+    match result {
+        Ok(entries) => Ok(DocumentResponse(to_document(entries))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+fn to_import_report_dto(report: ImportReport) -> ImportReportDto {
+    ImportReportDto {
+        created: report.created,
+        updated: report.updated,
+        unchanged: report.unchanged,
+        failed: report
+            .failed
+            .into_iter()
+            .map(|(key, err)| ImportFailureDto {
+                key,
+                code: err.code().to_string(),
+                message: err.message().to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn format_error(error: FormatError) -> RoutesError {
+    let status = match error {
+        FormatError::InvalidDocument => Status::BadRequest,
+        FormatError::Unsupported => Status::UnsupportedMediaType,
+        _ => Status::InternalServerError,
+    };
+    RoutesError(status, error.code(), error.message())
+}
+
+/// Default size limit for imported documents, configurable through the `import` limit
+const DEFAULT_IMPORT_LIMIT: u32 = 1;
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct ImportDomainResponse(Json<ImportReportDto>);
+
+#[post("/v1/domains/<slug>/import?<dry_run>", data = "<data>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn import_domain(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    slug: &str,
+    dry_run: Option<bool>,
+    content_type: Option<&ContentType>,
+    limits: &Limits,
+    data: Data<'_>,
+) -> Result<ImportDomainResponse, RoutesError> {
+    let format = content_type
+        .and_then(|content_type| find_format(content_type.media_type()))
+        .ok_or_else(|| format_error(FormatError::Unsupported))?;
+
+    let limit = limits
+        .get("import")
+        .unwrap_or(DEFAULT_IMPORT_LIMIT.mebibytes());
+    let body = match data.open(limit).into_string().await {
+        Ok(body) if body.is_complete() => body.into_inner(),
+        Ok(_) => {
+            return Err(RoutesError(
+                Status::PayloadTooLarge,
+                "payload_too_large",
+                "The document exceeds the import size limit",
+            ))
+        }
+        Err(_) => return Err(format_error(FormatError::InvalidDocument)),
     };
+
+    let document = format.parse(body.as_str()).map_err(format_error)?;
+    let entries = document
+        .into_iter()
+        .map(|(key, value)| (key, ConfigValue::from(value)))
+        .collect();
+
+    let result = domains_service::import_domain(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        slug,
+        entries,
+        dry_run.unwrap_or(false),
+    )
+    .await;
+
+    match result {
+        Ok(report) => Ok(ImportDomainResponse(Json(to_import_report_dto(report)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+fn to_promotion_dto(promotion: Promotion) -> PromotionDto {
+    PromotionDto {
+        key: promotion.key,
+        status: match promotion.status {
+            PromotionStatus::Created => "created",
+            PromotionStatus::Updated => "updated",
+            PromotionStatus::Unchanged => "unchanged",
+        }
+        .to_string(),
+        version: promotion.version,
+        diff: promotion.diff.map(to_value_diff_dto),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct PromoteDomainResponse(Json<PromotionReportDto>);
+
+#[post(
+    "/v1/domains/<slug>/promote?<dry_run>",
+    format = "application/json",
+    data = "<input>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn promote_domain(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    slug: &str,
+    dry_run: Option<bool>,
+    input: Json<PromoteDomainDto>,
+) -> Result<PromoteDomainResponse, RoutesError> {
+    let PromoteDomainDto { to, keys } = input.into_inner();
+
+    let result = domains_service::promote_domain(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        slug,
+        to.as_str(),
+        keys,
+        dry_run.unwrap_or(false),
+    )
+    .await;
+
+    match result {
+        Ok(promotions) => Ok(PromoteDomainResponse(Json(PromotionReportDto {
+            from: slug.to_string(),
+            to,
+            configs: promotions.into_iter().map(to_promotion_dto).collect(),
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
 }
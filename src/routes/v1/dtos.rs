@@ -23,3 +23,10 @@ pub struct PaginatedListDto<T> {
     pub data: Vec<T>,
     pub pagination: PaginationDto,
 }
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DeletionDto {
+    pub configs: i64,
+    pub versions: i64,
+}
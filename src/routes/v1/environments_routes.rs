@@ -0,0 +1,150 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::principal::Principal;
+use crate::services::environments_service::{self, EnvironmentsServiceError};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{delete, get, post};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+use super::guards::Tenant;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetEnvironmentDto {
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateEnvironmentDto {
+    pub slug: String,
+}
+
+fn to_http_status(error: &EnvironmentsServiceError) -> Status {
+    match error {
+        EnvironmentsServiceError::AlreadyExists => Status::Conflict,
+        EnvironmentsServiceError::EnvironmentNotFound => Status::NotFound,
+        EnvironmentsServiceError::DomainNotFound => Status::NotFound,
+        EnvironmentsServiceError::InvalidSlug => Status::BadRequest,
+        EnvironmentsServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 201, content_type = "json")]
+pub struct CreateEnvironmentSuccess(Json<GetEnvironmentDto>);
+
+#[post(
+    "/v1/domains/<domain_slug>/environments",
+    format = "application/json",
+    data = "<input>"
+)]
+pub async fn create_environment(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    input: Json<CreateEnvironmentDto>,
+) -> Result<CreateEnvironmentSuccess, RoutesError> {
+    let result = environments_service::create_environment(
+        db,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        input.slug.as_str(),
+    )
+    .await;
+
+    match result {
+        Ok(environment) => Ok(CreateEnvironmentSuccess(Json(GetEnvironmentDto {
+            slug: environment.slug,
+            created_at: environment.created_at,
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetEnvironmentsResponse(Json<PaginatedListDto<GetEnvironmentDto>>);
+
+#[get("/v1/domains/<domain_slug>/environments?<limit>&<offset>")]
+pub async fn get_environments(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetEnvironmentsResponse, RoutesError> {
+    let result = environments_service::get_environments(
+        db,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        limit,
+        offset,
+    )
+    .await;
+
+    match result {
+        Ok(environments) => {
+            let mut result = vec![];
+            for environment in environments.items {
+                result.push(GetEnvironmentDto {
+                    slug: environment.slug,
+                    created_at: environment.created_at,
+                });
+            }
+            Ok(GetEnvironmentsResponse(Json(PaginatedListDto {
+                data: result,
+                pagination: PaginationDto {
+                    count: environments.count,
+                    offset: environments.offset,
+                    limit: environments.limit,
+                    next: environments.next_offset.map(|next_offset| {
+                        format!(
+                            "/v1/domains/{}/environments?limit={}&offset={}",
+                            domain_slug, environments.limit, next_offset
+                        )
+                    }),
+                    prev: environments.prev_offset.map(|prev_offset| {
+                        format!(
+                            "/v1/domains/{}/environments?limit={}&offset={}",
+                            domain_slug, environments.limit, prev_offset
+                        )
+                    }),
+                },
+            })))
+        }
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 204, content_type = "json")]
+pub struct DeleteEnvironmentSuccess(());
+
+#[delete("/v1/domains/<domain_slug>/environments/<slug>")]
+pub async fn delete_environment(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    slug: &str,
+) -> Result<DeleteEnvironmentSuccess, RoutesError> {
+    let result =
+        environments_service::delete_environment(db, tenant.slug(), &principal, domain_slug, slug)
+            .await;
+
+    match result {
+        Ok(()) => Ok(DeleteEnvironmentSuccess(())),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
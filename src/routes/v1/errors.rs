@@ -9,6 +9,8 @@ use rocket::{
 };
 
 use super::dtos::ErrorDto;
+use super::guards::Authentication;
+use crate::auth::AuthError;
 
 pub struct RoutesError(pub Status, pub &'static str, pub &'static str);
 
@@ -33,24 +35,40 @@ impl<'a> Responder<'a, 'static> for RoutesError {
 
 #[catch(404)]
 pub fn not_found() -> Json<ErrorDto> {
-    return Json(ErrorDto {
+    Json(ErrorDto {
         code: "not_found".to_string(),
         message: "Resource not found".to_string(),
-    });
+    })
+}
+
+#[catch(401)]
+pub fn unauthorized(request: &Request) -> RoutesError {
+    let error = match request.local_cache(|| -> Authentication { Err(AuthError::MissingToken) }) {
+        Err(err) => err.clone(),
+        Ok(_) => AuthError::MissingToken,
+    };
+    RoutesError(Status::Unauthorized, error.code(), error.message())
+}
+
+/// Only raised by guards, as routes respond to forbidden requests with their own errors
+#[catch(403)]
+pub fn forbidden() -> RoutesError {
+    let error = AuthError::TenantMismatch;
+    RoutesError(Status::Forbidden, error.code(), error.message())
 }
 
 #[catch(400)]
 pub fn bad_request() -> Json<ErrorDto> {
-    return Json(ErrorDto {
+    Json(ErrorDto {
         code: "bad_request".to_string(),
         message: "Unable to parse input parameters".to_string(),
-    });
+    })
 }
 
 #[catch(default)]
 pub fn default_catcher() -> Json<ErrorDto> {
-    return Json(ErrorDto {
+    Json(ErrorDto {
         code: "unknown".to_string(),
         message: "Unknown Error".to_string(),
-    });
+    })
 }
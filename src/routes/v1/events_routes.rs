@@ -0,0 +1,95 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::db::notifications::Notifier;
+use crate::models::event::DomainEvent;
+use crate::models::principal::Principal;
+use crate::services::events_service::{self, EventsServiceError};
+use crate::shared::secrets::SecretsCipher;
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::{json::Value, Deserialize, Serialize};
+use rocket::{get, tokio::select, Shutdown, State};
+
+use super::errors::RoutesError;
+use super::guards::{LastEventId, Tenant};
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DomainEventDto {
+    pub kind: String,
+    pub key: String,
+    pub environment: Option<String>,
+    pub version: Option<i32>,
+    pub value: Option<Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn to_http_status(error: &EventsServiceError) -> Status {
+    match error {
+        EventsServiceError::DomainNotFound => Status::NotFound,
+        EventsServiceError::InvalidLastEventId => Status::BadRequest,
+        EventsServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+/// Server-sent event named after the kind of change, identified by its position so that
+/// clients can resume from it. Secrets are redacted, as everywhere values are shown
+fn to_event(event: DomainEvent) -> Event {
+    let id = event.id.to_string();
+    let kind = event.kind.name();
+    let dto = DomainEventDto {
+        kind: kind.to_string(),
+        key: event.key,
+        environment: event.environment,
+        version: event.version,
+        value: event.value.map(Value::from),
+        created_at: event.created_at,
+    };
+    Event::json(&dto).id(id).event(kind)
+}
+
+/// Stream the changes to the configs of a domain as they are made. Clients reconnecting with
+/// the `Last-Event-ID` header are first sent the changes they missed
+#[get("/v1/domains/<slug>/events")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_domain_events(
+    db: &ConfigMonkeyDb,
+    notifier: &State<Notifier>,
+    secrets: &State<SecretsCipher>,
+    tenant: Tenant,
+    principal: Principal,
+    last_event_id: LastEventId,
+    slug: &str,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], RoutesError> {
+    let result = events_service::follow_domain_events(
+        db,
+        notifier,
+        secrets,
+        tenant.slug(),
+        &principal,
+        slug,
+        last_event_id.0.as_deref(),
+    )
+    .await;
+
+    let mut events = match result {
+        Ok(events) => events,
+        Err(err) => return Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    };
+    Ok(EventStream! {
+        loop {
+            let batch = select! {
+                batch = events.next() => match batch {
+                    Ok(batch) => batch,
+                    Err(_) => break,
+                },
+                _ = &mut shutdown => break,
+            };
+            for event in batch {
+                yield to_event(event);
+            }
+        }
+    })
+}
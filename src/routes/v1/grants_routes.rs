@@ -0,0 +1,151 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::grant::Grant;
+use crate::models::principal::{Principal, Role};
+use crate::services::grants_service::{self, GrantsServiceError};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{delete, get, post};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+use super::guards::Tenant;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetGrantDto {
+    pub principal: String,
+    pub role: String,
+    /// Domain the role is granted over, or none when granted over the whole tenant
+    pub domain: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateGrantDto {
+    pub principal: String,
+    /// One of `reader`, `writer` or `admin`
+    pub role: String,
+    pub domain: Option<String>,
+}
+
+fn to_http_status(error: &GrantsServiceError) -> Status {
+    match error {
+        GrantsServiceError::AlreadyExists => Status::Conflict,
+        GrantsServiceError::InvalidPrincipal => Status::BadRequest,
+        GrantsServiceError::InvalidRole => Status::BadRequest,
+        GrantsServiceError::GrantNotFound => Status::NotFound,
+        GrantsServiceError::DomainNotFound => Status::NotFound,
+        GrantsServiceError::TenantNotFound => Status::NotFound,
+        GrantsServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+fn to_grant_dto(grant: Grant) -> GetGrantDto {
+    GetGrantDto {
+        principal: grant.principal,
+        role: grant.role.name().to_string(),
+        domain: grant.domain,
+        created_at: grant.created_at,
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 201, content_type = "json")]
+pub struct CreateGrantSuccess(Json<GetGrantDto>);
+
+#[post("/v1/grants", format = "application/json", data = "<input>")]
+pub async fn create_grant(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    input: Json<CreateGrantDto>,
+) -> Result<CreateGrantSuccess, RoutesError> {
+    let role = match Role::from_name(input.role.as_str()) {
+        Some(role) => role,
+        None => {
+            let err = GrantsServiceError::InvalidRole;
+            return Err(RoutesError(to_http_status(&err), err.code(), err.message()));
+        }
+    };
+
+    let result = grants_service::create_grant(
+        db,
+        tenant.slug(),
+        &principal,
+        input.domain.as_deref(),
+        input.principal.as_str(),
+        role,
+    )
+    .await;
+
+    match result {
+        Ok(grant) => Ok(CreateGrantSuccess(Json(to_grant_dto(grant)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetGrantsResponse(Json<PaginatedListDto<GetGrantDto>>);
+
+#[get("/v1/grants?<domain>&<limit>&<offset>")]
+pub async fn get_grants(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain: Option<&str>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetGrantsResponse, RoutesError> {
+    let result =
+        grants_service::get_grants(db, tenant.slug(), &principal, domain, limit, offset).await;
+
+    match result {
+        Ok(grants) => {
+            let path = match domain {
+                Some(domain) => format!("/v1/grants?domain={}&", domain),
+                None => String::from("/v1/grants?"),
+            };
+            Ok(GetGrantsResponse(Json(PaginatedListDto {
+                data: grants.items.into_iter().map(to_grant_dto).collect(),
+                pagination: PaginationDto {
+                    count: grants.count,
+                    offset: grants.offset,
+                    limit: grants.limit,
+                    next: grants.next_offset.map(|next_offset| {
+                        format!("{}limit={}&offset={}", path, grants.limit, next_offset)
+                    }),
+                    prev: grants.prev_offset.map(|prev_offset| {
+                        format!("{}limit={}&offset={}", path, grants.limit, prev_offset)
+                    }),
+                },
+            })))
+        }
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 204, content_type = "json")]
+pub struct DeleteGrantSuccess(());
+
+#[delete("/v1/grants?<principal>&<domain>")]
+pub async fn delete_grant(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    caller: Principal,
+    principal: &str,
+    domain: Option<&str>,
+) -> Result<DeleteGrantSuccess, RoutesError> {
+    let result = grants_service::delete_grant(db, tenant.slug(), &caller, domain, principal).await;
+
+    match result {
+        Ok(()) => Ok(DeleteGrantSuccess(())),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{
+    auth::{access_control::AccessControl, jwt::Claims, AuthError, Authenticator},
+    db::db::ConfigMonkeyDb,
+    models::{
+        api_key::ApiKey,
+        audit::Actor,
+        principal::{Principal, Role},
+    },
+    services::api_keys_service::{self, ApiKeysServiceError},
+};
+use rocket::{
+    error,
+    http::Status,
+    outcome::try_outcome,
+    request::{FromRequest, Outcome, Request},
+};
+use rocket_db_pools::Database;
+use std::convert::Infallible;
+
+/// Tenant of the requests that do not name one
+pub const DEFAULT_TENANT: &str = "default";
+
+/// What a request authenticated with
+#[derive(Clone)]
+pub enum Credentials {
+    Token(Claims),
+    ApiKey(ApiKey),
+}
+
+/// Outcome of authenticating a request, cached so that it is verified once per request
+/// and can be reported by the catchers
+pub type Authentication = Result<Option<Credentials>, AuthError>;
+
+/// Caller of a request, as identified by its API key or bearer token. Without authentication,
+/// requests that do not carry an API key are anonymous and hold no credentials
+pub struct Authenticated(pub Option<Credentials>);
+
+/// Verify the key carried in the `X-Api-Key` header. Keys are accepted whether bearer tokens
+/// are required or not
+async fn authenticate_api_key(request: &Request<'_>, key: &str) -> Authentication {
+    let mut db = match ConfigMonkeyDb::fetch(request.rocket()) {
+        Some(pool) => match pool.acquire().await {
+            Ok(db) => db,
+            Err(err) => {
+                error!(
+                    "Error acquiring a connection to verify an api key: {:?}",
+                    err
+                );
+                return Err(AuthError::InvalidApiKey);
+            }
+        },
+        None => return Err(AuthError::InvalidApiKey),
+    };
+    match api_keys_service::authenticate_api_key(&mut db, key).await {
+        Ok(api_key) => Ok(Some(Credentials::ApiKey(api_key))),
+        Err(ApiKeysServiceError::InvalidApiKey) => Err(AuthError::InvalidApiKey),
+        Err(err) => {
+            error!("Error verifying an api key: {}", err.message());
+            Err(AuthError::InvalidApiKey)
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Authenticated {
+    type Error = AuthError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let authentication: &Authentication = request
+            .local_cache_async(async {
+                if let Some(key) = request.headers().get_one("X-Api-Key") {
+                    return authenticate_api_key(request, key).await;
+                }
+                match request.rocket().state::<Authenticator>() {
+                    Some(authenticator) => authenticator
+                        .authenticate(request.headers().get_one("Authorization"))
+                        .map(|claims| claims.map(Credentials::Token)),
+                    None => Ok(None),
+                }
+            })
+            .await;
+        match authentication {
+            Ok(credentials) => Outcome::Success(Authenticated(credentials.clone())),
+            Err(err) => Outcome::Failure((Status::Unauthorized, err.clone())),
+        }
+    }
+}
+
+/// Tenant a request acts on, which is the tenant of the API key it authenticated with, or the
+/// `tenant` claim of its bearer token, the default tenant when the token has none. Requests naming
+/// another tenant in their `X-Tenant` header are rejected. Unauthenticated requests act on the
+/// tenant named in that header, which is only to be trusted behind a gateway that sets it.
+/// Domains, configs and versions of other tenants are neither visible nor writable through the
+/// request
+pub struct Tenant(String);
+
+impl Tenant {
+    pub fn slug(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Tenant {
+    type Error = AuthError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Authenticated(credentials) = try_outcome!(request.guard::<Authenticated>().await);
+        let header = request.headers().get_one("X-Tenant");
+
+        let slug = match &credentials {
+            Some(Credentials::ApiKey(api_key)) => api_key.tenant.as_str(),
+            Some(Credentials::Token(claims)) => claims.tenant.as_deref().unwrap_or(DEFAULT_TENANT),
+            None => return Outcome::Success(Tenant(header.unwrap_or(DEFAULT_TENANT).to_string())),
+        };
+        match header {
+            Some(header) if header != slug => {
+                Outcome::Failure((Status::Forbidden, AuthError::TenantMismatch))
+            }
+            _ => Outcome::Success(Tenant(slug.to_string())),
+        }
+    }
+}
+
+/// Principal a request acts as. Requests authenticated with a bearer token act as its subject
+/// and the ones authenticated with an API key are limited to the scopes of the key, while the
+/// others name their principal in the `X-Principal` header, which is only to be trusted behind
+/// a gateway that sets it
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Principal {
+    type Error = AuthError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Authenticated(credentials) = try_outcome!(request.guard::<Authenticated>().await);
+
+        let name = match &credentials {
+            Some(Credentials::ApiKey(api_key)) => {
+                return Outcome::Success(Principal::ApiKey {
+                    name: api_key.name.clone(),
+                    // Keys are created with at least one scope
+                    scope: api_key.scopes.iter().max().copied().unwrap_or(Role::Reader),
+                });
+            }
+            Some(Credentials::Token(claims)) => Some(claims.sub.as_str()),
+            None => request.headers().get_one("X-Principal"),
+        };
+        match request.rocket().state::<AccessControl>() {
+            Some(access_control) => Outcome::Success(access_control.principal(name)),
+            None => Outcome::Success(Principal::Unrestricted),
+        }
+    }
+}
+
+/// Actor the changes made by a request are recorded on behalf of, named after its principal
+/// even when access control is disabled. Unauthenticated requests that do not name their
+/// principal may still name their author in the `X-Actor` header. Requests may carry their own
+/// `X-Request-Id` header, for their changes to be correlated with other logs
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Actor {
+    type Error = AuthError;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Authenticated(credentials) = try_outcome!(request.guard::<Authenticated>().await);
+
+        let name = match &credentials {
+            Some(Credentials::ApiKey(api_key)) => format!("api-key:{}", api_key.name),
+            Some(Credentials::Token(claims)) => claims.sub.clone(),
+            None => request
+                .headers()
+                .get_one("X-Principal")
+                .or_else(|| request.headers().get_one("X-Actor"))
+                .unwrap_or("anonymous")
+                .to_string(),
+        };
+        Outcome::Success(Actor {
+            name,
+            request_id: request
+                .headers()
+                .get_one("X-Request-Id")
+                .map(str::to_string),
+        })
+    }
+}
+
+/// Id of the last event received by a client resuming a stream of events, carried in the
+/// `Last-Event-ID` header it sends when reconnecting
+pub struct LastEventId(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(LastEventId(
+            request
+                .headers()
+                .get_one("Last-Event-ID")
+                .map(str::to_string),
+        ))
+    }
+}
+
+/// Versions a client expects a config to be at before changing it, carried in the `If-Match`
+/// header of the request as the `ETag` it was given when reading the value
+pub struct IfMatch(pub Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(IfMatch(
+            request.headers().get_one("If-Match").map(str::to_string),
+        ))
+    }
+}
@@ -1,5 +1,15 @@
+pub mod api_keys_routes;
+pub mod audit_routes;
 pub mod configs_routes;
+pub mod documents;
 pub mod domains_routes;
-pub mod versions_routes;
 pub mod dtos;
+pub mod environments_routes;
 pub mod errors;
+pub mod events_routes;
+pub mod grants_routes;
+pub mod guards;
+pub mod reencryptions_routes;
+pub mod tenants_routes;
+pub mod versions_routes;
+pub mod webhooks_routes;
@@ -0,0 +1,89 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::principal::Principal;
+use crate::models::reencryption::Reencryption;
+use crate::services::reencryptions_service::{self, ReencryptionsServiceError};
+use crate::shared::secrets::SecretsCipher;
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{get, post, State};
+
+use super::errors::RoutesError;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetReencryptionDto {
+    pub id: String,
+    pub key_id: String,
+    pub status: String,
+    pub total: i32,
+    pub processed: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+fn to_http_status(error: &ReencryptionsServiceError) -> Status {
+    match error {
+        ReencryptionsServiceError::NotFound => Status::NotFound,
+        ReencryptionsServiceError::AlreadyRunning => Status::Conflict,
+        ReencryptionsServiceError::SecretsDisabled => Status::UnprocessableEntity,
+        ReencryptionsServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+fn to_reencryption_dto(reencryption: Reencryption) -> GetReencryptionDto {
+    GetReencryptionDto {
+        id: reencryption.id,
+        key_id: reencryption.key_id,
+        status: reencryption.status.name().to_string(),
+        total: reencryption.total,
+        processed: reencryption.processed,
+        created_at: reencryption.created_at,
+        updated_at: reencryption.updated_at,
+        finished_at: reencryption.finished_at,
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 202, content_type = "json")]
+pub struct StartReencryptionSuccess(Json<GetReencryptionDto>);
+
+/// Re-encrypt every stored value under the active master key, in the background
+#[post("/v1/reencryptions")]
+pub async fn start_reencryption(
+    db: &ConfigMonkeyDb,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+) -> Result<StartReencryptionSuccess, RoutesError> {
+    let result = reencryptions_service::start_reencryption(db, &principal, secrets).await;
+
+    match result {
+        Ok(reencryption) => Ok(StartReencryptionSuccess(Json(to_reencryption_dto(
+            reencryption,
+        )))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetReencryptionResponse(Json<GetReencryptionDto>);
+
+#[get("/v1/reencryptions/<id>")]
+pub async fn get_reencryption(
+    db: &ConfigMonkeyDb,
+    principal: Principal,
+    id: &str,
+) -> Result<GetReencryptionResponse, RoutesError> {
+    let result = reencryptions_service::get_reencryption(db, &principal, id).await;
+
+    match result {
+        Ok(reencryption) => Ok(GetReencryptionResponse(Json(to_reencryption_dto(
+            reencryption,
+        )))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
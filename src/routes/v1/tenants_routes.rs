@@ -0,0 +1,96 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::principal::Principal;
+use crate::services::tenants_service::{self, TenantsServiceError};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{get, post};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetTenantDto {
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateTenantDto {
+    pub slug: String,
+}
+
+fn to_http_status(error: &TenantsServiceError) -> Status {
+    match error {
+        TenantsServiceError::AlreadyExists => Status::Conflict,
+        TenantsServiceError::InvalidSlug => Status::BadRequest,
+        TenantsServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 201, content_type = "json")]
+pub struct CreateTenantSuccess(Json<GetTenantDto>);
+
+#[post("/v1/tenants", format = "application/json", data = "<input>")]
+pub async fn create_tenant(
+    db: Connection<ConfigMonkeyDb>,
+    principal: Principal,
+    input: Json<CreateTenantDto>,
+) -> Result<CreateTenantSuccess, RoutesError> {
+    let result = tenants_service::create_tenant(db, &principal, input.slug.as_str()).await;
+
+    match result {
+        Ok(tenant) => Ok(CreateTenantSuccess(Json(GetTenantDto {
+            slug: tenant.slug,
+            created_at: tenant.created_at,
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetTenantsResponse(Json<PaginatedListDto<GetTenantDto>>);
+
+#[get("/v1/tenants?<limit>&<offset>")]
+pub async fn get_tenants(
+    db: Connection<ConfigMonkeyDb>,
+    principal: Principal,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetTenantsResponse, RoutesError> {
+    let result = tenants_service::get_tenants(db, &principal, limit, offset).await;
+
+    match result {
+        Ok(tenants) => {
+            let mut result = vec![];
+            for tenant in tenants.items {
+                result.push(GetTenantDto {
+                    slug: tenant.slug,
+                    created_at: tenant.created_at,
+                });
+            }
+            Ok(GetTenantsResponse(Json(PaginatedListDto {
+                data: result,
+                pagination: PaginationDto {
+                    count: tenants.count,
+                    offset: tenants.offset,
+                    limit: tenants.limit,
+                    next: tenants.next_offset.map(|next_offset| {
+                        format!("/v1/tenants?limit={}&offset={}", tenants.limit, next_offset)
+                    }),
+                    prev: tenants.prev_offset.map(|prev_offset| {
+                        format!("/v1/tenants?limit={}&offset={}", tenants.limit, prev_offset)
+                    }),
+                },
+            })))
+        }
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
@@ -1,20 +1,29 @@
 use crate::db::db::ConfigMonkeyDb;
-use crate::models::config::ConfigValue;
+use crate::db::notifications::Notifier;
+use crate::models::audit::Actor;
+use crate::models::config::{
+    ConfigValue, ConfigVersion, ResolvedVersion, ValueDiff, VersionChange,
+};
+use crate::models::list::List;
+use crate::models::principal::Principal;
 use crate::services::versions_service::{self, VersionsServiceError};
+use crate::shared::json_pointer::unescape_token;
+use crate::shared::secrets::{SecretsCipher, SecretsError};
 use chrono::{DateTime, Utc};
-use rocket::http::Status;
+use rocket::http::uri::{fmt::Path, Segments};
+use rocket::http::{Header, Status};
 use rocket::response::Responder;
-use rocket::serde::json::json;
 use rocket::serde::{
     json::{Json, Value},
     Deserialize, Serialize,
 };
 
-use rocket::{get, post};
+use rocket::{delete, get, post, put, Request, State};
 use rocket_db_pools::Connection;
 
 use super::dtos::{PaginatedListDto, PaginationDto};
 use super::errors::RoutesError;
+use super::guards::{IfMatch, Tenant};
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
@@ -22,47 +31,140 @@ pub struct GetVersionDto {
     pub id: i32,
     pub created_at: DateTime<Utc>,
     pub value: Value,
+    pub restored_from: Option<i32>,
+    pub author: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetValueDto {
+    /// Domain holding the value, which is an ancestor of the requested one when inherited
+    pub domain: String,
+    pub version: i32,
+    pub value: Value,
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct CreateVersionDto {
     pub value: Value,
+    /// Store the value encrypted, and redact it wherever it is shown
+    #[serde(default)]
+    pub secret: bool,
+    /// Why the version is created, kept alongside it like a commit message
+    pub message: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ChangeDto<T> {
+    pub from: T,
+    pub to: T,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ValueDiffDto {
+    pub type_change: Option<ChangeDto<String>>,
+    pub value_change: Option<ChangeDto<Value>>,
+    pub patch: Option<Vec<Value>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VersionPreviewDto {
+    pub version: GetVersionDto,
+    /// Changes from the latest version, if the config already has one
+    pub diff: Option<ValueDiffDto>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VersionDiffDto {
+    pub from: i32,
+    pub to: i32,
+    pub diff: ValueDiffDto,
 }
 
 fn to_http_status(error: &VersionsServiceError) -> Status {
     match error {
         VersionsServiceError::ConfigNotFound => Status::NotFound,
         VersionsServiceError::DomainNotFound => Status::NotFound,
+        VersionsServiceError::EnvironmentNotFound => Status::NotFound,
+        VersionsServiceError::NoVersions => Status::NotFound,
+        VersionsServiceError::VersionNotFound => Status::NotFound,
+        VersionsServiceError::PropertyNotFound => Status::NotFound,
+        VersionsServiceError::NotADocument => Status::UnprocessableEntity,
+        VersionsServiceError::SecretProperty => Status::UnprocessableEntity,
+        VersionsServiceError::InvalidWait => Status::BadRequest,
+        VersionsServiceError::VersionMismatch => Status::PreconditionFailed,
+        VersionsServiceError::VersionConflict => Status::Conflict,
+        VersionsServiceError::Forbidden => Status::Forbidden,
         _ => Status::InternalServerError,
     }
 }
 
-fn to_value(config_value: ConfigValue) -> Value {
-    match config_value {
-        ConfigValue::String(v) => json!(v),
-        ConfigValue::Boolean(v) => json!(v),
-        ConfigValue::Integer(v) => json!(v),
-        ConfigValue::Float(v) => json!(v),
+fn secrets_error(error: SecretsError) -> RoutesError {
+    let status = match error {
+        SecretsError::Disabled => Status::UnprocessableEntity,
+        _ => Status::InternalServerError,
+    };
+    RoutesError(status, error.code(), error.message())
+}
+
+/// Build the value of a new version, sealing it when it is meant to be a secret
+fn to_config_value(
+    input: CreateVersionDto,
+    secrets: &SecretsCipher,
+) -> Result<ConfigValue, RoutesError> {
+    if !input.secret {
+        return Ok(ConfigValue::from(input.value));
+    }
+    match secrets.seal(&input.value) {
+        Ok(sealed_value) => Ok(ConfigValue::Secret(sealed_value)),
+        Err(err) => Err(secrets_error(err)),
     }
 }
 
-fn from_value(value: &Value) -> ConfigValue {
-    match value {
-        Value::Number(v) => {
-            if v.is_f64() {
-                ConfigValue::Float(v.as_f64().unwrap())
-            } else if v.is_i64() {
-                ConfigValue::Integer(v.as_i64().unwrap() as i64)
-            } else {
-                ConfigValue::Integer(v.as_u64().unwrap() as i64)
-            }
-        }
-        Value::String(v) => ConfigValue::String(v.to_string()),
-        Value::Bool(v) => ConfigValue::Boolean(*v),
-        _ => {
-            panic!("Value type not supported!");
-        }
+/// Show a resolved value in the clear, opening it when it is a secret
+fn to_revealed_value_dto(
+    resolved: ResolvedVersion,
+    secrets: &SecretsCipher,
+) -> Result<GetValueDto, RoutesError> {
+    let value = match resolved.version.value {
+        ConfigValue::Secret(sealed_value) => secrets.open(&sealed_value).map_err(secrets_error)?,
+        value => Value::from(value),
+    };
+    Ok(GetValueDto {
+        domain: resolved.domain,
+        version: resolved.version.version,
+        value,
+    })
+}
+
+fn to_version_dto(version: ConfigVersion) -> GetVersionDto {
+    GetVersionDto {
+        id: version.version,
+        created_at: version.created_at,
+        value: Value::from(version.value),
+        restored_from: version.restored_from,
+        author: version.author,
+        message: version.message,
+    }
+}
+
+pub fn to_value_diff_dto(diff: ValueDiff) -> ValueDiffDto {
+    ValueDiffDto {
+        type_change: diff.type_change.map(|(from, to)| ChangeDto {
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+        value_change: diff.value_change.map(|(from, to)| ChangeDto {
+            from: Value::from(from),
+            to: Value::from(to),
+        }),
+        patch: diff.patch,
     }
 }
 
@@ -70,27 +172,67 @@ fn from_value(value: &Value) -> ConfigValue {
 #[response(status = 201, content_type = "json")]
 pub struct CreateVersionSuccess(Json<GetVersionDto>);
 
+#[derive(Responder)]
+pub enum CreateVersionResponse {
+    #[response(status = 201, content_type = "json")]
+    Created(Json<GetVersionDto>),
+    /// Nothing was created, the body shows the version that would have been
+    #[response(status = 200, content_type = "json")]
+    DryRun(Json<VersionPreviewDto>),
+}
+
+fn to_create_version_response(change: VersionChange, dry_run: bool) -> CreateVersionResponse {
+    if dry_run {
+        CreateVersionResponse::DryRun(Json(VersionPreviewDto {
+            version: to_version_dto(change.version),
+            diff: change.diff.map(to_value_diff_dto),
+        }))
+    } else {
+        CreateVersionResponse::Created(Json(to_version_dto(change.version)))
+    }
+}
+
 #[post(
-    "/v1/configs/<domain_slug>/<key>/versions",
+    "/v1/configs/<domain_slug>/<key>/versions?<dry_run>",
     format = "application/json",
     data = "<input>"
 )]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_version(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
     domain_slug: &str,
     key: &str,
+    if_match: IfMatch,
+    dry_run: Option<bool>,
     input: Json<CreateVersionDto>,
-) -> Result<CreateVersionSuccess, RoutesError> {
-    let config_value = from_value(&input.value);
+) -> Result<CreateVersionResponse, RoutesError> {
+    let input = input.into_inner();
+    let message = input.message.clone();
+    let config_value = to_config_value(input, secrets)?;
+    let dry_run = dry_run.unwrap_or(false);
 
-    let result = versions_service::create_version(db, domain_slug, key, config_value).await;
+    let result = versions_service::create_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        None,
+        key,
+        config_value,
+        message.as_deref(),
+        if_match.0.as_deref(),
+        dry_run,
+    )
+    .await;
 
     match result {
-        Ok(version) => Ok(CreateVersionSuccess(Json(GetVersionDto {
-            id: version.version,
-            created_at: version.created_at,
-            value: to_value(version.value),
-        }))),
+        Ok(change) => Ok(to_create_version_response(change, dry_run)),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
     }
 }
@@ -99,50 +241,661 @@ pub async fn create_version(
 #[response(status = 200, content_type = "json")]
 pub struct GetVersionsResponse(Json<PaginatedListDto<GetVersionDto>>);
 
+/// Build a page of versions, linking to the previous and next pages of `path`
+fn to_versions_response(versions: List<ConfigVersion>, path: String) -> GetVersionsResponse {
+    let mut result = vec![];
+    for version in versions.items {
+        result.push(to_version_dto(version))
+    }
+    GetVersionsResponse(Json(PaginatedListDto {
+        data: result,
+        pagination: PaginationDto {
+            count: versions.count,
+            offset: versions.offset,
+            limit: versions.limit,
+            next: versions.next_offset.map(|next_offset| {
+                format!("{}?limit={}&offset={}", path, versions.limit, next_offset)
+            }),
+            prev: versions.prev_offset.map(|prev_offset| {
+                format!("{}?limit={}&offset={}", path, versions.limit, prev_offset)
+            }),
+        },
+    }))
+}
+
 #[get("/v1/configs/<domain_slug>/<key>/versions?<limit>&<offset>")]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_versions(
     db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
     domain_slug: &str,
     key: &str,
     limit: Option<i32>,
     offset: Option<i32>,
 ) -> Result<GetVersionsResponse, RoutesError> {
-    let result = versions_service::get_versions(db, domain_slug, key, limit, offset).await;
-    return match result {
-        Ok(versions) => {
-            let mut result = vec![];
-            for version in versions.items {
-                result.push(GetVersionDto {
-                    id: version.version,
-                    value: to_value(version.value),
-                    created_at: version.created_at,
-                })
-            }
-            Ok(GetVersionsResponse(Json(PaginatedListDto {
-                data: result,
-                pagination: PaginationDto {
-                    count: versions.count,
-                    offset: versions.offset,
-                    limit: versions.limit,
-                    next: if let Some(next_offset) = versions.next_offset {
-                        Some(format!(
-                            "/v1/configs/{}/{}/versions?limit={}&offset={}",
-                            domain_slug, key, versions.limit, next_offset
-                        ))
-                    } else {
-                        None
-                    },
-                    prev: if let Some(prev_offset) = versions.prev_offset {
-                        Some(format!(
-                            "/v1/configs/{}/{}/versions?limit={}&offset={}",
-                            domain_slug, key, versions.limit, prev_offset
-                        ))
-                    } else {
-                        None
-                    },
-                },
-            })))
+    let result = versions_service::get_versions(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        None,
+        key,
+        limit,
+        offset,
+    )
+    .await;
+    match result {
+        Ok(versions) => Ok(to_versions_response(
+            versions,
+            format!("/v1/configs/{}/{}/versions", domain_slug, key),
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetVersionResponse(Json<GetVersionDto>);
+
+#[get("/v1/configs/<domain_slug>/<key>/versions/<version>")]
+pub async fn get_version(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    version: i32,
+) -> Result<GetVersionResponse, RoutesError> {
+    let result = versions_service::get_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        None,
+        key,
+        version,
+    )
+    .await;
+
+    match result {
+        Ok(version) => Ok(GetVersionResponse(Json(to_version_dto(version)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetVersionDiffResponse(Json<VersionDiffDto>);
+
+#[get("/v1/configs/<domain_slug>/<key>/versions/diff?<from>&<to>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn diff_versions(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    from: i32,
+    to: i32,
+) -> Result<GetVersionDiffResponse, RoutesError> {
+    let result = versions_service::diff_versions(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        None,
+        key,
+        from,
+        to,
+    )
+    .await;
+
+    match result {
+        Ok(diff) => Ok(GetVersionDiffResponse(Json(VersionDiffDto {
+            from,
+            to,
+            diff: to_value_diff_dto(diff),
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[post("/v1/configs/<domain_slug>/<key>/versions/<version>/rollback")]
+#[allow(clippy::too_many_arguments)]
+pub async fn rollback_version(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    version: i32,
+) -> Result<CreateVersionSuccess, RoutesError> {
+    let result = versions_service::rollback_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        None,
+        key,
+        version,
+    )
+    .await;
+
+    match result {
+        Ok(version) => Ok(CreateVersionSuccess(Json(to_version_dto(version)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+/// Turn the path segments into JSON Pointer (RFC 6901) reference tokens
+fn to_pointer_tokens(path: Segments<'_, Path>) -> Vec<String> {
+    path.map(unescape_token).collect()
+}
+
+/// A value, along with the `ETag` naming its version for `If-Match` to be given when changing it.
+/// Values inherited from another domain are not tagged, as they cannot be changed from this one
+pub struct GetValueResponse(Json<GetValueDto>, Option<Header<'static>>);
+
+impl<'r> Responder<'r, 'static> for GetValueResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> rocket::response::Result<'static> {
+        let mut response = self.0.respond_to(request)?;
+        if let Some(etag) = self.1 {
+            response.set_header(etag);
         }
+        Ok(response)
+    }
+}
+
+fn to_value_response(value: GetValueDto, domain_slug: &str) -> GetValueResponse {
+    let etag = (value.domain == domain_slug)
+        .then(|| Header::new("ETag", format!("\"{}\"", value.version)));
+    GetValueResponse(Json(value), etag)
+}
+
+/// Get the latest value of a config. Given the version the caller already holds in
+/// `after_version`, hold the request for up to `wait` until the value changes
+#[get("/v1/configs/<domain_slug>/<key>/value?<wait>&<after_version>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_value(
+    db: &ConfigMonkeyDb,
+    notifier: &State<Notifier>,
+    secrets: &State<SecretsCipher>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    key: &str,
+    wait: Option<&str>,
+    after_version: Option<i32>,
+) -> Result<GetValueResponse, RoutesError> {
+    let result = versions_service::get_value(
+        db,
+        notifier,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        None,
+        key,
+        after_version,
+        wait,
+    )
+    .await;
+
+    match result {
+        Ok(resolved) => Ok(to_value_response(
+            GetValueDto {
+                domain: resolved.domain,
+                version: resolved.version.version,
+                value: Value::from(resolved.version.value),
+            },
+            domain_slug,
+        )),
         Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
-    };
+    }
+}
+
+/// Reveal the value of a config, decrypting it when it is a secret
+#[get("/v1/configs/<domain_slug>/<key>/secret")]
+pub async fn get_secret_value(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+) -> Result<GetValueResponse, RoutesError> {
+    let result = versions_service::reveal_value(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        None,
+        key,
+    )
+    .await;
+
+    match result {
+        Ok(resolved) => Ok(to_value_response(
+            to_revealed_value_dto(resolved, secrets)?,
+            domain_slug,
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+// An empty path would also match `get_value`, which takes precedence
+#[get("/v1/configs/<domain_slug>/<key>/value/<path..>", rank = 2)]
+pub async fn get_value_property(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    path: Segments<'_, Path>,
+) -> Result<GetValueResponse, RoutesError> {
+    let tokens = to_pointer_tokens(path);
+
+    let result = versions_service::get_value_property(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        key,
+        &tokens,
+    )
+    .await;
+
+    match result {
+        Ok(resolved) => Ok(to_value_response(
+            GetValueDto {
+                domain: resolved.domain,
+                version: resolved.version.version,
+                value: Value::from(resolved.version.value),
+            },
+            domain_slug,
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[put(
+    "/v1/configs/<domain_slug>/<key>/value/<path..>?<dry_run>",
+    format = "application/json",
+    data = "<input>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_value_property(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    path: Segments<'_, Path>,
+    if_match: IfMatch,
+    dry_run: Option<bool>,
+    input: Json<CreateVersionDto>,
+) -> Result<CreateVersionResponse, RoutesError> {
+    let tokens = to_pointer_tokens(path);
+    let input = input.into_inner();
+    let message = input.message.clone();
+    let config_value = to_config_value(input, secrets)?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = versions_service::set_value_property(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        key,
+        &tokens,
+        config_value,
+        message.as_deref(),
+        if_match.0.as_deref(),
+        dry_run,
+    )
+    .await;
+
+    match result {
+        Ok(change) => Ok(to_create_version_response(change, dry_run)),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+pub enum DeleteValuePropertyResponse {
+    #[response(status = 200, content_type = "json")]
+    Deleted(Json<GetVersionDto>),
+    /// Nothing was deleted, the body shows the version that would have been created
+    #[response(status = 200, content_type = "json")]
+    DryRun(Json<VersionPreviewDto>),
+}
+
+#[delete("/v1/configs/<domain_slug>/<key>/value/<path..>?<dry_run>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_value_property(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    key: &str,
+    path: Segments<'_, Path>,
+    if_match: IfMatch,
+    dry_run: Option<bool>,
+) -> Result<DeleteValuePropertyResponse, RoutesError> {
+    let tokens = to_pointer_tokens(path);
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = versions_service::delete_value_property(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        key,
+        &tokens,
+        if_match.0.as_deref(),
+        dry_run,
+    )
+    .await;
+
+    match result {
+        Ok(change) if dry_run => Ok(DeleteValuePropertyResponse::DryRun(Json(
+            VersionPreviewDto {
+                version: to_version_dto(change.version),
+                diff: change.diff.map(to_value_diff_dto),
+            },
+        ))),
+        Ok(change) => Ok(DeleteValuePropertyResponse::Deleted(Json(to_version_dto(
+            change.version,
+        )))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+// Environment scoped routes. They overlap the routes above for keys named `versions` or `value`,
+// which take precedence, hence the explicit ranks
+
+#[post(
+    "/v1/configs/<domain_slug>/<environment_slug>/<key>/versions?<dry_run>",
+    format = "application/json",
+    data = "<input>"
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_environment_version(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    if_match: IfMatch,
+    dry_run: Option<bool>,
+    input: Json<CreateVersionDto>,
+) -> Result<CreateVersionResponse, RoutesError> {
+    let input = input.into_inner();
+    let message = input.message.clone();
+    let config_value = to_config_value(input, secrets)?;
+    let dry_run = dry_run.unwrap_or(false);
+
+    let result = versions_service::create_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        config_value,
+        message.as_deref(),
+        if_match.0.as_deref(),
+        dry_run,
+    )
+    .await;
+
+    match result {
+        Ok(change) => Ok(to_create_version_response(change, dry_run)),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[get(
+    "/v1/configs/<domain_slug>/<environment_slug>/<key>/versions?<limit>&<offset>",
+    rank = 3
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_environment_versions(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetVersionsResponse, RoutesError> {
+    let result = versions_service::get_versions(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        limit,
+        offset,
+    )
+    .await;
+    match result {
+        Ok(versions) => Ok(to_versions_response(
+            versions,
+            format!(
+                "/v1/configs/{}/{}/{}/versions",
+                domain_slug, environment_slug, key
+            ),
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[get(
+    "/v1/configs/<domain_slug>/<environment_slug>/<key>/versions/<version>",
+    rank = 4
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_environment_version(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    version: i32,
+) -> Result<GetVersionResponse, RoutesError> {
+    let result = versions_service::get_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        version,
+    )
+    .await;
+
+    match result {
+        Ok(version) => Ok(GetVersionResponse(Json(to_version_dto(version)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[get(
+    "/v1/configs/<domain_slug>/<environment_slug>/<key>/versions/diff?<from>&<to>",
+    rank = 3
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn diff_environment_versions(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    from: i32,
+    to: i32,
+) -> Result<GetVersionDiffResponse, RoutesError> {
+    let result = versions_service::diff_versions(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        from,
+        to,
+    )
+    .await;
+
+    match result {
+        Ok(diff) => Ok(GetVersionDiffResponse(Json(VersionDiffDto {
+            from,
+            to,
+            diff: to_value_diff_dto(diff),
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[post("/v1/configs/<domain_slug>/<environment_slug>/<key>/versions/<version>/rollback")]
+#[allow(clippy::too_many_arguments)]
+pub async fn rollback_environment_version(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    actor: Actor,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    version: i32,
+) -> Result<CreateVersionSuccess, RoutesError> {
+    let result = versions_service::rollback_version(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        &actor,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        version,
+    )
+    .await;
+
+    match result {
+        Ok(version) => Ok(CreateVersionSuccess(Json(to_version_dto(version)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[get(
+    "/v1/configs/<domain_slug>/<environment_slug>/<key>/value?<wait>&<after_version>",
+    rank = 3
+)]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_environment_value(
+    db: &ConfigMonkeyDb,
+    notifier: &State<Notifier>,
+    secrets: &State<SecretsCipher>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+    wait: Option<&str>,
+    after_version: Option<i32>,
+) -> Result<GetValueResponse, RoutesError> {
+    let result = versions_service::get_value(
+        db,
+        notifier,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        Some(environment_slug),
+        key,
+        after_version,
+        wait,
+    )
+    .await;
+
+    match result {
+        Ok(resolved) => Ok(to_value_response(
+            GetValueDto {
+                domain: resolved.domain,
+                version: resolved.version.version,
+                value: Value::from(resolved.version.value),
+            },
+            domain_slug,
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[get("/v1/configs/<domain_slug>/<environment_slug>/<key>/secret", rank = 3)]
+pub async fn get_environment_secret_value(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    secrets: &State<SecretsCipher>,
+    domain_slug: &str,
+    environment_slug: &str,
+    key: &str,
+) -> Result<GetValueResponse, RoutesError> {
+    let result = versions_service::reveal_value(
+        db,
+        secrets,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        Some(environment_slug),
+        key,
+    )
+    .await;
+
+    match result {
+        Ok(resolved) => Ok(to_value_response(
+            to_revealed_value_dto(resolved, secrets)?,
+            domain_slug,
+        )),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
 }
@@ -0,0 +1,248 @@
+use crate::db::db::ConfigMonkeyDb;
+use crate::models::principal::Principal;
+use crate::models::webhook::{Webhook, WebhookDelivery};
+use crate::services::webhooks_service::{self, WebhooksServiceError};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::Responder;
+use rocket::serde::{json::Json, Deserialize, Serialize};
+use rocket::{delete, get, post};
+use rocket_db_pools::Connection;
+
+use super::dtos::{PaginatedListDto, PaginationDto};
+use super::errors::RoutesError;
+use super::guards::Tenant;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetWebhookDto {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<String>,
+    pub keys: Option<Vec<String>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct CreateWebhookDto {
+    pub url: String,
+    /// Kinds of changes to be notified of, each being one of `config_created`, `config_deleted`
+    /// or `version_created`. Defaults to the latter two
+    pub events: Option<Vec<String>>,
+    /// Keys of the configs to be notified of. Defaults to all of them
+    pub keys: Option<Vec<String>>,
+    /// Secret the payloads are signed with. It is never shown
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct GetWebhookDeliveryDto {
+    pub id: String,
+    pub event_id: i64,
+    pub event: String,
+    pub key: String,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+fn to_http_status(error: &WebhooksServiceError) -> Status {
+    match error {
+        WebhooksServiceError::DomainNotFound => Status::NotFound,
+        WebhooksServiceError::WebhookNotFound => Status::NotFound,
+        WebhooksServiceError::InvalidUrl => Status::BadRequest,
+        WebhooksServiceError::InvalidEvents => Status::BadRequest,
+        WebhooksServiceError::InvalidKeys => Status::BadRequest,
+        WebhooksServiceError::InvalidSecret => Status::BadRequest,
+        WebhooksServiceError::Forbidden => Status::Forbidden,
+        _ => Status::InternalServerError,
+    }
+}
+
+fn to_webhook_dto(webhook: Webhook) -> GetWebhookDto {
+    GetWebhookDto {
+        id: webhook.id,
+        url: webhook.url,
+        events: webhook
+            .events
+            .iter()
+            .map(|kind| kind.name().to_string())
+            .collect(),
+        keys: webhook.keys,
+        created_at: webhook.created_at,
+    }
+}
+
+fn to_webhook_delivery_dto(delivery: WebhookDelivery) -> GetWebhookDeliveryDto {
+    GetWebhookDeliveryDto {
+        id: delivery.id,
+        event_id: delivery.event_id,
+        event: delivery.kind.name().to_string(),
+        key: delivery.key,
+        status: delivery.status.name().to_string(),
+        attempts: delivery.attempts,
+        next_attempt_at: delivery.next_attempt_at,
+        response_status: delivery.response_status,
+        error: delivery.error,
+        created_at: delivery.created_at,
+        delivered_at: delivery.delivered_at,
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 201, content_type = "json")]
+pub struct CreateWebhookSuccess(Json<GetWebhookDto>);
+
+#[post(
+    "/v1/domains/<domain_slug>/webhooks",
+    format = "application/json",
+    data = "<input>"
+)]
+pub async fn create_webhook(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    input: Json<CreateWebhookDto>,
+) -> Result<CreateWebhookSuccess, RoutesError> {
+    let result = webhooks_service::create_webhook(
+        db,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        input.url.as_str(),
+        input.events.as_deref(),
+        input.keys.as_deref(),
+        input.secret.as_str(),
+    )
+    .await;
+
+    match result {
+        Ok(webhook) => Ok(CreateWebhookSuccess(Json(to_webhook_dto(webhook)))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetWebhooksResponse(Json<PaginatedListDto<GetWebhookDto>>);
+
+#[get("/v1/domains/<domain_slug>/webhooks?<limit>&<offset>")]
+pub async fn get_webhooks(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetWebhooksResponse, RoutesError> {
+    let result =
+        webhooks_service::get_webhooks(db, tenant.slug(), &principal, domain_slug, limit, offset)
+            .await;
+
+    match result {
+        Ok(webhooks) => Ok(GetWebhooksResponse(Json(PaginatedListDto {
+            pagination: PaginationDto {
+                count: webhooks.count,
+                offset: webhooks.offset,
+                limit: webhooks.limit,
+                next: webhooks.next_offset.map(|next_offset| {
+                    format!(
+                        "/v1/domains/{}/webhooks?limit={}&offset={}",
+                        domain_slug, webhooks.limit, next_offset
+                    )
+                }),
+                prev: webhooks.prev_offset.map(|prev_offset| {
+                    format!(
+                        "/v1/domains/{}/webhooks?limit={}&offset={}",
+                        domain_slug, webhooks.limit, prev_offset
+                    )
+                }),
+            },
+            data: webhooks.items.into_iter().map(to_webhook_dto).collect(),
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 204, content_type = "json")]
+pub struct DeleteWebhookSuccess(());
+
+#[delete("/v1/domains/<domain_slug>/webhooks/<id>")]
+pub async fn delete_webhook(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    id: &str,
+) -> Result<DeleteWebhookSuccess, RoutesError> {
+    let result =
+        webhooks_service::delete_webhook(db, tenant.slug(), &principal, domain_slug, id).await;
+
+    match result {
+        Ok(()) => Ok(DeleteWebhookSuccess(())),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
+
+#[derive(Responder)]
+#[response(status = 200, content_type = "json")]
+pub struct GetWebhookDeliveriesResponse(Json<PaginatedListDto<GetWebhookDeliveryDto>>);
+
+#[get("/v1/domains/<domain_slug>/webhooks/<id>/deliveries?<limit>&<offset>")]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_webhook_deliveries(
+    db: Connection<ConfigMonkeyDb>,
+    tenant: Tenant,
+    principal: Principal,
+    domain_slug: &str,
+    id: &str,
+    limit: Option<i32>,
+    offset: Option<i32>,
+) -> Result<GetWebhookDeliveriesResponse, RoutesError> {
+    let result = webhooks_service::get_webhook_deliveries(
+        db,
+        tenant.slug(),
+        &principal,
+        domain_slug,
+        id,
+        limit,
+        offset,
+    )
+    .await;
+
+    match result {
+        Ok(deliveries) => Ok(GetWebhookDeliveriesResponse(Json(PaginatedListDto {
+            pagination: PaginationDto {
+                count: deliveries.count,
+                offset: deliveries.offset,
+                limit: deliveries.limit,
+                next: deliveries.next_offset.map(|next_offset| {
+                    format!(
+                        "/v1/domains/{}/webhooks/{}/deliveries?limit={}&offset={}",
+                        domain_slug, id, deliveries.limit, next_offset
+                    )
+                }),
+                prev: deliveries.prev_offset.map(|prev_offset| {
+                    format!(
+                        "/v1/domains/{}/webhooks/{}/deliveries?limit={}&offset={}",
+                        domain_slug, id, deliveries.limit, prev_offset
+                    )
+                }),
+            },
+            data: deliveries
+                .items
+                .into_iter()
+                .map(to_webhook_delivery_dto)
+                .collect(),
+        }))),
+        Err(err) => Err(RoutesError(to_http_status(&err), err.code(), err.message())),
+    }
+}
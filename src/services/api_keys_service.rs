@@ -0,0 +1,198 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{
+        api_key::ApiKey,
+        list::List,
+        principal::{Principal, Role},
+    },
+    repos::api_keys_repo::{self, ApiKeysRepoError},
+    services::grants_service::{authorize, AuthorizationError},
+    shared::validators::validate_slug,
+};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use rocket::error;
+use rocket_db_pools::Connection;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+
+pub enum ApiKeysServiceError {
+    Unknown,
+    AlreadyExists,
+    ApiKeyNotFound,
+    TenantNotFound,
+    InvalidSlug,
+    InvalidScopes,
+    InvalidExpiry,
+    InvalidApiKey,
+    Forbidden,
+}
+
+impl ApiKeysServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ApiKeysServiceError::AlreadyExists => "api_key_already_exists",
+            ApiKeysServiceError::ApiKeyNotFound => "api_key_not_found",
+            ApiKeysServiceError::TenantNotFound => "tenant_not_found",
+            ApiKeysServiceError::InvalidSlug => "invalid_slug",
+            ApiKeysServiceError::InvalidScopes => "invalid_scopes",
+            ApiKeysServiceError::InvalidExpiry => "invalid_expiry",
+            ApiKeysServiceError::InvalidApiKey => "invalid_api_key",
+            ApiKeysServiceError::Forbidden => "forbidden",
+            ApiKeysServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            ApiKeysServiceError::AlreadyExists => "An active api key with the same name already exists",
+            ApiKeysServiceError::ApiKeyNotFound => "Api key not found",
+            ApiKeysServiceError::TenantNotFound => "Tenant not found",
+            ApiKeysServiceError::InvalidSlug => "The slug contains invalid characters. Only letters, numbers, dash (-) and underscore (_) are allowed",
+            ApiKeysServiceError::InvalidScopes => "The scopes must hold at least one role, each being one of reader, writer or admin",
+            ApiKeysServiceError::InvalidExpiry => "The expiry date must be in the future",
+            ApiKeysServiceError::InvalidApiKey => "The api key is malformed, unknown, revoked or expired",
+            ApiKeysServiceError::Forbidden => "The principal is not allowed to perform this operation",
+            ApiKeysServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for ApiKeysServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => ApiKeysServiceError::Forbidden,
+            AuthorizationError::Unknown => ApiKeysServiceError::Unknown,
+        }
+    }
+}
+
+/// A newly created key, along with the plaintext key which is not stored anywhere
+pub struct CreatedApiKey {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+/// Keys look like `cm_<prefix>_<secret>`, both parts being hex encoded random bytes
+const KEY_PREFIX: &str = "cm";
+const PREFIX_BYTES: usize = 6;
+const SECRET_BYTES: usize = 32;
+
+fn random_hex(len: usize) -> String {
+    let mut bytes = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Create a key that acts on the tenant with up to the highest of its scopes. Only the
+/// administrators of the tenant can create keys
+pub async fn create_api_key(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    name: &str,
+    scopes: &[String],
+    expires_at: Option<DateTime<Utc>>,
+) -> Result<CreatedApiKey, ApiKeysServiceError> {
+    if !validate_slug(name) {
+        return Err(ApiKeysServiceError::InvalidSlug);
+    }
+    let scopes = scopes
+        .iter()
+        .map(|scope| Role::from_name(scope.as_str()))
+        .collect::<Option<Vec<Role>>>()
+        .filter(|scopes| !scopes.is_empty())
+        .ok_or(ApiKeysServiceError::InvalidScopes)?;
+    if matches!(expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+        return Err(ApiKeysServiceError::InvalidExpiry);
+    }
+
+    authorize(&mut db, tenant, principal, None, Role::Admin).await?;
+
+    let prefix = random_hex(PREFIX_BYTES);
+    let secret = random_hex(SECRET_BYTES);
+    let result = api_keys_repo::create_api_key(
+        &mut db,
+        tenant,
+        name,
+        prefix.as_str(),
+        hash_secret(secret.as_str()).as_str(),
+        &scopes,
+        expires_at,
+    )
+    .await;
+    match result {
+        Ok(api_key) => Ok(CreatedApiKey {
+            key: format!("{}_{}_{}", KEY_PREFIX, prefix, secret),
+            api_key,
+        }),
+        Err(ApiKeysRepoError::AlreadyExists) => Err(ApiKeysServiceError::AlreadyExists),
+        // Nothing is inserted when the tenant does not exist
+        Err(ApiKeysRepoError::NotFound) => Err(ApiKeysServiceError::TenantNotFound),
+        Err(_) => Err(ApiKeysServiceError::Unknown),
+    }
+}
+
+pub async fn get_api_keys(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<ApiKey>, ApiKeysServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    authorize(&mut db, tenant, principal, None, Role::Admin).await?;
+    match api_keys_repo::get_api_keys(&mut db, tenant, limit, offset).await {
+        Ok(api_keys) => Ok(List::from_items(api_keys, limit, offset)),
+        Err(_) => Err(ApiKeysServiceError::Unknown),
+    }
+}
+
+/// Revoke the active key with the given name, which can no longer be used from then on
+pub async fn revoke_api_key(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    name: &str,
+) -> Result<(), ApiKeysServiceError> {
+    authorize(&mut db, tenant, principal, None, Role::Admin).await?;
+    match api_keys_repo::revoke_api_key(&mut db, tenant, name).await {
+        Ok(()) => Ok(()),
+        Err(ApiKeysRepoError::NotFound) => Err(ApiKeysServiceError::ApiKeyNotFound),
+        Err(_) => Err(ApiKeysServiceError::Unknown),
+    }
+}
+
+/// Resolve the key a request authenticates with, as long as it is neither revoked nor expired
+pub async fn authenticate_api_key(
+    db: &mut PgConnection,
+    key: &str,
+) -> Result<ApiKey, ApiKeysServiceError> {
+    let (prefix, secret) = match key.split('_').collect::<Vec<&str>>()[..] {
+        [KEY_PREFIX, prefix, secret] => (prefix, secret),
+        _ => return Err(ApiKeysServiceError::InvalidApiKey),
+    };
+
+    let api_key = match api_keys_repo::get_api_key_by_prefix(db, prefix).await {
+        Ok(api_key) => api_key,
+        Err(ApiKeysRepoError::NotFound) => return Err(ApiKeysServiceError::InvalidApiKey),
+        Err(err) => {
+            error!("[authenticate_api_key] Error fetching api key: {:?}", err);
+            return Err(ApiKeysServiceError::Unknown);
+        }
+    };
+
+    let is_expired = matches!(api_key.expires_at, Some(expires_at) if expires_at <= Utc::now());
+    if api_key.secret_hash != hash_secret(secret) || api_key.revoked_at.is_some() || is_expired {
+        return Err(ApiKeysServiceError::InvalidApiKey);
+    }
+    Ok(api_key)
+}
@@ -0,0 +1,121 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{
+        audit::{Actor, AuditAction, AuditChange, AuditEvent, AuditFilter},
+        config::ConfigValue,
+        list::List,
+        principal::{Principal, Role},
+    },
+    repos::audit_repo,
+    services::grants_service::{authorize, AuthorizationError},
+};
+use rocket::{error, serde::json::Value};
+use rocket_db_pools::Connection;
+use sha2::{Digest, Sha256};
+use sqlx::PgConnection;
+
+pub enum AuditServiceError {
+    Unknown,
+    InvalidSince,
+    Forbidden,
+}
+
+impl AuditServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            AuditServiceError::InvalidSince => "invalid_since",
+            AuditServiceError::Forbidden => "forbidden",
+            AuditServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            AuditServiceError::InvalidSince => "The since date must be an RFC 3339 timestamp",
+            AuditServiceError::Forbidden => {
+                "The principal is not allowed to perform this operation"
+            }
+            AuditServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for AuditServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => AuditServiceError::Forbidden,
+            AuthorizationError::Unknown => AuditServiceError::Unknown,
+        }
+    }
+}
+
+/// Failure to record a change, which is to abort the change itself
+pub enum AuditError {
+    Unknown,
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+/// Record a change in the audit log. It is meant to be called within the transaction making
+/// the change, so that neither one is kept without the other
+pub async fn record(
+    db: &mut PgConnection,
+    tenant: &str,
+    actor: &Actor,
+    change: AuditChange,
+) -> Result<(), AuditError> {
+    match audit_repo::create_audit_event(db, tenant, actor, change).await {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            error!("[record] Error recording change: {:?}", err);
+            Err(AuditError::Unknown)
+        }
+    }
+}
+
+/// SHA-256 of a value. Secrets are hashed in their encrypted form, so as not to reveal them,
+/// which changes their hashes whenever they are re-encrypted. Other values are hashed in the
+/// clear, so that their hashes do not depend on the key they are encrypted with
+pub fn hash_value(value: &ConfigValue) -> String {
+    let hashed = match value {
+        ConfigValue::Secret(sealed_value) => sealed_value.envelope.clone(),
+        _ => Value::from(value.clone()).to_string(),
+    };
+    hex::encode(Sha256::digest(hashed.as_bytes()))
+}
+
+/// A new version of a config, replacing the given value, if any
+pub fn version_created(
+    domain: &str,
+    environment: Option<&str>,
+    key: &str,
+    before: Option<&ConfigValue>,
+    after: &ConfigValue,
+) -> AuditChange {
+    AuditChange {
+        environment: environment.map(str::to_string),
+        before_hash: before.map(hash_value),
+        after_hash: Some(hash_value(after)),
+        ..AuditChange::config(AuditAction::VersionCreated, domain, key)
+    }
+}
+
+/// Retrieve the audit log of a tenant. Only its administrators are allowed to, as the log
+/// spans all of its domains, deleted ones included
+pub async fn get_audit_events(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    filter: &AuditFilter,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<AuditEvent>, AuditServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    authorize(&mut db, tenant, principal, None, Role::Admin).await?;
+    match audit_repo::get_audit_events(&mut db, tenant, filter, limit, offset).await {
+        Ok(events) => Ok(List::from_items(events, limit, offset)),
+        Err(_) => Err(AuditServiceError::Unknown),
+    }
+}
@@ -1,14 +1,27 @@
 use crate::{
-    db::db::ConfigMonkeyDb,
-    models::{config::Config, list::List},
+    db::db::{finish_transaction, ConfigMonkeyDb},
+    models::{
+        audit::{Actor, AuditAction, AuditChange},
+        config::Config,
+        deletion::DeletionSummary,
+        list::List,
+        principal::{Principal, Role},
+    },
     repos::{
         configs_repo::{self, ConfigsRepoError},
         domains_repo::{self, DomainsRepoError},
-    }, shared::validators::validate_slug,
+        versions_repo,
+    },
+    services::{
+        audit_service::{record, AuditError},
+        grants_service::{authorize, AuthorizationError},
+    },
+    shared::validators::validate_slug,
 };
 
 use rocket::error;
 use rocket_db_pools::Connection;
+use sqlx::Connection as _;
 
 pub enum ConfigsServiceError {
     Unknown,
@@ -16,6 +29,7 @@ pub enum ConfigsServiceError {
     ConfigNotFound,
     AlreadyExists,
     InvalidSlug,
+    Forbidden,
 }
 
 impl ConfigsServiceError {
@@ -25,6 +39,7 @@ impl ConfigsServiceError {
             ConfigsServiceError::InvalidSlug => "invalid_slug",
             ConfigsServiceError::ConfigNotFound => "config_not_found",
             ConfigsServiceError::DomainNotFound => "domain_not_found",
+            ConfigsServiceError::Forbidden => "forbidden",
             ConfigsServiceError::Unknown => "unknown_error",
         }
     }
@@ -34,27 +49,58 @@ impl ConfigsServiceError {
             ConfigsServiceError::InvalidSlug => "The slug contains invalid characters. Only letters, numbers, dash (-) and underscore (_) are allowed",
             ConfigsServiceError::ConfigNotFound => "Config not found",
             ConfigsServiceError::DomainNotFound => "Domain not found",
+            ConfigsServiceError::Forbidden => "The principal is not allowed to perform this operation",
             ConfigsServiceError::Unknown => "Unknown error",
         }
     }
 }
 
+impl From<AuthorizationError> for ConfigsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => ConfigsServiceError::Forbidden,
+            AuthorizationError::Unknown => ConfigsServiceError::Unknown,
+        }
+    }
+}
+
+impl From<AuditError> for ConfigsServiceError {
+    fn from(error: AuditError) -> Self {
+        match error {
+            AuditError::Unknown => ConfigsServiceError::Unknown,
+        }
+    }
+}
+
 const DEFAULT_LIMIT: i32 = 10;
 const DEFAULT_OFFSET: i32 = 0;
 
+/// Create a config. On dry runs, the config is validated and created but the transaction
+/// is rolled back
 pub async fn create_config(
     mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
     domain_slug: &str,
     key: &str,
+    dry_run: bool,
 ) -> Result<Config, ConfigsServiceError> {
-
     let is_valid_slug = validate_slug(key);
     if !is_valid_slug {
         return Err(ConfigsServiceError::InvalidSlug);
     }
 
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[create_config] Error starting transaction: {:?}", err);
+            return Err(ConfigsServiceError::Unknown);
+        }
+    };
+
     // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
+    let domain_result = domains_repo::get_domain_by_slug(&mut tx, tenant, domain_slug).await;
     if let Err(get_domain_error) = domain_result {
         match get_domain_error {
             DomainsRepoError::NotFound => return Err(ConfigsServiceError::DomainNotFound),
@@ -68,11 +114,33 @@ pub async fn create_config(
         }
     }
 
+    let domain = domain_result.unwrap();
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Writer,
+    )
+    .await?;
+
     // Create config
-    let result =
-        configs_repo::create_config(&mut *db, domain_result.unwrap().id.as_str(), key).await;
+    let result = configs_repo::create_config(&mut tx, tenant, domain.id.as_str(), key).await;
     match result {
-        Ok(created_config) => Ok(created_config),
+        Ok(created_config) => {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::config(AuditAction::ConfigCreated, domain_slug, key),
+            )
+            .await?;
+            if let Err(err) = finish_transaction(tx, dry_run).await {
+                error!("[create_config] Error finishing transaction: {:?}", err);
+                return Err(ConfigsServiceError::Unknown);
+            }
+            Ok(created_config)
+        }
         Err(configs_repo_err) => match configs_repo_err {
             ConfigsRepoError::AlreadyExists => Err(ConfigsServiceError::AlreadyExists),
             ConfigsRepoError::NotFound => Err(ConfigsServiceError::DomainNotFound),
@@ -83,11 +151,13 @@ pub async fn create_config(
 
 pub async fn get_config(
     mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
     domain_slug: &str,
     key: &str,
 ) -> Result<Config, ConfigsServiceError> {
     // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
+    let domain_result = domains_repo::get_domain_by_slug(&mut db, tenant, domain_slug).await;
     if let Err(get_domain_error) = domain_result {
         match get_domain_error {
             DomainsRepoError::NotFound => return Err(ConfigsServiceError::DomainNotFound),
@@ -101,8 +171,18 @@ pub async fn get_config(
         }
     }
 
+    let domain = domain_result.unwrap();
+    authorize(
+        &mut db,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Reader,
+    )
+    .await?;
+
     // Get config
-    let result = configs_repo::get_config(&mut *db, domain_result.unwrap().id.as_str(), key).await;
+    let result = configs_repo::get_config(&mut db, tenant, domain.id.as_str(), key).await;
     match result {
         Ok(config) => Ok(config),
         Err(configs_repo_err) => match configs_repo_err {
@@ -114,12 +194,14 @@ pub async fn get_config(
 
 pub async fn get_configs(
     mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
     domain_slug: &str,
     limit_opt: Option<i32>,
     offset_opt: Option<i32>,
 ) -> Result<List<Config>, ConfigsServiceError> {
     // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
+    let domain_result = domains_repo::get_domain_by_slug(&mut db, tenant, domain_slug).await;
     if let Err(get_domain_error) = domain_result {
         match get_domain_error {
             DomainsRepoError::NotFound => return Err(ConfigsServiceError::DomainNotFound),
@@ -133,27 +215,47 @@ pub async fn get_configs(
         }
     }
 
+    let domain = domain_result.unwrap();
+    authorize(
+        &mut db,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Reader,
+    )
+    .await?;
+
     // Get configs
     let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
     let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
     let result =
-        configs_repo::get_configs(&mut *db, domain_result.unwrap().id.as_str(), limit, offset)
-            .await;
+        configs_repo::get_configs(&mut db, tenant, domain.id.as_str(), limit, offset).await;
     match result {
         Ok(configs) => Ok(List::from_items(configs, limit, offset)),
-        Err(configs_repo_err) => match configs_repo_err {
-            _ => Err(ConfigsServiceError::Unknown),
-        },
+        Err(_) => Err(ConfigsServiceError::Unknown),
     }
 }
 
+/// Delete a config along with all its versions. On dry runs, the deletion is rolled back
 pub async fn delete_config(
     mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
     domain_slug: &str,
     key: &str,
-) -> Result<(), ConfigsServiceError> {
+    dry_run: bool,
+) -> Result<DeletionSummary, ConfigsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[delete_config] Error starting transaction: {:?}", err);
+            return Err(ConfigsServiceError::Unknown);
+        }
+    };
+
     // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
+    let domain_result = domains_repo::get_domain_by_slug(&mut tx, tenant, domain_slug).await;
     if let Err(get_domain_error) = domain_result {
         match get_domain_error {
             DomainsRepoError::NotFound => return Err(ConfigsServiceError::DomainNotFound),
@@ -166,9 +268,18 @@ pub async fn delete_config(
             }
         }
     }
+    let domain = domain_result.unwrap();
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Writer,
+    )
+    .await?;
+
     // Get Config
-    let config_result =
-        configs_repo::get_config(&mut *db, domain_result.unwrap().id.as_str(), key).await;
+    let config_result = configs_repo::get_config(&mut tx, tenant, domain.id.as_str(), key).await;
     if let Err(get_config_error) = config_result {
         match get_config_error {
             ConfigsRepoError::NotFound => return Err(ConfigsServiceError::ConfigNotFound),
@@ -181,9 +292,33 @@ pub async fn delete_config(
             }
         }
     }
-    let result = configs_repo::delete_config(&mut *db, config_result.unwrap().id.as_str()).await;
+    let config_id = config_result.unwrap().id;
+
+    // Count the versions that are deleted along with the config
+    let versions = match versions_repo::count_versions(&mut tx, tenant, config_id.as_str()).await {
+        Ok(versions) => versions,
+        Err(_) => return Err(ConfigsServiceError::Unknown),
+    };
+
+    let result = configs_repo::delete_config(&mut tx, tenant, config_id.as_str()).await;
     match result {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::config(AuditAction::ConfigDeleted, domain_slug, key),
+            )
+            .await?;
+            if let Err(err) = finish_transaction(tx, dry_run).await {
+                error!("[delete_config] Error finishing transaction: {:?}", err);
+                return Err(ConfigsServiceError::Unknown);
+            }
+            Ok(DeletionSummary {
+                configs: 1,
+                versions,
+            })
+        }
         Err(configs_repo_err) => match configs_repo_err {
             ConfigsRepoError::NotFound => Err(ConfigsServiceError::ConfigNotFound),
             _ => Err(ConfigsServiceError::Unknown),
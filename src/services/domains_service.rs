@@ -1,16 +1,42 @@
 use crate::{
-    db::db::ConfigMonkeyDb,
-    models::{domain::Domain, list::List},
-    repos::domains_repo::{self, DomainsRepoError},
-    shared::validators::validate_slug,
+    db::db::{finish_transaction, ConfigMonkeyDb},
+    models::{
+        audit::{Actor, AuditAction, AuditChange},
+        config::{ConfigEntry, ConfigValue, ValueDiff},
+        deletion::DeletionSummary,
+        domain::Domain,
+        list::List,
+        principal::{Principal, Role},
+    },
+    repos::{
+        configs_repo::{self, ConfigsRepoError},
+        domains_repo::{self, DomainsRepoError},
+        versions_repo::{self, VersionsRepoError},
+    },
+    services::{
+        audit_service::{self, record, AuditError},
+        grants_service::{authorize, AuthorizationError},
+        versions_service::diff_values,
+    },
+    shared::{secrets::SecretsCipher, validators::validate_slug},
 };
+use rocket::error;
 use rocket_db_pools::Connection;
+use sqlx::{Connection as _, PgConnection};
+use std::collections::{BTreeMap, BTreeSet};
 
 pub enum DomainsServiceError {
     DuplicateSlug,
     InvalidSlug,
     NotEmpty,
     NotFound,
+    HasChildren,
+    ParentNotFound,
+    ParentCycle,
+    TargetNotFound,
+    KeyNotFound,
+    TenantNotFound,
+    Forbidden,
     Unknown,
 }
 
@@ -21,6 +47,13 @@ impl DomainsServiceError {
             DomainsServiceError::InvalidSlug => "invalid_slug",
             DomainsServiceError::NotEmpty => "not_empty",
             DomainsServiceError::NotFound => "not_found",
+            DomainsServiceError::HasChildren => "has_children",
+            DomainsServiceError::ParentNotFound => "parent_not_found",
+            DomainsServiceError::ParentCycle => "parent_cycle",
+            DomainsServiceError::TargetNotFound => "target_not_found",
+            DomainsServiceError::KeyNotFound => "key_not_found",
+            DomainsServiceError::TenantNotFound => "tenant_not_found",
+            DomainsServiceError::Forbidden => "forbidden",
             DomainsServiceError::Unknown => "unknown",
         }
     }
@@ -30,61 +63,690 @@ impl DomainsServiceError {
             DomainsServiceError::InvalidSlug => "The slug contains invalid characters. Only letters, numbers, dash (-) and underscore (_) are allowed",
             DomainsServiceError::NotEmpty => "The domain could not be deleted because there are existing configs",
             DomainsServiceError::NotFound => "Domain not found",
+            DomainsServiceError::HasChildren => "The domain could not be deleted because other domains inherit from it",
+            DomainsServiceError::ParentNotFound => "Parent domain not found",
+            DomainsServiceError::ParentCycle => "A domain cannot inherit from itself or from any of the domains inheriting from it",
+            DomainsServiceError::TargetNotFound => "Target domain not found",
+            DomainsServiceError::KeyNotFound => "One or more keys do not exist in the source domain",
+            DomainsServiceError::TenantNotFound => "Tenant not found",
+            DomainsServiceError::Forbidden => "The principal is not allowed to perform this operation",
             DomainsServiceError::Unknown => "Unknown error",
         }
     }
 }
 
+impl From<AuthorizationError> for DomainsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => DomainsServiceError::Forbidden,
+            AuthorizationError::Unknown => DomainsServiceError::Unknown,
+        }
+    }
+}
+
+impl From<AuditError> for DomainsServiceError {
+    fn from(error: AuditError) -> Self {
+        match error {
+            AuditError::Unknown => DomainsServiceError::Unknown,
+        }
+    }
+}
+
+/// Outcome of importing a set of configs into a domain, by key
+#[derive(Default)]
+pub struct ImportReport {
+    /// Configs that did not exist and were created with a first version
+    pub created: Vec<String>,
+    /// Existing configs that got a new version
+    pub updated: Vec<String>,
+    /// Existing configs whose latest version already holds the imported value
+    pub unchanged: Vec<String>,
+    pub failed: Vec<(String, DomainsServiceError)>,
+}
+
+pub enum PromotionStatus {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// Outcome of promoting the value of a config to another domain
+pub struct Promotion {
+    pub key: String,
+    pub status: PromotionStatus,
+    /// Latest version in the target domain, which is the created one when the value changed
+    pub version: Option<i32>,
+    /// Changes from the latest value in the target domain, when it was updated
+    pub diff: Option<ValueDiff>,
+}
+
 const DEFAULT_LIMIT: i32 = 10;
 const DEFAULT_OFFSET: i32 = 0;
 
+/// Resolve the domain to inherit from
+async fn get_parent(
+    db: &mut PgConnection,
+    tenant: &str,
+    parent_slug: &str,
+) -> Result<Domain, DomainsServiceError> {
+    match domains_repo::get_domain_by_slug(db, tenant, parent_slug).await {
+        Ok(parent) => Ok(parent),
+        Err(DomainsRepoError::NotFound) => Err(DomainsServiceError::ParentNotFound),
+        Err(_) => Err(DomainsServiceError::Unknown),
+    }
+}
+
 pub async fn create_domain(
-    db: Connection<ConfigMonkeyDb>,
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
     slug: &str,
+    parent_slug: Option<&str>,
 ) -> Result<Domain, DomainsServiceError> {
     let is_valid_slug = validate_slug(slug);
     if !is_valid_slug {
         return Err(DomainsServiceError::InvalidSlug);
     }
 
-    let result = domains_repo::create_domain(db, slug).await;
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[create_domain] Error starting transaction: {:?}", err);
+            return Err(DomainsServiceError::Unknown);
+        }
+    };
+
+    // Domains are created over the tenant, so only its administrators can create them
+    authorize(&mut tx, tenant, principal, None, Role::Admin).await?;
+
+    let parent = match parent_slug {
+        Some(parent_slug) if parent_slug == slug => return Err(DomainsServiceError::ParentCycle),
+        Some(parent_slug) => Some(get_parent(&mut tx, tenant, parent_slug).await?),
+        None => None,
+    };
+
+    let result = domains_repo::create_domain(
+        &mut tx,
+        tenant,
+        slug,
+        parent.as_ref().map(|parent| parent.id.as_str()),
+    )
+    .await;
     match result {
-        Ok(domain) => Ok(domain),
+        Ok(domain) => {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::domain(AuditAction::DomainCreated, slug),
+            )
+            .await?;
+            if let Err(err) = tx.commit().await {
+                error!("[create_domain] Error committing transaction: {:?}", err);
+                return Err(DomainsServiceError::Unknown);
+            }
+            Ok(domain)
+        }
         Err(err) => match err {
             DomainsRepoError::DuplicateSlug => Err(DomainsServiceError::DuplicateSlug),
+            // Nothing is inserted when the tenant does not exist
+            DomainsRepoError::NotFound => Err(DomainsServiceError::TenantNotFound),
             _ => Err(DomainsServiceError::Unknown),
         },
     }
 }
 
 pub async fn get_domains(
-    db: Connection<ConfigMonkeyDb>,
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
     limit_opt: Option<i32>,
     offset_opt: Option<i32>,
 ) -> Result<List<Domain>, DomainsServiceError> {
     let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
     let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
 
-    let result = domains_repo::get_domains(db, limit, offset).await;
+    authorize(&mut db, tenant, principal, None, Role::Reader).await?;
+    let result = domains_repo::get_domains(db, tenant, limit, offset).await;
     match result {
         Ok(domains) => Ok(List::from_items(domains, limit, offset)),
-        Err(err) => match err {
-            _ => Err(DomainsServiceError::Unknown),
-        },
+        Err(_) => Err(DomainsServiceError::Unknown),
     }
 }
 
+/// Delete an empty domain. On dry runs, the deletion is rolled back
 pub async fn delete_domain(
-    db: Connection<ConfigMonkeyDb>,
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
     slug: &str,
-) -> Result<(), DomainsServiceError> {
-    let result = domains_repo::delete_domain(db, slug).await;
+    dry_run: bool,
+) -> Result<DeletionSummary, DomainsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[delete_domain] Error starting transaction: {:?}", err);
+            return Err(DomainsServiceError::Unknown);
+        }
+    };
+
+    let domain = match domains_repo::get_domain_by_slug(&mut tx, tenant, slug).await {
+        Ok(domain) => domain,
+        Err(DomainsRepoError::NotFound) => return Err(DomainsServiceError::NotFound),
+        Err(_) => return Err(DomainsServiceError::Unknown),
+    };
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Admin,
+    )
+    .await?;
+
+    let result = domains_repo::delete_domain(&mut tx, tenant, slug).await;
     match result {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::domain(AuditAction::DomainDeleted, slug),
+            )
+            .await?;
+            if let Err(err) = finish_transaction(tx, dry_run).await {
+                error!("[delete_domain] Error finishing transaction: {:?}", err);
+                return Err(DomainsServiceError::Unknown);
+            }
+            // Domains with configs cannot be deleted, so nothing else is removed along with them
+            Ok(DeletionSummary::default())
+        }
         Err(err) => match err {
             DomainsRepoError::NotFound => Err(DomainsServiceError::NotFound),
             DomainsRepoError::NotEmpty => Err(DomainsServiceError::NotEmpty),
+            DomainsRepoError::HasChildren => Err(DomainsServiceError::HasChildren),
             _ => Err(DomainsServiceError::Unknown),
         },
     }
 }
+
+/// Set the domain to inherit from, or stop inheriting when no parent is given.
+/// Parents that already inherit from the domain are rejected, as they would form a cycle
+pub async fn set_domain_parent(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    slug: &str,
+    parent_slug: Option<&str>,
+) -> Result<Domain, DomainsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[set_domain_parent] Error starting transaction: {:?}", err);
+            return Err(DomainsServiceError::Unknown);
+        }
+    };
+
+    let domain = match domains_repo::get_domain_by_slug(&mut tx, tenant, slug).await {
+        Ok(domain) => domain,
+        Err(DomainsRepoError::NotFound) => return Err(DomainsServiceError::NotFound),
+        Err(_) => return Err(DomainsServiceError::Unknown),
+    };
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Admin,
+    )
+    .await?;
+
+    let parent = match parent_slug {
+        Some(parent_slug) => {
+            let parent = get_parent(&mut tx, tenant, parent_slug).await?;
+            // Inheriting from a domain exposes its values, so they must be readable
+            authorize(
+                &mut tx,
+                tenant,
+                principal,
+                Some(parent.id.as_str()),
+                Role::Reader,
+            )
+            .await?;
+            // Concurrent changes could otherwise close a cycle that neither of them sees
+            if domains_repo::lock_domain_parents(&mut tx, tenant)
+                .await
+                .is_err()
+            {
+                return Err(DomainsServiceError::Unknown);
+            }
+            match domains_repo::get_domain_chain(&mut tx, tenant, parent_slug).await {
+                Ok(chain) if chain.iter().any(|ancestor| ancestor.id == domain.id) => {
+                    return Err(DomainsServiceError::ParentCycle)
+                }
+                Ok(_) => Some(parent),
+                Err(_) => return Err(DomainsServiceError::Unknown),
+            }
+        }
+        None => None,
+    };
+
+    let result = domains_repo::set_domain_parent(
+        &mut tx,
+        tenant,
+        domain.id.as_str(),
+        parent.as_ref().map(|parent| parent.id.as_str()),
+    )
+    .await;
+    match result {
+        Ok(()) => {
+            if let Err(err) = tx.commit().await {
+                error!(
+                    "[set_domain_parent] Error committing transaction: {:?}",
+                    err
+                );
+                return Err(DomainsServiceError::Unknown);
+            }
+            Ok(Domain {
+                parent: parent.map(|parent| parent.slug),
+                ..domain
+            })
+        }
+        Err(DomainsRepoError::NotFound) => Err(DomainsServiceError::NotFound),
+        Err(_) => Err(DomainsServiceError::Unknown),
+    }
+}
+
+/// Retrieve the latest value of every config in the domain, including the ones inherited
+/// from its ancestors that the domain does not override
+pub async fn render_domain(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    slug: &str,
+) -> Result<Vec<ConfigEntry>, DomainsServiceError> {
+    let entries = get_resolved_entries(&mut db, secrets, tenant, principal, slug).await?;
+    Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+}
+
+/// Readers of a domain can see the values it inherits, whatever their role over its ancestors.
+/// Each entry comes with the id of the domain it is resolved from
+async fn get_resolved_entries(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    slug: &str,
+) -> Result<Vec<(String, ConfigEntry)>, DomainsServiceError> {
+    let chain = match domains_repo::get_domain_chain(db, tenant, slug).await {
+        Ok(chain) if chain.is_empty() => return Err(DomainsServiceError::NotFound),
+        Ok(chain) => chain,
+        Err(_) => return Err(DomainsServiceError::Unknown),
+    };
+    authorize(
+        db,
+        tenant,
+        principal,
+        Some(chain[0].id.as_str()),
+        Role::Reader,
+    )
+    .await?;
+
+    // Overlay each domain on top of its parent, starting from the root
+    let mut entries = BTreeMap::new();
+    for domain in chain.iter().rev() {
+        match versions_repo::get_latest_versions(db, secrets, tenant, domain.id.as_str()).await {
+            Ok(domain_entries) => {
+                for entry in domain_entries {
+                    entries.insert(entry.key.clone(), (domain.id.clone(), entry));
+                }
+            }
+            Err(_) => return Err(DomainsServiceError::Unknown),
+        }
+    }
+    Ok(entries.into_values().collect())
+}
+
+/// Import configs into a domain within a single transaction. Missing configs are created and
+/// a new version is only added when the value differs from the latest one. Invalid keys are
+/// reported as failed without aborting the import. On dry runs, the transaction is rolled back
+#[allow(clippy::too_many_arguments)]
+pub async fn import_domain(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
+    slug: &str,
+    entries: Vec<(String, ConfigValue)>,
+    dry_run: bool,
+) -> Result<ImportReport, DomainsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[import_domain] Error starting transaction: {:?}", err);
+            return Err(DomainsServiceError::Unknown);
+        }
+    };
+
+    let domain = match domains_repo::get_domain_by_slug(&mut tx, tenant, slug).await {
+        Ok(domain) => domain,
+        Err(DomainsRepoError::NotFound) => return Err(DomainsServiceError::NotFound),
+        Err(_) => return Err(DomainsServiceError::Unknown),
+    };
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        Role::Writer,
+    )
+    .await?;
+
+    let mut report = ImportReport::default();
+    for (key, config_value) in entries {
+        if !validate_slug(key.as_str()) {
+            report.failed.push((key, DomainsServiceError::InvalidSlug));
+            continue;
+        }
+
+        // Get or create config
+        let (config, created) =
+            match configs_repo::get_config(&mut tx, tenant, domain.id.as_str(), key.as_str()).await
+            {
+                Ok(config) => (config, false),
+                Err(ConfigsRepoError::NotFound) => {
+                    match configs_repo::create_config(
+                        &mut tx,
+                        tenant,
+                        domain.id.as_str(),
+                        key.as_str(),
+                    )
+                    .await
+                    {
+                        Ok(config) => (config, true),
+                        Err(_) => return Err(DomainsServiceError::Unknown),
+                    }
+                }
+                Err(_) => return Err(DomainsServiceError::Unknown),
+            };
+        if created {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::config(AuditAction::ConfigCreated, slug, key.as_str()),
+            )
+            .await?;
+        }
+
+        if versions_repo::lock_versions(&mut tx, tenant, config.id.as_str())
+            .await
+            .is_err()
+        {
+            return Err(DomainsServiceError::Unknown);
+        }
+
+        // Skip values that did not change
+        let latest_version = if created {
+            None
+        } else {
+            match versions_repo::get_latest_version(
+                &mut tx,
+                secrets,
+                tenant,
+                config.id.as_str(),
+                None,
+            )
+            .await
+            {
+                Ok(latest_version) if latest_version.value == config_value => {
+                    report.unchanged.push(key);
+                    continue;
+                }
+                Ok(latest_version) => Some(latest_version),
+                Err(VersionsRepoError::NotFound) => None,
+                Err(_) => return Err(DomainsServiceError::Unknown),
+            }
+        };
+
+        let version = match versions_repo::create_version(
+            &mut tx,
+            secrets,
+            tenant,
+            config.id.as_str(),
+            None,
+            config_value,
+            actor.name.as_str(),
+            None,
+        )
+        .await
+        {
+            Ok(version) => version,
+            Err(_) => return Err(DomainsServiceError::Unknown),
+        };
+        record(
+            &mut tx,
+            tenant,
+            actor,
+            audit_service::version_created(
+                slug,
+                None,
+                key.as_str(),
+                latest_version
+                    .as_ref()
+                    .map(|latest_version| &latest_version.value),
+                &version.value,
+            ),
+        )
+        .await?;
+        if created {
+            report.created.push(key);
+        } else {
+            report.updated.push(key);
+        }
+    }
+
+    match finish_transaction(tx, dry_run).await {
+        Ok(()) => Ok(report),
+        Err(err) => {
+            error!("[import_domain] Error finishing transaction: {:?}", err);
+            Err(DomainsServiceError::Unknown)
+        }
+    }
+}
+
+/// Copy the current value of configs, inherited ones included, from a domain to another within
+/// a single transaction. Missing configs are created in the target domain and a new version is
+/// only added when the value differs from the latest one. All configs are promoted unless a list
+/// of keys is given. Promoting secrets takes the role needed to reveal them. On dry runs, the
+/// transaction is rolled back
+#[allow(clippy::too_many_arguments)]
+pub async fn promote_domain(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
+    slug: &str,
+    target_slug: &str,
+    keys: Option<Vec<String>>,
+    dry_run: bool,
+) -> Result<Vec<Promotion>, DomainsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[promote_domain] Error starting transaction: {:?}", err);
+            return Err(DomainsServiceError::Unknown);
+        }
+    };
+
+    let mut entries = get_resolved_entries(&mut tx, secrets, tenant, principal, slug).await?;
+    if let Some(keys) = keys {
+        if keys
+            .iter()
+            .any(|key| !entries.iter().any(|(_, entry)| &entry.key == key))
+        {
+            return Err(DomainsServiceError::KeyNotFound);
+        }
+        entries.retain(|(_, entry)| keys.contains(&entry.key));
+    }
+
+    // Promoting a secret would let the target's administrators reveal it, so it takes the
+    // same role as revealing it: admin over the domain and over the ancestor holding it
+    let holders: BTreeSet<&str> = entries
+        .iter()
+        .filter(|(_, entry)| matches!(entry.version.value, ConfigValue::Secret(_)))
+        .map(|(domain_id, _)| domain_id.as_str())
+        .collect();
+    if !holders.is_empty() {
+        let source = match domains_repo::get_domain_by_slug(&mut tx, tenant, slug).await {
+            Ok(source) => source,
+            Err(DomainsRepoError::NotFound) => return Err(DomainsServiceError::NotFound),
+            Err(_) => return Err(DomainsServiceError::Unknown),
+        };
+        authorize(
+            &mut tx,
+            tenant,
+            principal,
+            Some(source.id.as_str()),
+            Role::Admin,
+        )
+        .await?;
+    }
+    for domain_id in holders {
+        authorize(&mut tx, tenant, principal, Some(domain_id), Role::Admin).await?;
+    }
+
+    let target = match domains_repo::get_domain_by_slug(&mut tx, tenant, target_slug).await {
+        Ok(target) => target,
+        Err(DomainsRepoError::NotFound) => return Err(DomainsServiceError::TargetNotFound),
+        Err(_) => return Err(DomainsServiceError::Unknown),
+    };
+    authorize(
+        &mut tx,
+        tenant,
+        principal,
+        Some(target.id.as_str()),
+        Role::Writer,
+    )
+    .await?;
+
+    let mut promotions = vec![];
+    for (_, entry) in entries {
+        // Get or create config
+        let (config, created) =
+            match configs_repo::get_config(&mut tx, tenant, target.id.as_str(), entry.key.as_str())
+                .await
+            {
+                Ok(config) => (config, false),
+                Err(ConfigsRepoError::NotFound) => {
+                    match configs_repo::create_config(
+                        &mut tx,
+                        tenant,
+                        target.id.as_str(),
+                        entry.key.as_str(),
+                    )
+                    .await
+                    {
+                        Ok(config) => (config, true),
+                        Err(_) => return Err(DomainsServiceError::Unknown),
+                    }
+                }
+                Err(_) => return Err(DomainsServiceError::Unknown),
+            };
+        if created {
+            record(
+                &mut tx,
+                tenant,
+                actor,
+                AuditChange::config(AuditAction::ConfigCreated, target_slug, entry.key.as_str()),
+            )
+            .await?;
+        }
+
+        if versions_repo::lock_versions(&mut tx, tenant, config.id.as_str())
+            .await
+            .is_err()
+        {
+            return Err(DomainsServiceError::Unknown);
+        }
+        let latest_version = match versions_repo::get_latest_version(
+            &mut tx,
+            secrets,
+            tenant,
+            config.id.as_str(),
+            None,
+        )
+        .await
+        {
+            Ok(latest_version) => Some(latest_version),
+            Err(VersionsRepoError::NotFound) => None,
+            Err(_) => return Err(DomainsServiceError::Unknown),
+        };
+        if let Some(latest_version) = &latest_version {
+            if latest_version.value == entry.version.value {
+                promotions.push(Promotion {
+                    key: entry.key,
+                    status: PromotionStatus::Unchanged,
+                    version: Some(latest_version.version),
+                    diff: None,
+                });
+                continue;
+            }
+        }
+
+        let version = match versions_repo::create_version(
+            &mut tx,
+            secrets,
+            tenant,
+            config.id.as_str(),
+            None,
+            entry.version.value,
+            actor.name.as_str(),
+            None,
+        )
+        .await
+        {
+            Ok(version) => version,
+            Err(_) => return Err(DomainsServiceError::Unknown),
+        };
+        record(
+            &mut tx,
+            tenant,
+            actor,
+            audit_service::version_created(
+                target_slug,
+                None,
+                entry.key.as_str(),
+                latest_version
+                    .as_ref()
+                    .map(|latest_version| &latest_version.value),
+                &version.value,
+            ),
+        )
+        .await?;
+        promotions.push(Promotion {
+            key: entry.key,
+            status: if created {
+                PromotionStatus::Created
+            } else {
+                PromotionStatus::Updated
+            },
+            version: Some(version.version),
+            diff: latest_version
+                .map(|latest_version| diff_values(&latest_version.value, &version.value)),
+        });
+    }
+
+    match finish_transaction(tx, dry_run).await {
+        Ok(()) => Ok(promotions),
+        Err(err) => {
+            error!("[promote_domain] Error finishing transaction: {:?}", err);
+            Err(DomainsServiceError::Unknown)
+        }
+    }
+}
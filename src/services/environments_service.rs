@@ -0,0 +1,157 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{
+        domain::Domain,
+        environment::Environment,
+        list::List,
+        principal::{Principal, Role},
+    },
+    repos::{
+        domains_repo::{self, DomainsRepoError},
+        environments_repo::{self, EnvironmentsRepoError},
+    },
+    services::grants_service::{authorize, AuthorizationError},
+    shared::validators::validate_slug,
+};
+use rocket::error;
+use rocket_db_pools::Connection;
+use sqlx::PgConnection;
+
+pub enum EnvironmentsServiceError {
+    Unknown,
+    DomainNotFound,
+    EnvironmentNotFound,
+    AlreadyExists,
+    InvalidSlug,
+    Forbidden,
+}
+
+impl EnvironmentsServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            EnvironmentsServiceError::AlreadyExists => "environment_already_exists",
+            EnvironmentsServiceError::InvalidSlug => "invalid_slug",
+            EnvironmentsServiceError::EnvironmentNotFound => "environment_not_found",
+            EnvironmentsServiceError::DomainNotFound => "domain_not_found",
+            EnvironmentsServiceError::Forbidden => "forbidden",
+            EnvironmentsServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            EnvironmentsServiceError::AlreadyExists => "Environment already exists",
+            EnvironmentsServiceError::InvalidSlug => "The slug contains invalid characters. Only letters, numbers, dash (-) and underscore (_) are allowed",
+            EnvironmentsServiceError::EnvironmentNotFound => "Environment not found",
+            EnvironmentsServiceError::DomainNotFound => "Domain not found",
+            EnvironmentsServiceError::Forbidden => "The principal is not allowed to perform this operation",
+            EnvironmentsServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for EnvironmentsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => EnvironmentsServiceError::Forbidden,
+            AuthorizationError::Unknown => EnvironmentsServiceError::Unknown,
+        }
+    }
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+/// Fetch a domain, checking that the principal holds the required role over it
+async fn get_domain(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    required_role: Role,
+) -> Result<Domain, EnvironmentsServiceError> {
+    match domains_repo::get_domain_by_slug(db, tenant, domain_slug).await {
+        Ok(domain) => {
+            authorize(
+                db,
+                tenant,
+                principal,
+                Some(domain.id.as_str()),
+                required_role,
+            )
+            .await?;
+            Ok(domain)
+        }
+        Err(DomainsRepoError::NotFound) => Err(EnvironmentsServiceError::DomainNotFound),
+        Err(get_domain_error) => {
+            error!(
+                "[get_domain] Error fetching domains: {:?}",
+                get_domain_error
+            );
+            Err(EnvironmentsServiceError::Unknown)
+        }
+    }
+}
+
+pub async fn create_environment(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    slug: &str,
+) -> Result<Environment, EnvironmentsServiceError> {
+    if !validate_slug(slug) {
+        return Err(EnvironmentsServiceError::InvalidSlug);
+    }
+
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    match environments_repo::create_environment(&mut db, tenant, domain.id.as_str(), slug).await {
+        Ok(environment) => Ok(environment),
+        Err(EnvironmentsRepoError::AlreadyExists) => Err(EnvironmentsServiceError::AlreadyExists),
+        Err(_) => Err(EnvironmentsServiceError::Unknown),
+    }
+}
+
+pub async fn get_environments(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<Environment>, EnvironmentsServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Reader).await?;
+    match environments_repo::get_environments(&mut db, tenant, domain.id.as_str(), limit, offset)
+        .await
+    {
+        Ok(environments) => Ok(List::from_items(environments, limit, offset)),
+        Err(_) => Err(EnvironmentsServiceError::Unknown),
+    }
+}
+
+/// Delete an environment, along with the version history it holds for every config
+pub async fn delete_environment(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    slug: &str,
+) -> Result<(), EnvironmentsServiceError> {
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    let environment =
+        match environments_repo::get_environment(&mut db, tenant, domain.id.as_str(), slug).await {
+            Ok(environment) => environment,
+            Err(EnvironmentsRepoError::NotFound) => {
+                return Err(EnvironmentsServiceError::EnvironmentNotFound)
+            }
+            Err(_) => return Err(EnvironmentsServiceError::Unknown),
+        };
+
+    match environments_repo::delete_environment(&mut db, tenant, environment.id.as_str()).await {
+        Ok(()) => Ok(()),
+        Err(EnvironmentsRepoError::NotFound) => Err(EnvironmentsServiceError::EnvironmentNotFound),
+        Err(_) => Err(EnvironmentsServiceError::Unknown),
+    }
+}
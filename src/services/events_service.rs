@@ -0,0 +1,198 @@
+use crate::{
+    db::notifications::{Notification, Notifier, DOMAIN_EVENT_CHANNEL},
+    models::{
+        domain::Domain,
+        event::DomainEvent,
+        principal::{Principal, Role},
+    },
+    repos::{
+        domains_repo::{self, DomainsRepoError},
+        events_repo,
+    },
+    services::grants_service::{authorize, AuthorizationError},
+    shared::secrets::SecretsCipher,
+};
+use rocket::{
+    error,
+    tokio::sync::broadcast::{error::RecvError, Receiver},
+};
+use sqlx::{PgConnection, PgPool};
+
+pub enum EventsServiceError {
+    Unknown,
+    DomainNotFound,
+    InvalidLastEventId,
+    Forbidden,
+}
+
+impl EventsServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            EventsServiceError::DomainNotFound => "domain_not_found",
+            EventsServiceError::InvalidLastEventId => "invalid_last_event_id",
+            EventsServiceError::Forbidden => "forbidden",
+            EventsServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            EventsServiceError::DomainNotFound => "Domain not found",
+            EventsServiceError::InvalidLastEventId => {
+                "The last event id must be the id of an event received before"
+            }
+            EventsServiceError::Forbidden => {
+                "The principal is not allowed to perform this operation"
+            }
+            EventsServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for EventsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => EventsServiceError::Forbidden,
+            AuthorizationError::Unknown => EventsServiceError::Unknown,
+        }
+    }
+}
+
+/// Events fetched at once when catching up
+const BATCH_SIZE: i32 = 100;
+
+/// Events of a domain followed by a client, from the last one it received
+pub struct DomainEvents {
+    pool: PgPool,
+    secrets: SecretsCipher,
+    domain_id: String,
+    last_event_id: i64,
+    notifications: Receiver<Option<Notification>>,
+}
+
+impl DomainEvents {
+    /// Retrieve the events following the last one returned, waiting for changes to the domain
+    /// when there are none yet
+    pub async fn next(&mut self) -> Result<Vec<DomainEvent>, EventsServiceError> {
+        loop {
+            let events = match self.pool.acquire().await {
+                Ok(mut db) => {
+                    events_repo::get_domain_events(
+                        &mut db,
+                        &self.secrets,
+                        self.domain_id.as_str(),
+                        self.last_event_id,
+                        BATCH_SIZE,
+                    )
+                    .await
+                }
+                Err(err) => {
+                    error!("[next] Error acquiring connection: {:?}", err);
+                    return Err(EventsServiceError::Unknown);
+                }
+            };
+            match events {
+                Ok(events) if events.is_empty() => self.wait_for_event().await?,
+                Ok(events) => {
+                    self.last_event_id = events.last().map_or(self.last_event_id, |e| e.id);
+                    return Ok(events);
+                }
+                Err(_) => return Err(EventsServiceError::Unknown),
+            }
+        }
+    }
+
+    async fn wait_for_event(&mut self) -> Result<(), EventsServiceError> {
+        loop {
+            match self.notifications.recv().await {
+                Ok(Some(notification))
+                    if !notification.is(DOMAIN_EVENT_CHANNEL, self.domain_id.as_str()) =>
+                {
+                    continue
+                }
+                // The app is shutting down
+                Err(RecvError::Closed) => return Err(EventsServiceError::Unknown),
+                // An event of the domain, or notifications that may have been missed
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Fetch a domain, checking that the principal holds the required role over it
+async fn get_domain(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    required_role: Role,
+) -> Result<Domain, EventsServiceError> {
+    match domains_repo::get_domain_by_slug(db, tenant, domain_slug).await {
+        Ok(domain) => {
+            authorize(
+                db,
+                tenant,
+                principal,
+                Some(domain.id.as_str()),
+                required_role,
+            )
+            .await?;
+            Ok(domain)
+        }
+        Err(DomainsRepoError::NotFound) => Err(EventsServiceError::DomainNotFound),
+        Err(get_domain_error) => {
+            error!(
+                "[get_domain] Error fetching domains: {:?}",
+                get_domain_error
+            );
+            Err(EventsServiceError::Unknown)
+        }
+    }
+}
+
+/// Follow the changes to the configs of a domain. Clients resuming a stream name the last event
+/// they received, to be sent the ones that followed it, while the others are only sent the
+/// changes made from now on
+pub async fn follow_domain_events(
+    pool: &PgPool,
+    notifier: &Notifier,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    last_event_id: Option<&str>,
+) -> Result<DomainEvents, EventsServiceError> {
+    let last_event_id = match last_event_id.map(|id| id.trim().parse::<i64>()) {
+        Some(Ok(id)) if id >= 0 => Some(id),
+        Some(_) => return Err(EventsServiceError::InvalidLastEventId),
+        None => None,
+    };
+    // Subscribe before looking the events up, not to miss the ones created in between
+    let notifications = notifier.subscribe();
+
+    let mut db = match pool.acquire().await {
+        Ok(db) => db,
+        Err(err) => {
+            error!(
+                "[follow_domain_events] Error acquiring connection: {:?}",
+                err
+            );
+            return Err(EventsServiceError::Unknown);
+        }
+    };
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Reader).await?;
+    let last_event_id = match last_event_id {
+        Some(id) => id,
+        None => match events_repo::get_last_event_id(&mut db, domain.id.as_str()).await {
+            Ok(id) => id.unwrap_or(0),
+            Err(_) => return Err(EventsServiceError::Unknown),
+        },
+    };
+
+    Ok(DomainEvents {
+        pool: pool.clone(),
+        secrets: secrets.clone(),
+        domain_id: domain.id,
+        last_event_id,
+        notifications,
+    })
+}
@@ -0,0 +1,223 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{
+        domain::Domain,
+        grant::Grant,
+        list::List,
+        principal::{Principal, Role},
+    },
+    repos::{
+        domains_repo::{self, DomainsRepoError},
+        grants_repo::{self, GrantsRepoError},
+    },
+};
+use rocket::error;
+use rocket_db_pools::Connection;
+use sqlx::PgConnection;
+
+pub enum GrantsServiceError {
+    Unknown,
+    DomainNotFound,
+    TenantNotFound,
+    GrantNotFound,
+    AlreadyExists,
+    InvalidPrincipal,
+    InvalidRole,
+    Forbidden,
+}
+
+impl GrantsServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            GrantsServiceError::AlreadyExists => "grant_already_exists",
+            GrantsServiceError::InvalidPrincipal => "invalid_principal",
+            GrantsServiceError::InvalidRole => "invalid_role",
+            GrantsServiceError::GrantNotFound => "grant_not_found",
+            GrantsServiceError::DomainNotFound => "domain_not_found",
+            GrantsServiceError::TenantNotFound => "tenant_not_found",
+            GrantsServiceError::Forbidden => "forbidden",
+            GrantsServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            GrantsServiceError::AlreadyExists => {
+                "The principal already has a role over the tenant or domain"
+            }
+            GrantsServiceError::InvalidPrincipal => "The principal cannot be empty",
+            GrantsServiceError::InvalidRole => "The role must be one of reader, writer or admin",
+            GrantsServiceError::GrantNotFound => "Grant not found",
+            GrantsServiceError::DomainNotFound => "Domain not found",
+            GrantsServiceError::TenantNotFound => "Tenant not found",
+            GrantsServiceError::Forbidden => {
+                "The principal is not allowed to perform this operation"
+            }
+            GrantsServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+/// Reasons for denying a principal an operation
+pub enum AuthorizationError {
+    Forbidden,
+    Unknown,
+}
+
+impl From<AuthorizationError> for GrantsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => GrantsServiceError::Forbidden,
+            AuthorizationError::Unknown => GrantsServiceError::Unknown,
+        }
+    }
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+/// Check that the principal holds at least the required role over a domain, or over the
+/// whole tenant when no domain is given. Roles granted over the tenant apply to all its domains
+pub async fn authorize(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    domain_id: Option<&str>,
+    required_role: Role,
+) -> Result<(), AuthorizationError> {
+    let name = match principal {
+        Principal::Unrestricted => return Ok(()),
+        // API keys act on their own tenant only, so their scope applies to all its domains
+        Principal::ApiKey { scope, .. } if *scope >= required_role => return Ok(()),
+        Principal::ApiKey { .. } | Principal::Anonymous => {
+            return Err(AuthorizationError::Forbidden)
+        }
+        Principal::Named(name) => name,
+    };
+
+    match grants_repo::get_role(db, tenant, domain_id, name.as_str()).await {
+        Ok(Some(role)) if role >= required_role => Ok(()),
+        Ok(_) => Err(AuthorizationError::Forbidden),
+        Err(err) => {
+            error!("[authorize] Error fetching role: {:?}", err);
+            Err(AuthorizationError::Unknown)
+        }
+    }
+}
+
+/// Check that the principal is allowed to do anything, in every tenant
+pub fn authorize_unrestricted(principal: &Principal) -> Result<(), AuthorizationError> {
+    match principal {
+        Principal::Unrestricted => Ok(()),
+        _ => Err(AuthorizationError::Forbidden),
+    }
+}
+
+/// Resolve the domain grants are managed over, if any, and check that the principal
+/// administers it
+async fn get_grant_scope(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: Option<&str>,
+) -> Result<Option<Domain>, GrantsServiceError> {
+    let domain = match domain_slug {
+        Some(domain_slug) => {
+            match domains_repo::get_domain_by_slug(db, tenant, domain_slug).await {
+                Ok(domain) => Some(domain),
+                Err(DomainsRepoError::NotFound) => return Err(GrantsServiceError::DomainNotFound),
+                Err(_) => return Err(GrantsServiceError::Unknown),
+            }
+        }
+        None => None,
+    };
+    authorize(
+        db,
+        tenant,
+        principal,
+        domain.as_ref().map(|domain| domain.id.as_str()),
+        Role::Admin,
+    )
+    .await?;
+    Ok(domain)
+}
+
+/// Grant a role to a principal over a domain, or over the whole tenant when no domain is given.
+/// Only administrators of the domain or tenant can grant roles over it
+pub async fn create_grant(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: Option<&str>,
+    grantee: &str,
+    role: Role,
+) -> Result<Grant, GrantsServiceError> {
+    if grantee.trim().is_empty() {
+        return Err(GrantsServiceError::InvalidPrincipal);
+    }
+
+    let domain = get_grant_scope(&mut db, tenant, principal, domain_slug).await?;
+    let result = grants_repo::create_grant(
+        &mut db,
+        tenant,
+        domain.as_ref().map(|domain| domain.id.as_str()),
+        grantee,
+        role,
+    )
+    .await;
+    match result {
+        Ok(grant) => Ok(grant),
+        Err(GrantsRepoError::AlreadyExists) => Err(GrantsServiceError::AlreadyExists),
+        // Nothing is inserted when the tenant does not exist
+        Err(GrantsRepoError::NotFound) => Err(GrantsServiceError::TenantNotFound),
+        Err(_) => Err(GrantsServiceError::Unknown),
+    }
+}
+
+pub async fn get_grants(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: Option<&str>,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<Grant>, GrantsServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    let domain = get_grant_scope(&mut db, tenant, principal, domain_slug).await?;
+    let result = grants_repo::get_grants(
+        &mut db,
+        tenant,
+        domain.as_ref().map(|domain| domain.id.as_str()),
+        limit,
+        offset,
+    )
+    .await;
+    match result {
+        Ok(grants) => Ok(List::from_items(grants, limit, offset)),
+        Err(_) => Err(GrantsServiceError::Unknown),
+    }
+}
+
+/// Revoke the role of a principal over a domain, or over the whole tenant when no domain is given
+pub async fn delete_grant(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: Option<&str>,
+    grantee: &str,
+) -> Result<(), GrantsServiceError> {
+    let domain = get_grant_scope(&mut db, tenant, principal, domain_slug).await?;
+    let result = grants_repo::delete_grant(
+        &mut db,
+        tenant,
+        domain.as_ref().map(|domain| domain.id.as_str()),
+        grantee,
+    )
+    .await;
+    match result {
+        Ok(()) => Ok(()),
+        Err(GrantsRepoError::NotFound) => Err(GrantsServiceError::GrantNotFound),
+        Err(_) => Err(GrantsServiceError::Unknown),
+    }
+}
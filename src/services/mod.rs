@@ -1,3 +1,11 @@
+pub mod api_keys_service;
+pub mod audit_service;
 pub mod configs_service;
 pub mod domains_service;
+pub mod environments_service;
+pub mod events_service;
+pub mod grants_service;
+pub mod reencryptions_service;
+pub mod tenants_service;
 pub mod versions_service;
+pub mod webhooks_service;
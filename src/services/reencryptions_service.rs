@@ -0,0 +1,266 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{
+        principal::Principal,
+        reencryption::{Reencryption, ReencryptionStatus},
+    },
+    repos::{
+        reencryptions_repo::{self, ReencryptionsRepoError},
+        versions_repo,
+    },
+    services::grants_service::{authorize_unrestricted, AuthorizationError},
+    shared::secrets::SecretsCipher,
+};
+use rocket::{error, info, tokio, Orbit, Rocket};
+use rocket_db_pools::Database;
+use sqlx::{types::Uuid, PgPool};
+use std::time::Duration;
+
+pub enum ReencryptionsServiceError {
+    Unknown,
+    NotFound,
+    AlreadyRunning,
+    SecretsDisabled,
+    Forbidden,
+}
+
+impl ReencryptionsServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            ReencryptionsServiceError::NotFound => "reencryption_not_found",
+            ReencryptionsServiceError::AlreadyRunning => "reencryption_in_progress",
+            ReencryptionsServiceError::SecretsDisabled => "secrets_disabled",
+            ReencryptionsServiceError::Forbidden => "forbidden",
+            ReencryptionsServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            ReencryptionsServiceError::NotFound => "Re-encryption not found",
+            ReencryptionsServiceError::AlreadyRunning => "Another re-encryption is still running",
+            ReencryptionsServiceError::SecretsDisabled => {
+                "Secret values require a master key to be configured"
+            }
+            ReencryptionsServiceError::Forbidden => {
+                "The principal is not allowed to perform this operation"
+            }
+            ReencryptionsServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for ReencryptionsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => ReencryptionsServiceError::Forbidden,
+            AuthorizationError::Unknown => ReencryptionsServiceError::Unknown,
+        }
+    }
+}
+
+/// Number of values re-encrypted in each transaction
+const BATCH_SIZE: i32 = 100;
+
+/// Delay before retrying when the only values left are locked by a concurrent batch
+const LOCKED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Start re-encrypting every value of every tenant under the active master key, secret or not.
+/// The job carries on in the background, one batch at a time, while its progress is recorded
+pub async fn start_reencryption(
+    pool: &PgPool,
+    principal: &Principal,
+    secrets: &SecretsCipher,
+) -> Result<Reencryption, ReencryptionsServiceError> {
+    authorize_unrestricted(principal)?;
+    let key_id = secrets
+        .active_key()
+        .ok_or(ReencryptionsServiceError::SecretsDisabled)?;
+
+    let mut db = match pool.acquire().await {
+        Ok(db) => db,
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    };
+    let total = match versions_repo::count_stale_values(&mut db, key_id).await {
+        Ok(total) => total as i32,
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    };
+    let reencryption = match reencryptions_repo::create_reencryption(&mut db, key_id, total).await {
+        Ok(reencryption) => reencryption,
+        Err(ReencryptionsRepoError::AlreadyRunning) => {
+            return Err(ReencryptionsServiceError::AlreadyRunning)
+        }
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    };
+
+    tokio::spawn(run_reencryption(
+        pool.clone(),
+        secrets.clone(),
+        reencryption.id.clone(),
+    ));
+    Ok(reencryption)
+}
+
+pub async fn get_reencryption(
+    pool: &PgPool,
+    principal: &Principal,
+    id: &str,
+) -> Result<Reencryption, ReencryptionsServiceError> {
+    authorize_unrestricted(principal)?;
+    if Uuid::parse_str(id).is_err() {
+        return Err(ReencryptionsServiceError::NotFound);
+    }
+
+    let mut db = match pool.acquire().await {
+        Ok(db) => db,
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    };
+    match reencryptions_repo::get_reencryption(&mut db, id).await {
+        Ok(reencryption) => Ok(reencryption),
+        Err(ReencryptionsRepoError::NotFound) => Err(ReencryptionsServiceError::NotFound),
+        Err(_) => Err(ReencryptionsServiceError::Unknown),
+    }
+}
+
+/// Carry on with the job left running when the app was last stopped, if any. Jobs
+/// re-encrypting under a key that is no longer the active one are failed instead
+pub async fn resume_reencryption(rocket: &Rocket<Orbit>) {
+    let (Some(db), Some(secrets)) = (
+        ConfigMonkeyDb::fetch(rocket),
+        rocket.state::<SecretsCipher>(),
+    ) else {
+        return;
+    };
+    let pool: &PgPool = db;
+
+    let mut db = match pool.acquire().await {
+        Ok(db) => db,
+        Err(err) => {
+            error!(
+                "[resume_reencryption] Error acquiring connection: {:?}",
+                err
+            );
+            return;
+        }
+    };
+    let reencryption = match reencryptions_repo::get_running_reencryption(&mut db).await {
+        Ok(Some(reencryption)) => reencryption,
+        _ => return,
+    };
+
+    if secrets.active_key() == Some(reencryption.key_id.as_str()) {
+        info!("Resuming re-encryption {}", reencryption.id);
+        tokio::spawn(run_reencryption(
+            pool.clone(),
+            secrets.clone(),
+            reencryption.id,
+        ));
+    } else {
+        error!(
+            "Re-encryption {} was under key `{}`, which is no longer the active one",
+            reencryption.id, reencryption.key_id
+        );
+        let _ = reencryptions_repo::finish_reencryption(
+            &mut db,
+            reencryption.id.as_str(),
+            ReencryptionStatus::Failed,
+        )
+        .await;
+    }
+}
+
+/// Re-encrypt batches of values until none is left under another key. Each batch is
+/// committed along with the progress of the job, so that it can be resumed after an interruption
+async fn run_reencryption(pool: PgPool, secrets: SecretsCipher, id: String) {
+    loop {
+        match run_batch(&pool, &secrets, id.as_str()).await {
+            Ok(true) => continue,
+            Ok(false) => return,
+            Err(_) => {
+                if let Ok(mut db) = pool.acquire().await {
+                    let _ = reencryptions_repo::finish_reencryption(
+                        &mut db,
+                        id.as_str(),
+                        ReencryptionStatus::Failed,
+                    )
+                    .await;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Re-encrypt a single batch of values, returning whether there may be more of them
+async fn run_batch(
+    pool: &PgPool,
+    secrets: &SecretsCipher,
+    id: &str,
+) -> Result<bool, ReencryptionsServiceError> {
+    let key_id = secrets
+        .active_key()
+        .ok_or(ReencryptionsServiceError::SecretsDisabled)?;
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    };
+
+    let stale_values =
+        match versions_repo::lock_stale_values(&mut tx, secrets, key_id, BATCH_SIZE).await {
+            Ok(stale_values) => stale_values,
+            Err(_) => return Err(ReencryptionsServiceError::Unknown),
+        };
+    if stale_values.is_empty() {
+        // Values locked by a concurrent batch are skipped above, so they have to be counted
+        // before the job can be deemed complete
+        match versions_repo::count_stale_values(&mut tx, key_id).await {
+            Ok(0) => {}
+            Ok(_) => {
+                let _ = tx.rollback().await;
+                tokio::time::sleep(LOCKED_INTERVAL).await;
+                return Ok(true);
+            }
+            Err(_) => return Err(ReencryptionsServiceError::Unknown),
+        }
+        match reencryptions_repo::finish_reencryption(&mut tx, id, ReencryptionStatus::Completed)
+            .await
+        {
+            Ok(_) => {}
+            Err(ReencryptionsRepoError::NotFound) => {
+                return Err(ReencryptionsServiceError::NotFound)
+            }
+            Err(_) => return Err(ReencryptionsServiceError::Unknown),
+        }
+        return match tx.commit().await {
+            Ok(_) => Ok(false),
+            Err(_) => Err(ReencryptionsServiceError::Unknown),
+        };
+    }
+
+    for (version_id, value) in stale_values.iter() {
+        let sealed_value = match secrets.seal(value) {
+            Ok(sealed_value) => sealed_value,
+            Err(err) => {
+                error!(
+                    "[run_batch] Error re-encrypting version {}: {:?}",
+                    version_id, err
+                );
+                return Err(ReencryptionsServiceError::Unknown);
+            }
+        };
+        if versions_repo::update_value(&mut tx, version_id, &sealed_value)
+            .await
+            .is_err()
+        {
+            return Err(ReencryptionsServiceError::Unknown);
+        }
+    }
+    match reencryptions_repo::add_processed(&mut tx, id, stale_values.len() as i32).await {
+        Ok(_) => {}
+        Err(ReencryptionsRepoError::NotFound) => return Err(ReencryptionsServiceError::NotFound),
+        Err(_) => return Err(ReencryptionsServiceError::Unknown),
+    }
+    match tx.commit().await {
+        Ok(_) => Ok(true),
+        Err(_) => Err(ReencryptionsServiceError::Unknown),
+    }
+}
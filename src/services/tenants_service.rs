@@ -0,0 +1,79 @@
+use crate::{
+    db::db::ConfigMonkeyDb,
+    models::{list::List, principal::Principal, tenant::Tenant},
+    repos::tenants_repo::{self, TenantsRepoError},
+    services::grants_service::{authorize_unrestricted, AuthorizationError},
+    shared::validators::validate_slug,
+};
+use rocket_db_pools::Connection;
+
+pub enum TenantsServiceError {
+    Unknown,
+    AlreadyExists,
+    InvalidSlug,
+    Forbidden,
+}
+
+impl TenantsServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            TenantsServiceError::AlreadyExists => "tenant_already_exists",
+            TenantsServiceError::InvalidSlug => "invalid_slug",
+            TenantsServiceError::Forbidden => "forbidden",
+            TenantsServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            TenantsServiceError::AlreadyExists => "Tenant already exists",
+            TenantsServiceError::InvalidSlug => "The slug contains invalid characters. Only letters, numbers, dash (-) and underscore (_) are allowed",
+            TenantsServiceError::Forbidden => "The principal is not allowed to perform this operation",
+            TenantsServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for TenantsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => TenantsServiceError::Forbidden,
+            AuthorizationError::Unknown => TenantsServiceError::Unknown,
+        }
+    }
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+pub async fn create_tenant(
+    mut db: Connection<ConfigMonkeyDb>,
+    principal: &Principal,
+    slug: &str,
+) -> Result<Tenant, TenantsServiceError> {
+    authorize_unrestricted(principal)?;
+    if !validate_slug(slug) {
+        return Err(TenantsServiceError::InvalidSlug);
+    }
+
+    match tenants_repo::create_tenant(&mut db, slug).await {
+        Ok(tenant) => Ok(tenant),
+        Err(TenantsRepoError::AlreadyExists) => Err(TenantsServiceError::AlreadyExists),
+        Err(_) => Err(TenantsServiceError::Unknown),
+    }
+}
+
+pub async fn get_tenants(
+    mut db: Connection<ConfigMonkeyDb>,
+    principal: &Principal,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<Tenant>, TenantsServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    authorize_unrestricted(principal)?;
+    match tenants_repo::get_tenants(&mut db, limit, offset).await {
+        Ok(tenants) => Ok(List::from_items(tenants, limit, offset)),
+        Err(_) => Err(TenantsServiceError::Unknown),
+    }
+}
@@ -1,23 +1,53 @@
 use crate::{
-    db::db::ConfigMonkeyDb,
+    db::{
+        db::{finish_transaction, ConfigMonkeyDb},
+        notifications::{Notification, Notifier, VersionCreated},
+    },
     models::{
-        config::{ConfigValue, ConfigVersion},
+        audit::Actor,
+        config::{Config, ConfigValue, ConfigVersion, ResolvedVersion, ValueDiff, VersionChange},
         list::List,
+        principal::{Principal, Role},
     },
     repos::{
         configs_repo::{self, ConfigsRepoError},
         domains_repo::{self, DomainsRepoError},
+        environments_repo::{self, EnvironmentsRepoError},
         versions_repo::{self, VersionsRepoError},
     },
+    services::{
+        audit_service::{self, record, AuditError},
+        grants_service::{authorize, AuthorizationError},
+    },
+    shared::{json_patch, json_pointer, secrets::SecretsCipher},
 };
 
-use rocket::error;
+use rocket::{
+    error,
+    serde::json::{from_str, Value},
+    tokio::{
+        sync::broadcast::{error::RecvError, Receiver},
+        time::{sleep_until, timeout_at, Instant},
+    },
+};
 use rocket_db_pools::Connection;
+use sqlx::{Connection as _, PgConnection, PgPool};
+use std::time::Duration;
 
 pub enum VersionsServiceError {
     Unknown,
     DomainNotFound,
     ConfigNotFound,
+    EnvironmentNotFound,
+    NoVersions,
+    VersionNotFound,
+    PropertyNotFound,
+    NotADocument,
+    SecretProperty,
+    InvalidWait,
+    VersionMismatch,
+    VersionConflict,
+    Forbidden,
 }
 
 impl VersionsServiceError {
@@ -25,6 +55,16 @@ impl VersionsServiceError {
         match *self {
             VersionsServiceError::ConfigNotFound => "config_not_found",
             VersionsServiceError::DomainNotFound => "domain_not_found",
+            VersionsServiceError::EnvironmentNotFound => "environment_not_found",
+            VersionsServiceError::NoVersions => "no_versions",
+            VersionsServiceError::VersionNotFound => "version_not_found",
+            VersionsServiceError::PropertyNotFound => "property_not_found",
+            VersionsServiceError::NotADocument => "not_a_document",
+            VersionsServiceError::SecretProperty => "secret_property",
+            VersionsServiceError::InvalidWait => "invalid_wait",
+            VersionsServiceError::VersionMismatch => "version_mismatch",
+            VersionsServiceError::VersionConflict => "version_conflict",
+            VersionsServiceError::Forbidden => "forbidden",
             VersionsServiceError::Unknown => "unknown_error",
         }
     }
@@ -32,111 +72,1052 @@ impl VersionsServiceError {
         match *self {
             VersionsServiceError::ConfigNotFound => "Config not found",
             VersionsServiceError::DomainNotFound => "Domain not found",
+            VersionsServiceError::EnvironmentNotFound => "Environment not found",
+            VersionsServiceError::NoVersions => "The config does not have any versions yet",
+            VersionsServiceError::VersionNotFound => "Version not found",
+            VersionsServiceError::PropertyNotFound => "Property not found",
+            VersionsServiceError::NotADocument => "The config value is not a JSON object or array",
+            VersionsServiceError::SecretProperty => {
+                "A property cannot be a secret, the whole value has to be created as one"
+            }
+            VersionsServiceError::InvalidWait => {
+                "The wait must be a duration such as 30s, 500ms or 1m"
+            }
+            VersionsServiceError::VersionMismatch => {
+                "The latest version of the config does not match the one given in If-Match"
+            }
+            VersionsServiceError::VersionConflict => {
+                "Another version of the config was created at the same time"
+            }
+            VersionsServiceError::Forbidden => {
+                "The principal is not allowed to perform this operation"
+            }
             VersionsServiceError::Unknown => "Unknown error",
         }
     }
 }
 
+impl From<AuthorizationError> for VersionsServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => VersionsServiceError::Forbidden,
+            AuthorizationError::Unknown => VersionsServiceError::Unknown,
+        }
+    }
+}
+
+impl From<AuditError> for VersionsServiceError {
+    fn from(error: AuditError) -> Self {
+        match error {
+            AuditError::Unknown => VersionsServiceError::Unknown,
+        }
+    }
+}
+
 const DEFAULT_LIMIT: i32 = 10;
 const DEFAULT_OFFSET: i32 = 0;
+/// Longest a request may wait for a value to change
+const MAX_WAIT: Duration = Duration::from_secs(60);
 
+/// Create a new version of a config, reporting how it differs from the latest one.
+/// Given the `If-Match` header of the request, the version is only created when the latest
+/// one still matches it. On dry runs, the version is created but the transaction is rolled back
+#[allow(clippy::too_many_arguments)]
 pub async fn create_version(
     mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
     domain_slug: &str,
+    environment_slug: Option<&str>,
     key: &str,
     config_value: ConfigValue,
-) -> Result<ConfigVersion, VersionsServiceError> {
-    // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
-    if let Err(get_domain_error) = domain_result {
-        match get_domain_error {
-            DomainsRepoError::NotFound => return Err(VersionsServiceError::DomainNotFound),
-            _ => {
-                error!(
-                    "[create_version] Error fetching domains: {:?}",
-                    get_domain_error
-                );
-                return Err(VersionsServiceError::Unknown);
-            }
+    message: Option<&str>,
+    if_match: Option<&str>,
+    dry_run: bool,
+) -> Result<VersionChange, VersionsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[create_version] Error starting transaction: {:?}", err);
+            return Err(VersionsServiceError::Unknown);
         }
-    }
-    // Get Config
-    let config_result =
-        configs_repo::get_config(&mut *db, domain_result.unwrap().id.as_str(), key).await;
-    if let Err(get_config_error) = config_result {
-        match get_config_error {
-            ConfigsRepoError::NotFound => return Err(VersionsServiceError::ConfigNotFound),
-            _ => {
-                error!(
-                    "[create_version] Error fetching config: {:?}",
-                    get_config_error
-                );
-                return Err(VersionsServiceError::Unknown);
-            }
+    };
+
+    let (config, environment_id) = get_domain_config(
+        &mut tx,
+        tenant,
+        principal,
+        Role::Writer,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+    lock_versions(&mut tx, tenant, config.id.as_str()).await?;
+    let previous_version = match get_latest_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+    )
+    .await
+    {
+        Ok(version) => Some(version),
+        Err(VersionsServiceError::NoVersions) => None,
+        Err(err) => return Err(err),
+    };
+    if let Some(if_match) = if_match {
+        let latest = previous_version
+            .as_ref()
+            .map_or(0, |previous| previous.version);
+        if !matches_version(if_match, latest) {
+            return Err(VersionsServiceError::VersionMismatch);
         }
     }
 
-    let result =
-        versions_repo::create_version(&mut *db, config_result.unwrap().id.as_str(), config_value)
-            .await;
-    match result {
-        Ok(version) => Ok(version),
-        Err(configs_repo_err) => match configs_repo_err {
-            VersionsRepoError::NotFound => Err(VersionsServiceError::ConfigNotFound),
-            _ => Err(VersionsServiceError::Unknown),
-        },
+    let version = match versions_repo::create_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+        config_value,
+        actor.name.as_str(),
+        message,
+    )
+    .await
+    {
+        Ok(version) => version,
+        Err(VersionsRepoError::NotFound) => return Err(VersionsServiceError::ConfigNotFound),
+        Err(VersionsRepoError::AlreadyExists) => return Err(VersionsServiceError::VersionConflict),
+        Err(_) => return Err(VersionsServiceError::Unknown),
+    };
+    record(
+        &mut tx,
+        tenant,
+        actor,
+        audit_service::version_created(
+            domain_slug,
+            environment_slug,
+            key,
+            previous_version.as_ref().map(|previous| &previous.value),
+            &version.value,
+        ),
+    )
+    .await?;
+
+    if let Err(err) = finish_transaction(tx, dry_run).await {
+        error!("[create_version] Error finishing transaction: {:?}", err);
+        return Err(VersionsServiceError::Unknown);
     }
+
+    Ok(VersionChange {
+        diff: previous_version.map(|previous| diff_values(&previous.value, &version.value)),
+        version,
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_versions(
     mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
     domain_slug: &str,
+    environment_slug: Option<&str>,
     key: &str,
     limit_opt: Option<i32>,
     offset_opt: Option<i32>,
 ) -> Result<List<ConfigVersion>, VersionsServiceError> {
+    let (config, environment_id) = get_domain_config(
+        &mut db,
+        tenant,
+        principal,
+        Role::Reader,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+
+    // Get versions
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    let result = versions_repo::get_versions(
+        &mut db,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+        limit,
+        offset,
+    )
+    .await;
+    match result {
+        Ok(versions) => Ok(List::from_items(versions, limit, offset)),
+        Err(configs_repo_err) => match configs_repo_err {
+            VersionsRepoError::NotFound => Err(VersionsServiceError::ConfigNotFound),
+            _ => Err(VersionsServiceError::Unknown),
+        },
+    }
+}
+
+/// Hold off other writers of a config until the end of the transaction
+async fn lock_versions(
+    db: &mut PgConnection,
+    tenant: &str,
+    config_id: &str,
+) -> Result<(), VersionsServiceError> {
+    match versions_repo::lock_versions(db, tenant, config_id).await {
+        Ok(()) => Ok(()),
+        Err(VersionsRepoError::NotFound) => Err(VersionsServiceError::ConfigNotFound),
+        Err(_) => Err(VersionsServiceError::Unknown),
+    }
+}
+
+/// Whether an `If-Match` header names the latest version of a config, as found in the `ETag`
+/// of its value. `"0"` stands for a config without versions and `*` for any existing version.
+/// Weak tags never match, as the comparison is meant to be strong
+fn matches_version(if_match: &str, latest: i32) -> bool {
+    let etag = format!("\"{}\"", latest);
+    if_match
+        .split(',')
+        .map(str::trim)
+        .any(|tag| tag == etag || (tag == "*" && latest > 0))
+}
+
+/// Resolve a config from its domain slug and key, along with the id of the environment
+/// holding the version history, if any. The principal must hold the required role over the domain
+async fn get_domain_config(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    required_role: Role,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+) -> Result<(Config, Option<String>), VersionsServiceError> {
     // Get domain
-    let domain_result = domains_repo::get_domain_by_slug(&mut *db, domain_slug).await;
-    if let Err(get_domain_error) = domain_result {
-        match get_domain_error {
-            DomainsRepoError::NotFound => return Err(VersionsServiceError::DomainNotFound),
-            _ => {
-                error!(
-                    "[get_versions] Error fetching domains: {:?}",
-                    get_domain_error
-                );
+    let domain = match domains_repo::get_domain_by_slug(db, tenant, domain_slug).await {
+        Ok(domain) => domain,
+        Err(DomainsRepoError::NotFound) => return Err(VersionsServiceError::DomainNotFound),
+        Err(get_domain_error) => {
+            error!(
+                "[get_domain_config] Error fetching domains: {:?}",
+                get_domain_error
+            );
+            return Err(VersionsServiceError::Unknown);
+        }
+    };
+    authorize(
+        db,
+        tenant,
+        principal,
+        Some(domain.id.as_str()),
+        required_role,
+    )
+    .await?;
+    // Get Environment
+    let environment_id = match environment_slug {
+        Some(environment_slug) => {
+            match environments_repo::get_environment(
+                db,
+                tenant,
+                domain.id.as_str(),
+                environment_slug,
+            )
+            .await
+            {
+                Ok(environment) => Some(environment.id),
+                Err(EnvironmentsRepoError::NotFound) => {
+                    return Err(VersionsServiceError::EnvironmentNotFound)
+                }
+                Err(get_environment_error) => {
+                    error!(
+                        "[get_domain_config] Error fetching environment: {:?}",
+                        get_environment_error
+                    );
+                    return Err(VersionsServiceError::Unknown);
+                }
+            }
+        }
+        None => None,
+    };
+    // Get Config
+    match configs_repo::get_config(db, tenant, domain.id.as_str(), key).await {
+        Ok(config) => Ok((config, environment_id)),
+        Err(ConfigsRepoError::NotFound) => Err(VersionsServiceError::ConfigNotFound),
+        Err(get_config_error) => {
+            error!(
+                "[get_domain_config] Error fetching config: {:?}",
+                get_config_error
+            );
+            Err(VersionsServiceError::Unknown)
+        }
+    }
+}
+
+/// Retrieve the most recent version of a config
+async fn get_latest_version(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    config_id: &str,
+    environment_id: Option<&str>,
+) -> Result<ConfigVersion, VersionsServiceError> {
+    match versions_repo::get_latest_version(db, secrets, tenant, config_id, environment_id).await {
+        Ok(version) => Ok(version),
+        Err(VersionsRepoError::NotFound) => Err(VersionsServiceError::NoVersions),
+        Err(_) => Err(VersionsServiceError::Unknown),
+    }
+}
+
+/// Turn a config value into a JSON document that can be navigated with a JSON Pointer.
+/// Strings holding a serialized object or array are parsed, and flagged so that they
+/// can be written back as strings
+fn to_document(config_value: ConfigValue) -> Result<(Value, bool), VersionsServiceError> {
+    match config_value {
+        ConfigValue::Object(_) | ConfigValue::Array(_) => Ok((Value::from(config_value), false)),
+        ConfigValue::String(text) => match from_str::<Value>(text.as_str()) {
+            Ok(document @ (Value::Object(_) | Value::Array(_))) => Ok((document, true)),
+            _ => Err(VersionsServiceError::NotADocument),
+        },
+        _ => Err(VersionsServiceError::NotADocument),
+    }
+}
+
+fn from_document(document: Value, is_text: bool) -> ConfigValue {
+    if is_text {
+        ConfigValue::String(document.to_string())
+    } else {
+        ConfigValue::from(document)
+    }
+}
+
+/// Retrieve the latest version of a config, walking up the parent chain of the domain until
+/// one of them holds a version of its own. Ancestors are not required to have the environment.
+/// Principals with the required role over the domain can see the values it inherits, whatever
+/// their role over its ancestors
+#[allow(clippy::too_many_arguments)]
+async fn resolve_latest_version(
+    db: &mut PgConnection,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    required_role: Role,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+) -> Result<ResolvedVersion, VersionsServiceError> {
+    let chain = match domains_repo::get_domain_chain(db, tenant, domain_slug).await {
+        Ok(chain) if chain.is_empty() => return Err(VersionsServiceError::DomainNotFound),
+        Ok(chain) => chain,
+        Err(get_chain_error) => {
+            error!(
+                "[resolve_latest_version] Error fetching domains: {:?}",
+                get_chain_error
+            );
+            return Err(VersionsServiceError::Unknown);
+        }
+    };
+    authorize(
+        db,
+        tenant,
+        principal,
+        Some(chain[0].id.as_str()),
+        required_role,
+    )
+    .await?;
+
+    let mut not_found_error = VersionsServiceError::ConfigNotFound;
+    for (depth, domain) in chain.into_iter().enumerate() {
+        let environment_id = match environment_slug {
+            Some(environment_slug) => {
+                match environments_repo::get_environment(
+                    db,
+                    tenant,
+                    domain.id.as_str(),
+                    environment_slug,
+                )
+                .await
+                {
+                    Ok(environment) => Some(environment.id),
+                    Err(EnvironmentsRepoError::NotFound) if depth > 0 => continue,
+                    Err(EnvironmentsRepoError::NotFound) => {
+                        return Err(VersionsServiceError::EnvironmentNotFound)
+                    }
+                    Err(_) => return Err(VersionsServiceError::Unknown),
+                }
+            }
+            None => None,
+        };
+        let config = match configs_repo::get_config(db, tenant, domain.id.as_str(), key).await {
+            Ok(config) => config,
+            Err(ConfigsRepoError::NotFound) => continue,
+            Err(_) => return Err(VersionsServiceError::Unknown),
+        };
+        match versions_repo::get_latest_version(
+            db,
+            secrets,
+            tenant,
+            config.id.as_str(),
+            environment_id.as_deref(),
+        )
+        .await
+        {
+            Ok(version) => {
+                return Ok(ResolvedVersion {
+                    domain: domain.slug,
+                    version,
+                })
+            }
+            Err(VersionsRepoError::NotFound) => not_found_error = VersionsServiceError::NoVersions,
+            Err(_) => return Err(VersionsServiceError::Unknown),
+        }
+    }
+    Err(not_found_error)
+}
+
+/// Parse how long to wait for, such as `30s`, `500ms` or `1m`, in seconds when no unit is given.
+/// Waits are capped to [`MAX_WAIT`]
+fn parse_wait(wait: &str) -> Result<Duration, VersionsServiceError> {
+    let (amount, unit) = match wait.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => wait.split_at(index),
+        None => (wait, "s"),
+    };
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| VersionsServiceError::InvalidWait)?;
+    let duration = match unit {
+        "ms" => Duration::from_millis(amount),
+        "s" => Duration::from_secs(amount),
+        "m" => Duration::from_secs(amount.saturating_mul(60)),
+        _ => return Err(VersionsServiceError::InvalidWait),
+    };
+    Ok(duration.min(MAX_WAIT))
+}
+
+/// Configs a value may be resolved from, whose new versions may change it: the ones with its key
+/// in the domain and its ancestors, in the environment with the same slug, if any
+struct ValueScope {
+    tenant: String,
+    key: String,
+    domain_ids: Vec<String>,
+    /// Ids of the environments of the domains, or none when the value is not an environment's
+    environment_ids: Option<Vec<String>>,
+}
+
+impl ValueScope {
+    fn contains(&self, version_created: &VersionCreated) -> bool {
+        version_created.tenant == self.tenant
+            && version_created.key == self.key
+            && self.domain_ids.contains(&version_created.domain_id)
+            && match (&self.environment_ids, &version_created.environment_id) {
+                (Some(environment_ids), Some(environment_id)) => {
+                    environment_ids.contains(environment_id)
+                }
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+async fn get_value_scope(
+    db: &mut PgConnection,
+    tenant: &str,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+) -> Result<ValueScope, VersionsServiceError> {
+    let chain = match domains_repo::get_domain_chain(db, tenant, domain_slug).await {
+        Ok(chain) => chain,
+        Err(_) => return Err(VersionsServiceError::Unknown),
+    };
+    let environment_ids = match environment_slug {
+        Some(environment_slug) => {
+            let mut environment_ids = vec![];
+            for domain in chain.iter() {
+                match environments_repo::get_environment(
+                    db,
+                    tenant,
+                    domain.id.as_str(),
+                    environment_slug,
+                )
+                .await
+                {
+                    Ok(environment) => environment_ids.push(environment.id),
+                    Err(EnvironmentsRepoError::NotFound) => continue,
+                    Err(_) => return Err(VersionsServiceError::Unknown),
+                }
+            }
+            Some(environment_ids)
+        }
+        None => None,
+    };
+    Ok(ValueScope {
+        tenant: tenant.to_string(),
+        key: key.to_string(),
+        domain_ids: chain.into_iter().map(|domain| domain.id).collect(),
+        environment_ids,
+    })
+}
+
+/// Wait until a version is created for a config in the scope, or the deadline passes
+async fn wait_for_version(
+    notifications: &mut Receiver<Option<Notification>>,
+    scope: &ValueScope,
+    deadline: Instant,
+) {
+    loop {
+        match timeout_at(deadline, notifications.recv()).await {
+            Ok(Ok(Some(notification)))
+                if !notification
+                    .version_created()
+                    .is_some_and(|version_created| scope.contains(&version_created)) =>
+            {
+                continue
+            }
+            Ok(Err(RecvError::Closed)) => return sleep_until(deadline).await,
+            // A version for the key, notifications that may have been missed, or the deadline
+            _ => return,
+        }
+    }
+}
+
+/// Retrieve the latest value of a config. Given the version the caller already holds, wait up to
+/// `wait` for it to change, so that values can be long polled. The value is looked up again only
+/// when a version is created for a config it may be resolved from, without holding on to a
+/// connection in between. It is returned as is when the wait elapses
+#[allow(clippy::too_many_arguments)]
+pub async fn get_value(
+    pool: &PgPool,
+    notifier: &Notifier,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+    after_version: Option<i32>,
+    wait: Option<&str>,
+) -> Result<ResolvedVersion, VersionsServiceError> {
+    let wait = match wait {
+        Some(wait) => parse_wait(wait)?,
+        None => Duration::ZERO,
+    };
+    let deadline = Instant::now() + wait;
+    // Subscribe before looking the value up, not to miss the versions created in between
+    let mut notifications = notifier.subscribe();
+
+    loop {
+        let mut db = match pool.acquire().await {
+            Ok(db) => db,
+            Err(err) => {
+                error!("[get_value] Error acquiring connection: {:?}", err);
                 return Err(VersionsServiceError::Unknown);
             }
+        };
+        let result = resolve_latest_version(
+            &mut db,
+            secrets,
+            tenant,
+            principal,
+            Role::Reader,
+            domain_slug,
+            environment_slug,
+            key,
+        )
+        .await;
+        let is_unchanged = match (&result, after_version) {
+            (Ok(resolved), Some(after_version)) => resolved.version.version == after_version,
+            (Err(VersionsServiceError::NoVersions), Some(_)) => true,
+            _ => false,
+        };
+        if !is_unchanged || Instant::now() >= deadline {
+            return result;
         }
+        let scope = get_value_scope(&mut db, tenant, domain_slug, environment_slug, key).await?;
+        drop(db);
+        wait_for_version(&mut notifications, &scope, deadline).await;
     }
-    // Get Config
-    let config_result =
-        configs_repo::get_config(&mut *db, domain_result.unwrap().id.as_str(), key).await;
-    if let Err(get_config_error) = config_result {
-        match get_config_error {
-            ConfigsRepoError::NotFound => return Err(VersionsServiceError::ConfigNotFound),
-            _ => {
+}
+
+/// Retrieve the latest value of a config like [`get_value`], for the purpose of revealing it
+/// when it is a secret. Only admins of the domain are allowed to, and inherited values also
+/// require admin over the ancestor holding them, as anyone can pick a domain as their parent
+pub async fn reveal_value(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+) -> Result<ResolvedVersion, VersionsServiceError> {
+    let resolved = resolve_latest_version(
+        &mut db,
+        secrets,
+        tenant,
+        principal,
+        Role::Admin,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+
+    if resolved.domain != domain_slug {
+        let ancestor = match domains_repo::get_domain_by_slug(&mut db, tenant, &resolved.domain)
+            .await
+        {
+            Ok(domain) => domain,
+            Err(DomainsRepoError::NotFound) => return Err(VersionsServiceError::DomainNotFound),
+            Err(get_domain_error) => {
                 error!(
-                    "[get_versions] Error fetching config: {:?}",
-                    get_config_error
+                    "[reveal_value] Error fetching domains: {:?}",
+                    get_domain_error
                 );
                 return Err(VersionsServiceError::Unknown);
             }
+        };
+        authorize(
+            &mut db,
+            tenant,
+            principal,
+            Some(ancestor.id.as_str()),
+            Role::Admin,
+        )
+        .await?;
+    }
+
+    Ok(resolved)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn get_version(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+    version: i32,
+) -> Result<ConfigVersion, VersionsServiceError> {
+    let (config, environment_id) = get_domain_config(
+        &mut db,
+        tenant,
+        principal,
+        Role::Reader,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+
+    match versions_repo::get_version(
+        &mut db,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+        version,
+    )
+    .await
+    {
+        Ok(version) => Ok(version),
+        Err(VersionsRepoError::NotFound) => Err(VersionsServiceError::VersionNotFound),
+        Err(_) => Err(VersionsServiceError::Unknown),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn rollback_version(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+    version: i32,
+) -> Result<ConfigVersion, VersionsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[rollback_version] Error starting transaction: {:?}", err);
+            return Err(VersionsServiceError::Unknown);
+        }
+    };
+
+    let (config, environment_id) = get_domain_config(
+        &mut tx,
+        tenant,
+        principal,
+        Role::Writer,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+    lock_versions(&mut tx, tenant, config.id.as_str()).await?;
+    let latest_version = match get_latest_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+    )
+    .await
+    {
+        Ok(version) => Some(version),
+        Err(VersionsServiceError::NoVersions) => None,
+        Err(err) => return Err(err),
+    };
+
+    let version = match versions_repo::rollback_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        environment_id.as_deref(),
+        version,
+        actor.name.as_str(),
+    )
+    .await
+    {
+        Ok(version) => version,
+        Err(VersionsRepoError::NotFound) => return Err(VersionsServiceError::VersionNotFound),
+        Err(VersionsRepoError::AlreadyExists) => return Err(VersionsServiceError::VersionConflict),
+        Err(_) => return Err(VersionsServiceError::Unknown),
+    };
+    record(
+        &mut tx,
+        tenant,
+        actor,
+        audit_service::version_created(
+            domain_slug,
+            environment_slug,
+            key,
+            latest_version.as_ref().map(|latest| &latest.value),
+            &version.value,
+        ),
+    )
+    .await?;
+
+    match tx.commit().await {
+        Ok(_) => Ok(version),
+        Err(err) => {
+            error!("[rollback_version] Error committing transaction: {:?}", err);
+            Err(VersionsServiceError::Unknown)
         }
     }
+}
 
-    // Get versions
-    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
-    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+/// Compare two config values. Objects and arrays are described with a JSON Patch,
+/// while any other change is reported with the previous and new values
+pub fn diff_values(from: &ConfigValue, to: &ConfigValue) -> ValueDiff {
+    let type_change = if from.type_name() != to.type_name() {
+        Some((from.type_name(), to.type_name()))
+    } else {
+        None
+    };
 
-    let result =
-        versions_repo::get_versions(&mut *db, config_result.unwrap().id.as_str(), limit, offset)
-            .await;
-    match result {
-        Ok(versions) => Ok(List::from_items(versions, limit, offset)),
-        Err(configs_repo_err) => match configs_repo_err {
-            VersionsRepoError::NotFound => Err(VersionsServiceError::ConfigNotFound),
-            _ => Err(VersionsServiceError::Unknown),
+    match (from, to) {
+        (
+            ConfigValue::Object(_) | ConfigValue::Array(_),
+            ConfigValue::Object(_) | ConfigValue::Array(_),
+        ) => ValueDiff {
+            type_change,
+            value_change: None,
+            patch: Some(json_patch::diff(
+                &Value::from(from.clone()),
+                &Value::from(to.clone()),
+            )),
         },
+        _ => ValueDiff {
+            type_change,
+            value_change: if from != to {
+                Some((from.clone(), to.clone()))
+            } else {
+                None
+            },
+            patch: None,
+        },
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn diff_versions(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    environment_slug: Option<&str>,
+    key: &str,
+    from_version: i32,
+    to_version: i32,
+) -> Result<ValueDiff, VersionsServiceError> {
+    let (config, environment_id) = get_domain_config(
+        &mut db,
+        tenant,
+        principal,
+        Role::Reader,
+        domain_slug,
+        environment_slug,
+        key,
+    )
+    .await?;
+
+    let mut versions = vec![];
+    for version in [from_version, to_version] {
+        match versions_repo::get_version(
+            &mut db,
+            secrets,
+            tenant,
+            config.id.as_str(),
+            environment_id.as_deref(),
+            version,
+        )
+        .await
+        {
+            Ok(version) => versions.push(version),
+            Err(VersionsRepoError::NotFound) => return Err(VersionsServiceError::VersionNotFound),
+            Err(_) => return Err(VersionsServiceError::Unknown),
+        }
+    }
+
+    Ok(diff_values(&versions[0].value, &versions[1].value))
+}
+
+pub async fn get_value_property(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    key: &str,
+    path: &[String],
+) -> Result<ResolvedVersion, VersionsServiceError> {
+    let ResolvedVersion { domain, version } = resolve_latest_version(
+        &mut db,
+        secrets,
+        tenant,
+        principal,
+        Role::Reader,
+        domain_slug,
+        None,
+        key,
+    )
+    .await?;
+
+    let (document, _) = to_document(version.value)?;
+    match json_pointer::get(&document, path) {
+        Some(property) => Ok(ResolvedVersion {
+            domain,
+            version: ConfigVersion {
+                value: ConfigValue::from(property.clone()),
+                ..version
+            },
+        }),
+        None => Err(VersionsServiceError::PropertyNotFound),
+    }
+}
+
+/// Set a property of the latest value of a config, as a new version. Secrets are sealed as a
+/// whole, so they cannot be set as properties of a document kept in the clear. Given the
+/// `If-Match` header of the request, the version is only created when the latest one still
+/// matches it. On dry runs, the version is created but the transaction is rolled back
+#[allow(clippy::too_many_arguments)]
+pub async fn set_value_property(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
+    domain_slug: &str,
+    key: &str,
+    path: &[String],
+    config_value: ConfigValue,
+    message: Option<&str>,
+    if_match: Option<&str>,
+    dry_run: bool,
+) -> Result<VersionChange, VersionsServiceError> {
+    if let ConfigValue::Secret(_) = config_value {
+        return Err(VersionsServiceError::SecretProperty);
     }
+
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!("[set_value_property] Error starting transaction: {:?}", err);
+            return Err(VersionsServiceError::Unknown);
+        }
+    };
+
+    let (config, _) = get_domain_config(
+        &mut tx,
+        tenant,
+        principal,
+        Role::Writer,
+        domain_slug,
+        None,
+        key,
+    )
+    .await?;
+    lock_versions(&mut tx, tenant, config.id.as_str()).await?;
+    let latest_version =
+        get_latest_version(&mut tx, secrets, tenant, config.id.as_str(), None).await?;
+    if let Some(if_match) = if_match {
+        if !matches_version(if_match, latest_version.version) {
+            return Err(VersionsServiceError::VersionMismatch);
+        }
+    }
+
+    let (mut document, is_text) = to_document(latest_version.value.clone())?;
+    json_pointer::set(&mut document, path, Value::from(config_value))
+        .map_err(|_| VersionsServiceError::PropertyNotFound)?;
+
+    let version = match versions_repo::create_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        None,
+        from_document(document, is_text),
+        actor.name.as_str(),
+        message,
+    )
+    .await
+    {
+        Ok(version) => version,
+        Err(VersionsRepoError::AlreadyExists) => return Err(VersionsServiceError::VersionConflict),
+        Err(_) => return Err(VersionsServiceError::Unknown),
+    };
+    record(
+        &mut tx,
+        tenant,
+        actor,
+        audit_service::version_created(
+            domain_slug,
+            None,
+            key,
+            Some(&latest_version.value),
+            &version.value,
+        ),
+    )
+    .await?;
+
+    if let Err(err) = finish_transaction(tx, dry_run).await {
+        error!(
+            "[set_value_property] Error finishing transaction: {:?}",
+            err
+        );
+        return Err(VersionsServiceError::Unknown);
+    }
+
+    Ok(VersionChange {
+        diff: Some(diff_values(&latest_version.value, &version.value)),
+        version,
+    })
+}
+
+/// Delete a property of the latest value of a config, as a new version. Given the `If-Match`
+/// header of the request, the version is only created when the latest one still matches it.
+/// On dry runs, the version is created but the transaction is rolled back
+#[allow(clippy::too_many_arguments)]
+pub async fn delete_value_property(
+    mut db: Connection<ConfigMonkeyDb>,
+    secrets: &SecretsCipher,
+    tenant: &str,
+    principal: &Principal,
+    actor: &Actor,
+    domain_slug: &str,
+    key: &str,
+    path: &[String],
+    if_match: Option<&str>,
+    dry_run: bool,
+) -> Result<VersionChange, VersionsServiceError> {
+    let mut tx = match db.begin().await {
+        Ok(tx) => tx,
+        Err(err) => {
+            error!(
+                "[delete_value_property] Error starting transaction: {:?}",
+                err
+            );
+            return Err(VersionsServiceError::Unknown);
+        }
+    };
+
+    let (config, _) = get_domain_config(
+        &mut tx,
+        tenant,
+        principal,
+        Role::Writer,
+        domain_slug,
+        None,
+        key,
+    )
+    .await?;
+    lock_versions(&mut tx, tenant, config.id.as_str()).await?;
+    let latest_version =
+        get_latest_version(&mut tx, secrets, tenant, config.id.as_str(), None).await?;
+    if let Some(if_match) = if_match {
+        if !matches_version(if_match, latest_version.version) {
+            return Err(VersionsServiceError::VersionMismatch);
+        }
+    }
+
+    let (mut document, is_text) = to_document(latest_version.value.clone())?;
+    json_pointer::remove(&mut document, path)
+        .map_err(|_| VersionsServiceError::PropertyNotFound)?;
+
+    let version = match versions_repo::create_version(
+        &mut tx,
+        secrets,
+        tenant,
+        config.id.as_str(),
+        None,
+        from_document(document, is_text),
+        actor.name.as_str(),
+        None,
+    )
+    .await
+    {
+        Ok(version) => version,
+        Err(VersionsRepoError::AlreadyExists) => return Err(VersionsServiceError::VersionConflict),
+        Err(_) => return Err(VersionsServiceError::Unknown),
+    };
+    record(
+        &mut tx,
+        tenant,
+        actor,
+        audit_service::version_created(
+            domain_slug,
+            None,
+            key,
+            Some(&latest_version.value),
+            &version.value,
+        ),
+    )
+    .await?;
+
+    if let Err(err) = finish_transaction(tx, dry_run).await {
+        error!(
+            "[delete_value_property] Error finishing transaction: {:?}",
+            err
+        );
+        return Err(VersionsServiceError::Unknown);
+    }
+
+    Ok(VersionChange {
+        diff: Some(diff_values(&latest_version.value, &version.value)),
+        version,
+    })
 }
@@ -0,0 +1,413 @@
+use crate::{
+    db::{
+        db::ConfigMonkeyDb,
+        notifications::{Notification, Notifier, DOMAIN_EVENT_CHANNEL},
+    },
+    models::{
+        domain::Domain,
+        event::DomainEventKind,
+        list::List,
+        principal::{Principal, Role},
+        webhook::{DueDelivery, Webhook, WebhookDelivery, WebhookDeliveryStatus},
+    },
+    repos::{
+        domains_repo::{self, DomainsRepoError},
+        webhooks_repo::{self, WebhooksRepoError},
+    },
+    services::grants_service::{authorize, AuthorizationError},
+    shared::{secrets::SecretsCipher, webhooks::WebhookSender},
+};
+use reqwest::Url;
+use rocket::{
+    error,
+    futures::future::join_all,
+    serde::json::{json, Value},
+    tokio::{
+        self,
+        sync::broadcast::{error::RecvError, Receiver},
+        time::timeout,
+    },
+    Orbit, Rocket,
+};
+use rocket_db_pools::{Connection, Database};
+use sqlx::{types::Uuid, PgConnection, PgPool};
+use std::{future::pending, time::Duration};
+
+pub enum WebhooksServiceError {
+    Unknown,
+    DomainNotFound,
+    WebhookNotFound,
+    InvalidUrl,
+    InvalidEvents,
+    InvalidKeys,
+    InvalidSecret,
+    Forbidden,
+}
+
+impl WebhooksServiceError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            WebhooksServiceError::DomainNotFound => "domain_not_found",
+            WebhooksServiceError::WebhookNotFound => "webhook_not_found",
+            WebhooksServiceError::InvalidUrl => "invalid_url",
+            WebhooksServiceError::InvalidEvents => "invalid_events",
+            WebhooksServiceError::InvalidKeys => "invalid_keys",
+            WebhooksServiceError::InvalidSecret => "invalid_secret",
+            WebhooksServiceError::Forbidden => "forbidden",
+            WebhooksServiceError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            WebhooksServiceError::DomainNotFound => "Domain not found",
+            WebhooksServiceError::WebhookNotFound => "Webhook not found",
+            WebhooksServiceError::InvalidUrl => "The url must be an absolute http or https url",
+            WebhooksServiceError::InvalidEvents => "The events must hold at least one of config_created, config_deleted or version_created",
+            WebhooksServiceError::InvalidKeys => "The keys must hold at least one key, or be left out to match every key",
+            WebhooksServiceError::InvalidSecret => "The secret must not be empty",
+            WebhooksServiceError::Forbidden => "The principal is not allowed to perform this operation",
+            WebhooksServiceError::Unknown => "Unknown error",
+        }
+    }
+}
+
+impl From<AuthorizationError> for WebhooksServiceError {
+    fn from(error: AuthorizationError) -> Self {
+        match error {
+            AuthorizationError::Forbidden => WebhooksServiceError::Forbidden,
+            AuthorizationError::Unknown => WebhooksServiceError::Unknown,
+        }
+    }
+}
+
+const DEFAULT_LIMIT: i32 = 10;
+const DEFAULT_OFFSET: i32 = 0;
+
+/// Changes webhooks are notified of unless they name others
+const DEFAULT_EVENTS: [DomainEventKind; 2] = [
+    DomainEventKind::VersionCreated,
+    DomainEventKind::ConfigDeleted,
+];
+
+/// Deliveries attempted at once
+const BATCH_SIZE: i32 = 20;
+
+/// Longest time between two checks for due deliveries
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Time to wait before checking for due deliveries again, after failing to
+const ERROR_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Fetch a domain, checking that the principal holds the required role over it
+async fn get_domain(
+    db: &mut PgConnection,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    required_role: Role,
+) -> Result<Domain, WebhooksServiceError> {
+    match domains_repo::get_domain_by_slug(db, tenant, domain_slug).await {
+        Ok(domain) => {
+            authorize(
+                db,
+                tenant,
+                principal,
+                Some(domain.id.as_str()),
+                required_role,
+            )
+            .await?;
+            Ok(domain)
+        }
+        Err(DomainsRepoError::NotFound) => Err(WebhooksServiceError::DomainNotFound),
+        Err(get_domain_error) => {
+            error!(
+                "[get_domain] Error fetching domains: {:?}",
+                get_domain_error
+            );
+            Err(WebhooksServiceError::Unknown)
+        }
+    }
+}
+
+fn validate_url(url: &str) -> bool {
+    match Url::parse(url) {
+        Ok(url) => url.scheme() == "http" || url.scheme() == "https",
+        Err(_) => false,
+    }
+}
+
+/// Register a webhook on a domain, notified of the given kinds of changes to the configs
+/// with the given keys, or to all of them. Only admins of the domain are allowed to
+#[allow(clippy::too_many_arguments)]
+pub async fn create_webhook(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    url: &str,
+    events: Option<&[String]>,
+    keys: Option<&[String]>,
+    secret: &str,
+) -> Result<Webhook, WebhooksServiceError> {
+    if !validate_url(url) {
+        return Err(WebhooksServiceError::InvalidUrl);
+    }
+    let events: Vec<DomainEventKind> = match events {
+        Some(names) => names
+            .iter()
+            .map(|name| DomainEventKind::from_name(name))
+            .collect::<Option<_>>()
+            .ok_or(WebhooksServiceError::InvalidEvents)?,
+        None => DEFAULT_EVENTS.to_vec(),
+    };
+    if events.is_empty() {
+        return Err(WebhooksServiceError::InvalidEvents);
+    }
+    if keys.is_some_and(|keys| keys.is_empty()) {
+        return Err(WebhooksServiceError::InvalidKeys);
+    }
+    if secret.is_empty() {
+        return Err(WebhooksServiceError::InvalidSecret);
+    }
+
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    match webhooks_repo::create_webhook(&mut db, domain.id.as_str(), url, &events, keys, secret)
+        .await
+    {
+        Ok(webhook) => Ok(webhook),
+        Err(_) => Err(WebhooksServiceError::Unknown),
+    }
+}
+
+pub async fn get_webhooks(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<Webhook>, WebhooksServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    match webhooks_repo::get_webhooks(&mut db, domain.id.as_str(), limit, offset).await {
+        Ok(webhooks) => Ok(List::from_items(webhooks, limit, offset)),
+        Err(_) => Err(WebhooksServiceError::Unknown),
+    }
+}
+
+/// Delete a webhook, giving up on the events not delivered to it yet
+pub async fn delete_webhook(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    id: &str,
+) -> Result<(), WebhooksServiceError> {
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    if Uuid::parse_str(id).is_err() {
+        return Err(WebhooksServiceError::WebhookNotFound);
+    }
+
+    match webhooks_repo::delete_webhook(&mut db, domain.id.as_str(), id).await {
+        Ok(()) => Ok(()),
+        Err(WebhooksRepoError::NotFound) => Err(WebhooksServiceError::WebhookNotFound),
+        Err(_) => Err(WebhooksServiceError::Unknown),
+    }
+}
+
+/// Retrieve the log of the deliveries to a webhook, from the most recent event to the oldest
+#[allow(clippy::too_many_arguments)]
+pub async fn get_webhook_deliveries(
+    mut db: Connection<ConfigMonkeyDb>,
+    tenant: &str,
+    principal: &Principal,
+    domain_slug: &str,
+    id: &str,
+    limit_opt: Option<i32>,
+    offset_opt: Option<i32>,
+) -> Result<List<WebhookDelivery>, WebhooksServiceError> {
+    let limit = limit_opt.unwrap_or(DEFAULT_LIMIT);
+    let offset = offset_opt.unwrap_or(DEFAULT_OFFSET);
+
+    let domain = get_domain(&mut db, tenant, principal, domain_slug, Role::Admin).await?;
+    if Uuid::parse_str(id).is_err() {
+        return Err(WebhooksServiceError::WebhookNotFound);
+    }
+    let webhook = match webhooks_repo::get_webhook(&mut db, domain.id.as_str(), id).await {
+        Ok(webhook) => webhook,
+        Err(WebhooksRepoError::NotFound) => return Err(WebhooksServiceError::WebhookNotFound),
+        Err(_) => return Err(WebhooksServiceError::Unknown),
+    };
+    match webhooks_repo::get_webhook_deliveries(&mut db, webhook.id.as_str(), limit, offset).await {
+        Ok(deliveries) => Ok(List::from_items(deliveries, limit, offset)),
+        Err(_) => Err(WebhooksServiceError::Unknown),
+    }
+}
+
+/// Start delivering the events enqueued for webhooks, for as long as the app runs. Deliveries
+/// left pending when the app was last stopped are picked up where they were left
+pub async fn start_webhook_deliveries(rocket: &Rocket<Orbit>) {
+    let (Some(db), Some(notifier), Some(sender), Some(secrets)) = (
+        ConfigMonkeyDb::fetch(rocket),
+        rocket.state::<Notifier>(),
+        rocket.state::<WebhookSender>(),
+        rocket.state::<SecretsCipher>(),
+    ) else {
+        return;
+    };
+    let pool: &PgPool = db;
+
+    tokio::spawn(run_deliveries(
+        pool.clone(),
+        notifier.subscribe(),
+        sender.clone(),
+        secrets.clone(),
+    ));
+}
+
+/// Deliver the due events, then wait for new events or for the next retry to be due
+async fn run_deliveries(
+    pool: PgPool,
+    mut notifications: Receiver<Option<Notification>>,
+    sender: WebhookSender,
+    secrets: SecretsCipher,
+) {
+    loop {
+        let wait = match deliver_batch(&pool, &sender, &secrets).await {
+            Ok(true) => continue,
+            Ok(false) => next_attempt_in(&pool).await.min(IDLE_INTERVAL),
+            Err(_) => ERROR_INTERVAL,
+        };
+        let _ = timeout(wait, wait_for_event(&mut notifications)).await;
+    }
+}
+
+/// Deliveries are enqueued within the transactions appending the events they deliver
+async fn wait_for_event(notifications: &mut Receiver<Option<Notification>>) {
+    loop {
+        match notifications.recv().await {
+            Ok(Some(notification)) if notification.channel != DOMAIN_EVENT_CHANNEL => continue,
+            Err(RecvError::Closed) => return pending().await,
+            _ => return,
+        }
+    }
+}
+
+async fn next_attempt_in(pool: &PgPool) -> Duration {
+    let next = match pool.acquire().await {
+        Ok(mut db) => webhooks_repo::get_next_attempt_in_ms(&mut db).await,
+        Err(_) => return ERROR_INTERVAL,
+    };
+    match next {
+        Ok(Some(ms)) => Duration::from_millis(ms as u64),
+        Ok(None) => IDLE_INTERVAL,
+        Err(_) => ERROR_INTERVAL,
+    }
+}
+
+/// Attempt a batch of due deliveries at once, returning whether there may be more of them
+async fn deliver_batch(
+    pool: &PgPool,
+    sender: &WebhookSender,
+    secrets: &SecretsCipher,
+) -> Result<bool, WebhooksServiceError> {
+    let mut db = match pool.acquire().await {
+        Ok(db) => db,
+        Err(err) => {
+            error!("[deliver_batch] Error acquiring connection: {:?}", err);
+            return Err(WebhooksServiceError::Unknown);
+        }
+    };
+    let lease_ms = sender.lease().as_millis() as i64;
+    let deliveries =
+        match webhooks_repo::claim_due_deliveries(&mut db, secrets, BATCH_SIZE, lease_ms).await {
+            Ok(deliveries) => deliveries,
+            Err(_) => return Err(WebhooksServiceError::Unknown),
+        };
+    drop(db);
+
+    let count = deliveries.len() as i32;
+    join_all(
+        deliveries
+            .into_iter()
+            .map(|delivery| deliver(pool, sender, delivery)),
+    )
+    .await;
+    Ok(count == BATCH_SIZE)
+}
+
+/// Body of a delivery. Secrets are redacted, as everywhere values are shown
+fn to_payload(delivery: &DueDelivery) -> String {
+    let event = &delivery.event;
+    json!({
+        "id": delivery.id,
+        "event": event.kind.name(),
+        "event_id": event.id,
+        "domain": delivery.domain,
+        "key": event.key,
+        "environment": event.environment,
+        "version": event.version,
+        "value": event.value.clone().map(Value::from),
+        "created_at": event.created_at,
+    })
+    .to_string()
+}
+
+/// Attempt a delivery and record how it went. Failed attempts are retried with an exponential
+/// backoff, until the configured number of attempts is reached
+async fn deliver(pool: &PgPool, sender: &WebhookSender, delivery: DueDelivery) {
+    let result = sender
+        .send(
+            delivery.url.as_str(),
+            delivery.secret.as_str(),
+            delivery.id.as_str(),
+            delivery.event.kind.name(),
+            to_payload(&delivery),
+        )
+        .await;
+
+    let (status, response_status, error, retry_in) = match result {
+        Ok(response_status) => (
+            WebhookDeliveryStatus::Delivered,
+            Some(response_status),
+            None,
+            Duration::ZERO,
+        ),
+        Err(failure) if delivery.attempts >= sender.max_attempts() => (
+            WebhookDeliveryStatus::Failed,
+            failure.response_status,
+            Some(failure.error),
+            Duration::ZERO,
+        ),
+        Err(failure) => (
+            WebhookDeliveryStatus::Pending,
+            failure.response_status,
+            Some(failure.error),
+            sender.backoff(delivery.attempts),
+        ),
+    };
+
+    let recorded = match pool.acquire().await {
+        Ok(mut db) => {
+            webhooks_repo::record_delivery_attempt(
+                &mut db,
+                delivery.id.as_str(),
+                status,
+                response_status,
+                error.as_deref(),
+                retry_in.as_millis() as i64,
+            )
+            .await
+        }
+        Err(_) => Err(WebhooksRepoError::Unknown),
+    };
+    // The delivery is attempted again once its lease expires
+    if recorded.is_err() {
+        error!(
+            "[deliver] Error recording the attempt of delivery {}",
+            delivery.id
+        );
+    }
+}
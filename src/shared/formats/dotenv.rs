@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+
+use rocket::{
+    http::ContentType,
+    serde::json::{from_str, serde_json::Map, Value},
+};
+
+use super::{flatten, DocumentFormat, FormatError, PathSegment};
+
+/// The document as `.env` variables, one `NAME=value` line per scalar value.
+///
+/// Names are built by joining the path to the value with `_`, using the index for
+/// array items, upper casing it and replacing anything other than letters, digits
+/// and `_` with `_`, e.g. `{"database": {"hosts": ["a"]}}` becomes `DATABASE_HOSTS_0="a"`.
+/// Strings are double quoted with `\`, `"`, `$` and line breaks escaped, numbers and
+/// booleans are written as is and null becomes an empty value.
+///
+/// When reading, names are kept as they are. Quoted values are always strings, while
+/// unquoted ones become booleans or numbers when they look like one, null when empty
+/// and strings otherwise, so that exported files can be imported back unchanged
+pub struct Dotenv;
+
+fn to_name(path: &[PathSegment]) -> String {
+    let name = path
+        .iter()
+        .map(|segment| match segment {
+            PathSegment::Key(key) => key.to_string(),
+            PathSegment::Index(index) => index.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("_")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+
+    // Variable names can't start with a digit
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", name)
+    } else {
+        name
+    }
+}
+
+fn to_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => {
+            let mut escaped = String::with_capacity(text.len() + 2);
+            escaped.push('"');
+            for c in text.chars() {
+                match c {
+                    '\\' => escaped.push_str("\\\\"),
+                    '"' => escaped.push_str("\\\""),
+                    '$' => escaped.push_str("\\$"),
+                    '\n' => escaped.push_str("\\n"),
+                    '\r' => escaped.push_str("\\r"),
+                    _ => escaped.push(c),
+                }
+            }
+            escaped.push('"');
+            escaped
+        }
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+/// Parse the value of a variable, dropping any trailing comment
+fn parse_value(raw: &str) -> Result<Value, FormatError> {
+    let raw = raw.trim();
+    if let Some(quoted) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.chars();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some(c) => value.push(c),
+                    None => return Err(FormatError::InvalidDocument),
+                },
+                Some(c) => value.push(c),
+                None => return Err(FormatError::InvalidDocument),
+            }
+        }
+        let rest = chars.as_str().trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(FormatError::InvalidDocument);
+        }
+        return Ok(Value::String(value));
+    }
+    if let Some(quoted) = raw.strip_prefix('\'') {
+        let Some((value, rest)) = quoted.split_once('\'') else {
+            return Err(FormatError::InvalidDocument);
+        };
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err(FormatError::InvalidDocument);
+        }
+        return Ok(Value::String(value.to_string()));
+    }
+
+    let unquoted = match raw.find(" #") {
+        Some(comment) => raw[..comment].trim_end(),
+        None => raw,
+    };
+    if unquoted.is_empty() {
+        return Ok(Value::Null);
+    }
+    if let Ok(value @ (Value::Bool(_) | Value::Number(_))) = from_str::<Value>(unquoted) {
+        return Ok(value);
+    }
+    Ok(Value::String(unquoted.to_string()))
+}
+
+impl DocumentFormat for Dotenv {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("text", "x-dotenv")
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError> {
+        let mut names = HashSet::new();
+        let mut output = String::new();
+        for (path, value) in flatten(document) {
+            let name = to_name(&path);
+            if !names.insert(name.clone()) {
+                return Err(FormatError::KeyCollision);
+            }
+            output.push_str(&format!("{}={}\n", name, to_value(value)));
+        }
+        Ok(output)
+    }
+
+    fn parse(&self, input: &str) -> Result<Map<String, Value>, FormatError> {
+        let mut document = Map::new();
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let Some((name, raw_value)) = line.split_once('=') else {
+                return Err(FormatError::InvalidDocument);
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(FormatError::InvalidDocument);
+            }
+            document.insert(name.to_string(), parse_value(raw_value)?);
+        }
+        Ok(document)
+    }
+}
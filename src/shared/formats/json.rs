@@ -0,0 +1,23 @@
+use rocket::{
+    http::ContentType,
+    serde::json::{from_str, serde_json::Map, to_pretty_string, Value},
+};
+
+use super::{DocumentFormat, FormatError};
+
+/// The document as a pretty printed JSON object
+pub struct Json;
+
+impl DocumentFormat for Json {
+    fn content_type(&self) -> ContentType {
+        ContentType::JSON
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError> {
+        to_pretty_string(document).map_err(|_| FormatError::Unknown)
+    }
+
+    fn parse(&self, input: &str) -> Result<Map<String, Value>, FormatError> {
+        from_str(input).map_err(|_| FormatError::InvalidDocument)
+    }
+}
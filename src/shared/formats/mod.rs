@@ -0,0 +1,121 @@
+use rocket::{
+    http::{ContentType, MediaType},
+    serde::json::{serde_json::Map, Value},
+};
+
+pub mod dotenv;
+pub mod json;
+pub mod properties;
+pub mod toml;
+pub mod yaml;
+
+#[derive(Debug)]
+pub enum FormatError {
+    KeyCollision,
+    InvalidDocument,
+    Unsupported,
+    Unknown,
+}
+
+impl FormatError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            FormatError::KeyCollision => "key_collision",
+            FormatError::InvalidDocument => "invalid_document",
+            FormatError::Unsupported => "unsupported_format",
+            FormatError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            FormatError::KeyCollision => {
+                "Two or more keys map to the same name once flattened for this format"
+            }
+            FormatError::InvalidDocument => {
+                "The document could not be parsed, or its top level is not a map of keys to values"
+            }
+            FormatError::Unsupported => "The format is not supported for this operation",
+            FormatError::Unknown => "Unknown error",
+        }
+    }
+}
+
+/// A file format that a configuration document, i.e. a map of config keys to values, can be written in
+pub trait DocumentFormat: Sync {
+    /// Content type of the serialized document
+    fn content_type(&self) -> ContentType;
+
+    /// Whether this format should be used for the requested media type
+    fn accepts(&self, media_type: &MediaType) -> bool {
+        let content_type = self.content_type();
+        media_type.top() == content_type.top() && media_type.sub() == content_type.sub()
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError>;
+
+    /// Read a document written in this format. Formats that can only be written keep this default
+    fn parse(&self, _input: &str) -> Result<Map<String, Value>, FormatError> {
+        Err(FormatError::Unsupported)
+    }
+}
+
+/// Supported formats, the first one being the default
+pub static FORMATS: [&dyn DocumentFormat; 5] = [
+    &json::Json,
+    &dotenv::Dotenv,
+    &toml::Toml,
+    &yaml::Yaml,
+    &properties::Properties,
+];
+
+/// Find the format for a media type. Wildcards resolve to the default format
+pub fn find_format(media_type: &MediaType) -> Option<&'static dyn DocumentFormat> {
+    if media_type.top() == "*" && media_type.sub() == "*" {
+        return Some(FORMATS[0]);
+    }
+    FORMATS
+        .iter()
+        .find(|format| format.accepts(media_type))
+        .copied()
+}
+
+/// A step in the path from the root of a document to one of its values
+#[derive(Clone, Copy)]
+pub enum PathSegment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Walk the document depth-first, collecting the path to every scalar value.
+/// Empty objects and arrays have no scalar values and are therefore left out
+pub fn flatten(document: &Map<String, Value>) -> Vec<(Vec<PathSegment<'_>>, &Value)> {
+    let mut leaves = vec![];
+    for (key, value) in document {
+        flatten_into(&mut leaves, vec![PathSegment::Key(key)], value);
+    }
+    leaves
+}
+
+fn flatten_into<'a>(
+    leaves: &mut Vec<(Vec<PathSegment<'a>>, &'a Value)>,
+    path: Vec<PathSegment<'a>>,
+    value: &'a Value,
+) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let mut child_path = path.clone();
+                child_path.push(PathSegment::Key(key));
+                flatten_into(leaves, child_path, child);
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate() {
+                let mut child_path = path.clone();
+                child_path.push(PathSegment::Index(index));
+                flatten_into(leaves, child_path, child);
+            }
+        }
+        _ => leaves.push((path, value)),
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashSet;
+
+use rocket::{
+    http::ContentType,
+    serde::json::{serde_json::Map, Value},
+};
+
+use super::{flatten, DocumentFormat, FormatError, PathSegment};
+
+/// The document as Java properties, one `key=value` line per scalar value.
+///
+/// Keys are built from the path to the value, joining object keys with `.` and
+/// appending array indexes in brackets, e.g. `{"database": {"hosts": ["a"]}}` becomes
+/// `database.hosts[0]=a`. Keys and values are escaped as expected by
+/// `java.util.Properties::load`, with characters outside of printable ASCII written
+/// as `\uXXXX`. Null becomes an empty value
+pub struct Properties;
+
+fn escape(text: &str, is_key: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for (position, c) in text.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{000c}' => escaped.push_str("\\f"),
+            // Leading whitespace is dropped from values, any whitespace ends a key
+            ' ' if is_key || position == 0 => escaped.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key || position == 0 => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => {
+                let mut units = [0; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    escaped.push_str(&format!("\\u{:04X}", unit));
+                }
+            }
+        }
+    }
+    escaped
+}
+
+fn to_key(path: &[PathSegment]) -> String {
+    let mut key = String::new();
+    for segment in path {
+        match segment {
+            PathSegment::Key(name) => {
+                if !key.is_empty() {
+                    key.push('.');
+                }
+                key.push_str(&escape(name, true));
+            }
+            PathSegment::Index(index) => key.push_str(&format!("[{}]", index)),
+        }
+    }
+    key
+}
+
+fn to_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => escape(text, false),
+        Value::Null => String::new(),
+        _ => value.to_string(),
+    }
+}
+
+impl DocumentFormat for Properties {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("text", "x-java-properties")
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError> {
+        let mut keys = HashSet::new();
+        let mut output = String::new();
+        for (path, value) in flatten(document) {
+            let key = to_key(&path);
+            if !keys.insert(key.clone()) {
+                return Err(FormatError::KeyCollision);
+            }
+            output.push_str(&format!("{}={}\n", key, to_value(value)));
+        }
+        Ok(output)
+    }
+}
@@ -0,0 +1,40 @@
+use rocket::{
+    http::ContentType,
+    serde::json::{serde_json::Map, Value},
+};
+
+use super::{DocumentFormat, FormatError};
+
+/// The document as a TOML table, with nested objects written as sub tables.
+/// TOML has no null, so null values are left out, including inside arrays
+pub struct Toml;
+
+fn without_nulls(value: &Value) -> Option<Value> {
+    match value {
+        Value::Null => None,
+        Value::Object(map) => Some(Value::Object(
+            map.iter()
+                .filter_map(|(key, child)| without_nulls(child).map(|child| (key.clone(), child)))
+                .collect(),
+        )),
+        Value::Array(items) => Some(Value::Array(
+            items.iter().filter_map(without_nulls).collect(),
+        )),
+        _ => Some(value.clone()),
+    }
+}
+
+impl DocumentFormat for Toml {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("application", "toml")
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError> {
+        let table = without_nulls(&Value::Object(document.clone()));
+        toml::to_string(&table).map_err(|_| FormatError::Unknown)
+    }
+
+    fn parse(&self, input: &str) -> Result<Map<String, Value>, FormatError> {
+        toml::from_str(input).map_err(|_| FormatError::InvalidDocument)
+    }
+}
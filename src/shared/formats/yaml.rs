@@ -0,0 +1,31 @@
+use rocket::{
+    http::{ContentType, MediaType},
+    serde::json::{serde_json::Map, Value},
+};
+
+use super::{DocumentFormat, FormatError};
+
+/// The document as a YAML mapping. Every JSON value has a YAML equivalent
+pub struct Yaml;
+
+impl DocumentFormat for Yaml {
+    fn content_type(&self) -> ContentType {
+        ContentType::new("application", "yaml")
+    }
+
+    /// Besides `application/yaml`, also honor the unregistered types still used by some clients
+    fn accepts(&self, media_type: &MediaType) -> bool {
+        matches!(
+            (media_type.top().as_str(), media_type.sub().as_str()),
+            ("application", "yaml") | ("application", "x-yaml") | ("text", "yaml")
+        )
+    }
+
+    fn serialize(&self, document: &Map<String, Value>) -> Result<String, FormatError> {
+        serde_yaml::to_string(document).map_err(|_| FormatError::Unknown)
+    }
+
+    fn parse(&self, input: &str) -> Result<Map<String, Value>, FormatError> {
+        serde_yaml::from_str(input).map_err(|_| FormatError::InvalidDocument)
+    }
+}
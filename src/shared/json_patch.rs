@@ -0,0 +1,51 @@
+use rocket::serde::json::{json, Value};
+
+use super::json_pointer::escape_token;
+
+/// Compute the JSON Patch (RFC 6902) operations that turn `from` into `to`.
+/// Objects and arrays are compared recursively, any other change replaces the whole node
+pub fn diff(from: &Value, to: &Value) -> Vec<Value> {
+    let mut patch = vec![];
+    diff_at(&mut patch, "", from, to);
+    patch
+}
+
+fn diff_at(patch: &mut Vec<Value>, path: &str, from: &Value, to: &Value) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (Value::Object(from_map), Value::Object(to_map)) => {
+            for (key, from_value) in from_map {
+                let child_path = format!("{}/{}", path, escape_token(key));
+                match to_map.get(key) {
+                    Some(to_value) => diff_at(patch, &child_path, from_value, to_value),
+                    None => patch.push(json!({"op": "remove", "path": child_path})),
+                }
+            }
+            for (key, to_value) in to_map {
+                if !from_map.contains_key(key) {
+                    let child_path = format!("{}/{}", path, escape_token(key));
+                    patch.push(json!({"op": "add", "path": child_path, "value": to_value}));
+                }
+            }
+        }
+        (Value::Array(from_items), Value::Array(to_items)) => {
+            let common = from_items.len().min(to_items.len());
+            for index in 0..common {
+                let child_path = format!("{}/{}", path, index);
+                diff_at(patch, &child_path, &from_items[index], &to_items[index]);
+            }
+            for (index, to_value) in to_items.iter().enumerate().skip(common) {
+                let child_path = format!("{}/{}", path, index);
+                patch.push(json!({"op": "add", "path": child_path, "value": to_value}));
+            }
+            // Remove from the end so that the remaining indexes stay valid
+            for index in (common..from_items.len()).rev() {
+                let child_path = format!("{}/{}", path, index);
+                patch.push(json!({"op": "remove", "path": child_path}));
+            }
+        }
+        _ => patch.push(json!({"op": "replace", "path": path, "value": to})),
+    }
+}
@@ -0,0 +1,109 @@
+use rocket::serde::json::Value;
+
+/// Errors raised while resolving a JSON Pointer (RFC 6901) against a document
+#[derive(Debug)]
+pub enum JsonPointerError {
+    NotFound,
+}
+
+/// Decode a single reference token, turning `~1` into `/` and `~0` into `~`
+pub fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Encode a single reference token, the inverse of `unescape_token`
+pub fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parse an array index as defined by RFC 6901: no signs and no leading zeros
+fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty()
+        || (token.len() > 1 && token.starts_with('0'))
+        || !token.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    token.parse::<usize>().ok()
+}
+
+fn child<'a>(node: &'a Value, token: &str) -> Option<&'a Value> {
+    match node {
+        Value::Object(map) => map.get(token),
+        Value::Array(items) => parse_index(token).and_then(|index| items.get(index)),
+        _ => None,
+    }
+}
+
+fn child_mut<'a>(node: &'a mut Value, token: &str) -> Option<&'a mut Value> {
+    match node {
+        Value::Object(map) => map.get_mut(token),
+        Value::Array(items) => parse_index(token).and_then(move |index| items.get_mut(index)),
+        _ => None,
+    }
+}
+
+/// Resolve the value referenced by `tokens`. An empty list references the whole document
+pub fn get<'a>(document: &'a Value, tokens: &[String]) -> Option<&'a Value> {
+    tokens
+        .iter()
+        .try_fold(document, |node, token| child(node, token))
+}
+
+/// Set the value referenced by `tokens`, adding it when the last token does not exist yet.
+/// For arrays, the last token may also be `-` or the array length to append a new item
+pub fn set(document: &mut Value, tokens: &[String], value: Value) -> Result<(), JsonPointerError> {
+    let Some((last, parents)) = tokens.split_last() else {
+        *document = value;
+        return Ok(());
+    };
+
+    let parent = parents
+        .iter()
+        .try_fold(document, |node, token| child_mut(node, token))
+        .ok_or(JsonPointerError::NotFound)?;
+
+    match parent {
+        Value::Object(map) => {
+            map.insert(last.to_string(), value);
+            Ok(())
+        }
+        Value::Array(items) => {
+            let index = if last == "-" {
+                items.len()
+            } else {
+                parse_index(last).ok_or(JsonPointerError::NotFound)?
+            };
+            if index < items.len() {
+                items[index] = value;
+            } else if index == items.len() {
+                items.push(value);
+            } else {
+                return Err(JsonPointerError::NotFound);
+            }
+            Ok(())
+        }
+        _ => Err(JsonPointerError::NotFound),
+    }
+}
+
+/// Remove the value referenced by `tokens` from the document, returning it
+pub fn remove(document: &mut Value, tokens: &[String]) -> Result<Value, JsonPointerError> {
+    let Some((last, parents)) = tokens.split_last() else {
+        return Err(JsonPointerError::NotFound);
+    };
+
+    let parent = parents
+        .iter()
+        .try_fold(document, |node, token| child_mut(node, token))
+        .ok_or(JsonPointerError::NotFound)?;
+
+    match parent {
+        Value::Object(map) => map.remove(last).ok_or(JsonPointerError::NotFound),
+        Value::Array(items) => match parse_index(last) {
+            Some(index) if index < items.len() => Ok(items.remove(index)),
+            _ => Err(JsonPointerError::NotFound),
+        },
+        _ => Err(JsonPointerError::NotFound),
+    }
+}
@@ -1 +1,6 @@
+pub mod formats;
+pub mod json_patch;
+pub mod json_pointer;
+pub mod secrets;
 pub mod validators;
+pub mod webhooks;
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use crate::models::config::SealedValue;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use rocket::{
+    error, fairing,
+    serde::{
+        json::{serde_json, Value},
+        Deserialize,
+    },
+    Build, Rocket,
+};
+
+const NONCE_LENGTH: usize = 12;
+
+/// Id of the key given by the `master_key` setting
+pub const DEFAULT_KEY_ID: &str = "default";
+
+/// Settings of secret values, read from the `secrets` key of the rocket configuration
+#[derive(Deserialize, Default)]
+#[serde(crate = "rocket::serde")]
+pub struct SecretsConfig {
+    /// Base64 encoded 256 bit key that values are encrypted with, known as `default`.
+    /// Secret values cannot be stored or revealed without a master key, while other values
+    /// are stored in the clear
+    pub master_key: Option<String>,
+    /// Base64 encoded 256 bit keys by id. Retired keys are kept around to decrypt the values
+    /// that were not re-encrypted yet
+    #[serde(default)]
+    pub master_keys: HashMap<String, String>,
+    /// Id of the key new values are encrypted with. It can be left out when there is
+    /// a single master key
+    pub active_key: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum SecretsError {
+    Disabled,
+    Undecryptable,
+    Unknown,
+}
+
+impl SecretsError {
+    pub fn code(&self) -> &'static str {
+        match *self {
+            SecretsError::Disabled => "secrets_disabled",
+            SecretsError::Undecryptable => "undecryptable_secret",
+            SecretsError::Unknown => "unknown_error",
+        }
+    }
+    pub fn message(&self) -> &'static str {
+        match *self {
+            SecretsError::Disabled => "Secret values require a master key to be configured",
+            SecretsError::Undecryptable => {
+                "The secret value could not be decrypted with the configured master keys"
+            }
+            SecretsError::Unknown => "Unknown error",
+        }
+    }
+}
+
+/// Encrypts and decrypts stored values with AES-256-GCM, under the configured master keys
+#[derive(Clone, Default)]
+pub struct SecretsCipher {
+    keys: HashMap<String, Aes256Gcm>,
+    active_key: Option<String>,
+}
+
+impl SecretsCipher {
+    /// Id of the master key new values are encrypted with, if any
+    pub fn active_key(&self) -> Option<&str> {
+        self.active_key.as_deref()
+    }
+
+    /// Encrypt a value with the active master key, under a random nonce
+    pub fn seal(&self, value: &Value) -> Result<SealedValue, SecretsError> {
+        let key_id = self.active_key.as_ref().ok_or(SecretsError::Disabled)?;
+        let cipher = &self.keys[key_id];
+
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let plaintext = value.to_string();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+            .map_err(|_| SecretsError::Unknown)?;
+
+        let mut envelope = nonce.to_vec();
+        envelope.extend(ciphertext);
+        Ok(SealedValue {
+            key_id: key_id.to_string(),
+            envelope: STANDARD.encode(envelope),
+        })
+    }
+
+    /// Decrypt a value with the master key it was sealed with
+    pub fn open(&self, sealed_value: &SealedValue) -> Result<Value, SecretsError> {
+        if self.active_key.is_none() {
+            return Err(SecretsError::Disabled);
+        }
+        let cipher = self
+            .keys
+            .get(&sealed_value.key_id)
+            .ok_or(SecretsError::Undecryptable)?;
+        let envelope = STANDARD
+            .decode(&sealed_value.envelope)
+            .map_err(|_| SecretsError::Undecryptable)?;
+        if envelope.len() < NONCE_LENGTH {
+            return Err(SecretsError::Undecryptable);
+        }
+        let (nonce, ciphertext) = envelope.split_at(NONCE_LENGTH);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| SecretsError::Undecryptable)?;
+        serde_json::from_slice(&plaintext).map_err(|_| SecretsError::Undecryptable)
+    }
+
+    fn from_config(config: SecretsConfig) -> Result<SecretsCipher, String> {
+        let mut encoded_keys = config.master_keys;
+        if let Some(master_key) = config.master_key {
+            if encoded_keys.contains_key(DEFAULT_KEY_ID) {
+                return Err(format!(
+                    "The `{}` key is given by both master_key and master_keys",
+                    DEFAULT_KEY_ID
+                ));
+            }
+            encoded_keys.insert(DEFAULT_KEY_ID.to_string(), master_key);
+        }
+
+        let mut keys = HashMap::new();
+        for (key_id, encoded_key) in encoded_keys {
+            let cipher = STANDARD
+                .decode(encoded_key.trim())
+                .ok()
+                .and_then(|key| Aes256Gcm::new_from_slice(&key).ok())
+                .ok_or(format!(
+                    "The `{}` master key must be a base64 encoded 256 bit key",
+                    key_id
+                ))?;
+            keys.insert(key_id, cipher);
+        }
+
+        let active_key = match config.active_key {
+            Some(active_key) if keys.contains_key(&active_key) => Some(active_key),
+            Some(active_key) => return Err(format!("Unknown active key `{}`", active_key)),
+            None if keys.len() > 1 => {
+                return Err("The active key must be set when there are several master keys".into())
+            }
+            None => keys.keys().next().cloned(),
+        };
+        Ok(SecretsCipher { keys, active_key })
+    }
+}
+
+/// Load the secrets settings and manage the resulting cipher. Rocket fails to ignite when
+/// a master key is not a base64 encoded 256 bit key, or when the active one is unknown
+pub async fn init_secrets(rocket: Rocket<Build>) -> fairing::Result {
+    let config = match rocket.figment().extract_inner::<SecretsConfig>("secrets") {
+        Ok(config) => config,
+        Err(err) if err.missing() => SecretsConfig::default(),
+        Err(err) => {
+            error!("Invalid secrets settings: {}", err);
+            return Err(rocket);
+        }
+    };
+
+    match SecretsCipher::from_config(config) {
+        Ok(cipher) => Ok(rocket.manage(cipher)),
+        Err(err) => {
+            error!("Failed to load the master keys: {}", err);
+            Err(rocket)
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use rocket::{error, fairing, serde::Deserialize, Build, Rocket};
+use sha2::Sha256;
+
+/// Header carrying the HMAC-SHA256 of the payload, keyed with the secret of the webhook
+pub const SIGNATURE_HEADER: &str = "X-ConfigMonkey-Signature";
+/// Header carrying the kind of change delivered
+pub const EVENT_HEADER: &str = "X-ConfigMonkey-Event";
+/// Header carrying the id of the delivery, which is kept across its attempts
+pub const DELIVERY_HEADER: &str = "X-ConfigMonkey-Delivery";
+
+/// Settings of webhooks, read from the `webhooks` key of the rocket configuration
+#[derive(Deserialize, Clone)]
+#[serde(crate = "rocket::serde", default)]
+pub struct WebhooksConfig {
+    /// Attempts made to deliver an event before giving up on it
+    pub max_attempts: i32,
+    /// Delay before retrying a failed attempt, in milliseconds, doubled after each of them
+    pub backoff_ms: u64,
+    /// Longest delay between two attempts, in milliseconds
+    pub max_backoff_ms: u64,
+    /// Time endpoints are given to answer, in milliseconds
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        WebhooksConfig {
+            max_attempts: 8,
+            backoff_ms: 1_000,
+            max_backoff_ms: 3_600_000,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Why an attempt to deliver an event failed
+pub struct DeliveryFailure {
+    /// HTTP status the endpoint answered with, if it answered
+    pub response_status: Option<i32>,
+    pub error: String,
+}
+
+/// Sends the payloads of webhooks over HTTP, signed with their secret
+#[derive(Clone)]
+pub struct WebhookSender {
+    client: reqwest::Client,
+    config: WebhooksConfig,
+}
+
+impl WebhookSender {
+    pub fn max_attempts(&self) -> i32 {
+        self.config.max_attempts
+    }
+
+    /// Delay before the next attempt, after the given number of failed ones
+    pub fn backoff(&self, attempts: i32) -> Duration {
+        let factor = 2u64.saturating_pow(attempts.max(1) as u32 - 1);
+        Duration::from_millis(
+            self.config
+                .backoff_ms
+                .saturating_mul(factor)
+                .min(self.config.max_backoff_ms),
+        )
+    }
+
+    /// Time an attempt may take, after which it is assumed to have been interrupted
+    pub fn lease(&self) -> Duration {
+        Duration::from_millis(self.config.timeout_ms) + Duration::from_secs(30)
+    }
+
+    /// POST a payload to a webhook. Only successful statuses count as delivered
+    pub async fn send(
+        &self,
+        url: &str,
+        secret: &str,
+        delivery_id: &str,
+        event: &str,
+        payload: String,
+    ) -> Result<i32, DeliveryFailure> {
+        let response = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json")
+            .header(SIGNATURE_HEADER, sign(secret, payload.as_bytes()))
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, delivery_id)
+            .body(payload)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => Ok(response.status().as_u16() as i32),
+            Ok(response) => Err(DeliveryFailure {
+                response_status: Some(response.status().as_u16() as i32),
+                error: format!("The endpoint answered with {}", response.status()),
+            }),
+            Err(err) => Err(DeliveryFailure {
+                response_status: None,
+                error: err.to_string(),
+            }),
+        }
+    }
+
+    fn from_config(config: WebhooksConfig) -> Result<WebhookSender, String> {
+        if config.max_attempts < 1 {
+            return Err("At least one attempt must be made to deliver events".into());
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .map_err(|err| err.to_string())?;
+        Ok(WebhookSender { client, config })
+    }
+}
+
+/// Signature of a payload, as `sha256=` followed by the hex encoded HMAC-SHA256 of the payload
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    // HMAC accepts keys of any length
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Load the webhooks settings and manage the resulting sender
+pub async fn init_webhooks(rocket: Rocket<Build>) -> fairing::Result {
+    let config = match rocket.figment().extract_inner::<WebhooksConfig>("webhooks") {
+        Ok(config) => config,
+        Err(err) if err.missing() => WebhooksConfig::default(),
+        Err(err) => {
+            error!("Invalid webhooks settings: {}", err);
+            return Err(rocket);
+        }
+    };
+
+    match WebhookSender::from_config(config) {
+        Ok(sender) => Ok(rocket.manage(sender)),
+        Err(err) => {
+            error!("Failed to set up webhooks: {}", err);
+            Err(rocket)
+        }
+    }
+}
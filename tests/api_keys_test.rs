@@ -0,0 +1,326 @@
+use chrono::{Duration, Utc};
+use configmonkey::routes::v1::{
+    api_keys_routes::{CreatedApiKeyDto, GetApiKeyDto},
+    domains_routes::GetDomainDto,
+    dtos::{ErrorDto, PaginatedListDto},
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::{Client, LocalResponse},
+    serde::json::json,
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+const TENANT: &str = "default";
+
+async fn h_create_key(client: &Client, name: &str, scopes: Vec<&str>) -> String {
+    let response = h_create_api_key(client, TENANT, name, scopes, None).await;
+    let response_body = h_parse_response(response).await;
+    let created_api_key_dto: CreatedApiKeyDto = h_parse_dto(response_body.as_str());
+    created_api_key_dto.key
+}
+
+async fn assert_error(response: LocalResponse<'_>, status: Status, code: &str) {
+    assert_eq!(response.status(), status);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, code);
+}
+
+#[sqlx::test]
+async fn create_api_key_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let expires_at = Utc::now() + Duration::days(30);
+
+    let response =
+        h_create_api_key(&client, TENANT, "poller", vec!["reader"], Some(expires_at)).await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let created_api_key_dto: CreatedApiKeyDto = h_parse_dto(response_body.as_str());
+    assert_eq!(created_api_key_dto.name, "poller");
+    assert_eq!(created_api_key_dto.scopes, vec!["reader"]);
+    assert_eq!(
+        created_api_key_dto.expires_at.map(|at| at.timestamp()),
+        Some(expires_at.timestamp())
+    );
+    assert!(created_api_key_dto
+        .key
+        .starts_with(format!("cm_{}_", created_api_key_dto.prefix).as_str()));
+
+    let response = h_get_domains_with_api_key(&client, created_api_key_dto.key.as_str()).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_api_key_err_exists(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_api_key(&client, TENANT, "poller", vec!["reader"], None).await;
+
+    let response = h_create_api_key(&client, TENANT, "poller", vec!["writer"], None).await;
+
+    assert_error(response, Status::Conflict, "api_key_already_exists").await;
+
+    // the name can be reused once the key is revoked
+    h_revoke_api_key(&client, TENANT, "poller").await;
+
+    let response = h_create_api_key(&client, TENANT, "poller", vec!["writer"], None).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_api_key_err_invalid(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_api_key(&client, TENANT, "poller", vec![], None).await;
+
+    assert_error(response, Status::BadRequest, "invalid_scopes").await;
+
+    let response = h_create_api_key(&client, TENANT, "poller", vec!["reader", "owner"], None).await;
+
+    assert_error(response, Status::BadRequest, "invalid_scopes").await;
+
+    let response = h_create_api_key(&client, TENANT, "config poller", vec!["reader"], None).await;
+
+    assert_error(response, Status::BadRequest, "invalid_slug").await;
+
+    let expires_at = Utc::now() - Duration::days(1);
+    let response =
+        h_create_api_key(&client, TENANT, "poller", vec!["reader"], Some(expires_at)).await;
+
+    assert_error(response, Status::BadRequest, "invalid_expiry").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_api_keys_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    for name in ["billing", "poller", "shipping"] {
+        h_create_api_key(&client, TENANT, name, vec!["reader", "writer"], None).await;
+    }
+    h_revoke_api_key(&client, TENANT, "billing").await;
+
+    let response = h_get_api_keys(&client, TENANT, Some(2), None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    // the plaintext key is never listed
+    assert!(!response_body.contains("\"key\""));
+
+    let get_api_keys_dto: PaginatedListDto<GetApiKeyDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_api_keys_dto.data.len(), 2);
+    assert_eq!(get_api_keys_dto.data[0].name, "billing");
+    assert!(get_api_keys_dto.data[0].revoked_at.is_some());
+    assert_eq!(get_api_keys_dto.data[1].name, "poller");
+    assert_eq!(get_api_keys_dto.data[1].scopes, vec!["reader", "writer"]);
+    assert!(get_api_keys_dto.data[1].revoked_at.is_none());
+    h_validate_pagination(
+        get_api_keys_dto.pagination,
+        2,
+        2,
+        0,
+        Some(String::from("/v1/api-keys?limit=2&offset=2")),
+        None,
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn revoke_api_key_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let key = h_create_key(&client, "poller", vec!["reader"]).await;
+
+    let response = h_revoke_api_key(&client, TENANT, "poller").await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = h_get_domains_with_api_key(&client, key.as_str()).await;
+
+    assert_error(response, Status::Unauthorized, "invalid_api_key").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn revoke_api_key_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_revoke_api_key(&client, TENANT, "poller").await;
+
+    assert_error(response, Status::NotFound, "api_key_not_found").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn api_key_err_invalid(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let key = h_create_key(&client, "poller", vec!["reader"]).await;
+
+    for invalid_key in [
+        String::from("not-a-key"),
+        String::from("cm_000000000000_secret"),
+        format!("{}0", key),
+    ] {
+        let response = h_get_domains_with_api_key(&client, invalid_key.as_str()).await;
+
+        assert_error(response, Status::Unauthorized, "invalid_api_key").await;
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn api_key_err_expired(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let expires_at = Utc::now() + Duration::seconds(1);
+
+    let response =
+        h_create_api_key(&client, TENANT, "poller", vec!["reader"], Some(expires_at)).await;
+    let response_body = h_parse_response(response).await;
+    let created_api_key_dto: CreatedApiKeyDto = h_parse_dto(response_body.as_str());
+
+    rocket::tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    let response = h_get_domains_with_api_key(&client, created_api_key_dto.key.as_str()).await;
+
+    assert_error(response, Status::Unauthorized, "invalid_api_key").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn api_key_success_scopes(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let reader_key = h_create_key(&client, "poller", vec!["reader"]).await;
+    let admin_key = h_create_key(&client, "provisioner", vec!["reader", "admin"]).await;
+
+    // keys are limited to their scopes even when every principal is allowed anything
+    let response = h_create_domain_with_api_key(&client, reader_key.as_str(), "billing").await;
+
+    assert_error(response, Status::Forbidden, "forbidden").await;
+
+    let response = h_create_domain_with_api_key(&client, admin_key.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_get_domains_with_api_key(&client, reader_key.as_str()).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn api_key_success_tenant(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "acme").await;
+    h_create_tenant_domain(&client, "acme", "billing", None).await;
+    let response = h_create_api_key(&client, "acme", "poller", vec!["reader"], None).await;
+    let response_body = h_parse_response(response).await;
+    let created_api_key_dto: CreatedApiKeyDto = h_parse_dto(response_body.as_str());
+
+    // requests authenticated with a key act on the tenant of the key
+    let response = h_get_domains_with_api_key(&client, created_api_key_dto.key.as_str()).await;
+
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 1);
+    assert_eq!(get_domains_dto.data[0].slug, "billing");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn api_key_success_without_token(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let secret = "configmonkey-secret";
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [secret]}),
+    )
+    .await;
+    let token = encode(
+        &Header::new(Algorithm::HS256),
+        &json!({"sub": "sre", "exp": Utc::now().timestamp() + 3600}),
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .expect("valid token");
+
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_create_api_key(&client, TENANT, "poller", vec!["reader"], None).await;
+
+    assert_error(response, Status::Unauthorized, "missing_token").await;
+
+    let response = h_create_api_key_with_token(&client, token.as_str(), "poller").await;
+    let response_body = h_parse_response(response).await;
+    let created_api_key_dto: CreatedApiKeyDto = h_parse_dto(response_body.as_str());
+
+    // machine clients authenticate with their key instead of a bearer token
+    let response = h_get_domains_with_api_key(&client, created_api_key_dto.key.as_str()).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    Ok(())
+}
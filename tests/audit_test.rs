@@ -0,0 +1,347 @@
+use configmonkey::routes::v1::{
+    audit_routes::GetAuditEventDto,
+    dtos::{ErrorDto, PaginatedListDto},
+};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::Client,
+    serde::json::json,
+};
+use sha2::{Digest, Sha256};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+fn h_hash(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+async fn h_audit_events(client: &Client, query: &str) -> PaginatedListDto<GetAuditEventDto> {
+    let response = h_get_audit_events(client, query).await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    h_parse_dto(response_body.as_str())
+}
+
+#[sqlx::test]
+async fn audit_records_mutations(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(20)).await;
+    h_rollback_version(&client, "configmonkey", "max_connections", 1).await;
+    h_delete_config(&client, "configmonkey", "max_connections").await;
+    h_delete_domain(&client, "configmonkey").await;
+
+    let events = h_audit_events(&client, "?limit=100").await;
+
+    let actions: Vec<&str> = events
+        .data
+        .iter()
+        .map(|event| event.action.as_str())
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            "domain_created",
+            "config_created",
+            "version_created",
+            "version_created",
+            "version_created",
+            "config_deleted",
+            "domain_deleted"
+        ]
+    );
+    assert!(events.data.iter().all(|event| event.actor == "anonymous"
+        && event.domain == "configmonkey"
+        && event.environment.is_none()));
+    assert_eq!(events.data[0].key, None);
+    assert_eq!(events.data[1].key, Some(String::from("max_connections")));
+
+    // Versions are described by the hashes of the values they replace and introduce
+    assert_eq!(events.data[2].before_hash, None);
+    assert_eq!(events.data[2].after_hash, Some(h_hash("10")));
+    assert_eq!(events.data[3].before_hash, Some(h_hash("10")));
+    assert_eq!(events.data[3].after_hash, Some(h_hash("20")));
+    assert_eq!(events.data[4].before_hash, Some(h_hash("20")));
+    assert_eq!(events.data[4].after_hash, Some(h_hash("10")));
+    assert_eq!(events.data[5].after_hash, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn audit_records_actor_and_request_id(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    client
+        .post("/v1/domains")
+        .header(ContentType::JSON)
+        .header(Header::new("X-Principal", "alice"))
+        .header(Header::new("X-Request-Id", "req-1234"))
+        .body(json!({ "slug": "configmonkey" }).to_string())
+        .dispatch()
+        .await;
+    h_create_environment(&client, "configmonkey", "prod").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_environment_version(&client, "configmonkey", "prod", "log_level", json!("warn")).await;
+
+    let events = h_audit_events(&client, "").await;
+
+    assert_eq!(events.data.len(), 3);
+    assert_eq!(events.data[0].actor, "alice");
+    assert_eq!(events.data[0].request_id, Some(String::from("req-1234")));
+    assert_eq!(events.data[1].actor, "anonymous");
+    assert_eq!(events.data[1].request_id, None);
+    assert_eq!(events.data[2].environment, Some(String::from("prod")));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn audit_skips_dry_runs_and_failures(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config_dry_run(&client, "configmonkey", "max_connections").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_version_dry_run(&client, "configmonkey", "log_level", json!("warn")).await;
+    h_delete_domain_dry_run(&client, "configmonkey").await;
+
+    let response = h_create_domain(&client, "configmonkey").await;
+    assert_eq!(response.status(), Status::Conflict);
+
+    let events = h_audit_events(&client, "").await;
+
+    let actions: Vec<&str> = events
+        .data
+        .iter()
+        .map(|event| event.action.as_str())
+        .collect();
+    assert_eq!(actions, vec!["domain_created", "config_created"]);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn audit_hashes_secrets_without_revealing_them(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets(
+        pg_connect_options,
+        json!({ "master_key": "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=" }),
+    )
+    .await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+    h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+
+    let events = h_audit_events(&client, "?key=database_password").await;
+
+    let after_hash = events.data[1].after_hash.clone().expect("hashed value");
+    assert_ne!(after_hash, h_hash("\"hunter2\""));
+    assert_ne!(after_hash, h_hash("\"********\""));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_audit_events_filters(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_domain_as(&client, "alice", "other").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_config_as(&client, "alice", "configmonkey", "max_connections").await;
+
+    let events = h_audit_events(&client, "?domain=configmonkey").await;
+    assert_eq!(events.data.len(), 3);
+
+    let events = h_audit_events(&client, "?domain=configmonkey&key=log_level").await;
+    assert_eq!(events.data.len(), 1);
+    assert_eq!(events.data[0].action, "config_created");
+
+    let events = h_audit_events(&client, "?actor=alice").await;
+    let domains: Vec<&str> = events
+        .data
+        .iter()
+        .map(|event| event.domain.as_str())
+        .collect();
+    assert_eq!(domains, vec!["other", "configmonkey"]);
+
+    let events = h_audit_events(&client, "?since=2000-01-01T00:00:00Z").await;
+    assert_eq!(events.data.len(), 4);
+
+    let events = h_audit_events(&client, "?since=2100-01-01T00:00:00%2B02:00").await;
+    assert_eq!(events.data.len(), 0);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_audit_events_pagination(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    for key in ["a", "b", "c", "d"] {
+        h_create_config(&client, "configmonkey", key).await;
+    }
+
+    let events = h_audit_events(
+        &client,
+        "?domain=configmonkey&since=2000-01-01T00:00:00Z&limit=2&offset=2",
+    )
+    .await;
+
+    assert_eq!(events.data.len(), 2);
+    assert_eq!(events.data[0].key, Some(String::from("b")));
+    h_validate_pagination(
+        events.pagination,
+        2,
+        2,
+        2,
+        Some(String::from(
+            "/v1/audit?limit=2&offset=4&domain=configmonkey&since=2000-01-01T00:00:00Z",
+        )),
+        Some(String::from(
+            "/v1/audit?limit=2&offset=0&domain=configmonkey&since=2000-01-01T00:00:00Z",
+        )),
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_audit_events_err_invalid_since(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_get_audit_events(&client, "?since=yesterday").await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_since");
+    assert_eq!(
+        error_dto.message,
+        "The since date must be an RFC 3339 timestamp"
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_audit_events_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_rbac(
+        pg_connect_options,
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+    h_create_domain_as(&client, "root", "configmonkey").await;
+    h_create_grant(&client, "root", "alice", "admin", Some("configmonkey")).await;
+    h_create_grant(&client, "root", "bob", "admin", None).await;
+
+    let response = h_get_audit_events_as(&client, "alice", "").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_get_audit_events_as(&client, "bob", "").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let events: PaginatedListDto<GetAuditEventDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(events.data.len(), 1);
+    assert_eq!(events.data[0].actor, "root");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_audit_events_tenant_isolation(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "acme").await;
+    h_create_tenant_domain(&client, "acme", "configmonkey", None).await;
+
+    let events = h_audit_events(&client, "").await;
+    assert_eq!(events.data.len(), 0);
+
+    let response = client
+        .get("/v1/audit")
+        .header(Header::new("X-Tenant", "acme"))
+        .dispatch()
+        .await;
+    let response_body = h_parse_response(response).await;
+    let events: PaginatedListDto<GetAuditEventDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(events.data.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn audit_events_are_append_only(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options.clone()).await;
+    h_create_domain(&client, "configmonkey").await;
+
+    let pool = pg_pool_options.connect_with(pg_connect_options).await?;
+
+    let update_result = sqlx::query("update audit_events set actor = 'mallory'")
+        .execute(&pool)
+        .await;
+    assert!(update_result.is_err());
+
+    let delete_result = sqlx::query("delete from audit_events").execute(&pool).await;
+    assert!(delete_result.is_err());
+
+    let events = h_audit_events(&client, "").await;
+    assert_eq!(events.data.len(), 1);
+    assert_eq!(events.data[0].actor, "anonymous");
+
+    Ok(())
+}
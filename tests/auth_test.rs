@@ -0,0 +1,279 @@
+use chrono::Utc;
+use configmonkey::routes::v1::{
+    domains_routes::GetDomainDto,
+    dtos::{ErrorDto, PaginatedListDto},
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rocket::{
+    http::{ContentType, Status},
+    serde::json::{json, Value},
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+const SECRET: &str = "configmonkey-secret";
+
+fn fixture(name: &str) -> String {
+    format!(
+        "{}/tests/fixtures/auth/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    )
+}
+
+fn sign_token(header: Header, key: &EncodingKey, claims: Value) -> String {
+    encode(&header, &claims, key).expect("valid token")
+}
+
+fn hs256_token(secret: &str, expires_in: i64) -> String {
+    sign_token(
+        Header::new(Algorithm::HS256),
+        &EncodingKey::from_secret(secret.as_bytes()),
+        json!({"sub": "sre", "exp": Utc::now().timestamp() + expires_in}),
+    )
+}
+
+async fn assert_unauthorized(response: rocket::local::asynchronous::LocalResponse<'_>, code: &str) {
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, code);
+}
+
+#[sqlx::test]
+async fn hs256_success(_: PgPoolOptions, pg_connect_options: PgConnectOptions) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": ["previous-secret", SECRET]}),
+    )
+    .await;
+    let token = hs256_token(SECRET, 3600);
+
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response =
+        h_get_domains_with_authorization(&client, Some(format!("Bearer {}", token).as_str())).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rs256_public_key_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "public_keys": [fixture("rs256_public.pem")]}),
+    )
+    .await;
+    let private_key = std::fs::read(fixture("rs256_private.pem")).unwrap();
+    let token = sign_token(
+        Header::new(Algorithm::RS256),
+        &EncodingKey::from_rsa_pem(private_key.as_slice()).unwrap(),
+        json!({"sub": "sre", "exp": Utc::now().timestamp() + 3600}),
+    );
+
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn es256_jwks_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "jwks": fixture("es256_jwks.json"), "issuer": "https://auth.example.com", "audience": "configmonkey"}),
+    )
+    .await;
+    let private_key = std::fs::read(fixture("es256_private.pem")).unwrap();
+    let mut header = Header::new(Algorithm::ES256);
+    header.kid = Some(String::from("es256-key"));
+    let token = sign_token(
+        header,
+        &EncodingKey::from_ec_pem(private_key.as_slice()).unwrap(),
+        json!({
+            "sub": "sre",
+            "iss": "https://auth.example.com",
+            "aud": "configmonkey",
+            "exp": Utc::now().timestamp() + 3600
+        }),
+    );
+
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn tenant_claim_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [SECRET]}),
+    )
+    .await;
+    let token = hs256_token(SECRET, 3600);
+    let acme_token = sign_token(
+        Header::new(Algorithm::HS256),
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+        json!({"sub": "sre", "tenant": "acme", "exp": Utc::now().timestamp() + 3600}),
+    );
+
+    h_create_tenant_with_token(&client, token.as_str(), "acme").await;
+    let response = h_create_domain_with_token(&client, acme_token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    // tokens act on the tenant of their claim, or on the default one
+    let response = h_get_domains_with_token(&client, acme_token.as_str(), Some("acme")).await;
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 1);
+    assert_eq!(get_domains_dto.data[0].slug, "billing");
+
+    let response = h_get_domains_with_token(&client, token.as_str(), None).await;
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 0);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn tenant_claim_err_tenant_mismatch(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [SECRET]}),
+    )
+    .await;
+    let token = hs256_token(SECRET, 3600);
+
+    h_create_tenant_with_token(&client, token.as_str(), "acme").await;
+    let response = h_get_domains_with_token(&client, token.as_str(), Some("acme")).await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "tenant_mismatch");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn authentication_err_missing_token(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [SECRET]}),
+    )
+    .await;
+
+    let response = h_get_domains_with_authorization(&client, None).await;
+    assert_unauthorized(response, "missing_token").await;
+
+    let response = h_get_domains_with_authorization(&client, Some("Basic c3JlOnNyZQ==")).await;
+    assert_unauthorized(response, "missing_token").await;
+
+    // every route requires authentication, including the ones outside of a tenant
+    let response = h_get_tenants(&client, None, None).await;
+    assert_unauthorized(response, "missing_token").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn authentication_err_invalid_token(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [SECRET]}),
+    )
+    .await;
+
+    let response = h_create_domain_with_token(&client, "not-a-token", "billing").await;
+    assert_unauthorized(response, "invalid_token").await;
+
+    let token = hs256_token("another-secret", 3600);
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+    assert_unauthorized(response, "invalid_token").await;
+
+    // tokens are only checked against keys of the same algorithm
+    let private_key = std::fs::read(fixture("rs256_private.pem")).unwrap();
+    let token = sign_token(
+        Header::new(Algorithm::RS256),
+        &EncodingKey::from_rsa_pem(private_key.as_slice()).unwrap(),
+        json!({"sub": "sre", "exp": Utc::now().timestamp() + 3600}),
+    );
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+    assert_unauthorized(response, "invalid_token").await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn authentication_err_claims(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_auth(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [SECRET], "audience": "configmonkey"}),
+    )
+    .await;
+
+    let token = sign_token(
+        Header::new(Algorithm::HS256),
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+        json!({"sub": "sre", "aud": "another-service", "exp": Utc::now().timestamp() + 3600}),
+    );
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+    assert_unauthorized(response, "invalid_token").await;
+
+    let token = sign_token(
+        Header::new(Algorithm::HS256),
+        &EncodingKey::from_secret(SECRET.as_bytes()),
+        json!({"sub": "sre", "aud": "configmonkey", "exp": Utc::now().timestamp() - 3600}),
+    );
+    let response = h_create_domain_with_token(&client, token.as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "expired_token");
+    assert_eq!(error_dto.message, "The bearer token has expired");
+
+    Ok(())
+}
@@ -1,8 +1,11 @@
 use configmonkey::routes::v1::{
     configs_routes::GetConfigDto,
-    dtos::{ErrorDto, PaginatedListDto},
+    dtos::{DeletionDto, ErrorDto, PaginatedListDto},
+};
+use rocket::{
+    http::{ContentType, Status},
+    serde::json::json,
 };
-use rocket::http::{ContentType, Status};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
 mod common;
@@ -62,11 +65,7 @@ async fn create_config_err_invalid_slug(
 
     h_create_domain(&client, "configmonkey").await;
 
-    let bad_slugs = vec![
-        "Database URL",
-        "%DATABASE_URL%",
-        "${DB_URL}",
-    ];
+    let bad_slugs = ["Database URL", "%DATABASE_URL%", "${DB_URL}"];
 
     for bad_slug in bad_slugs.iter() {
         let response = h_create_config(&client, "configmonkey", bad_slug).await;
@@ -103,6 +102,54 @@ async fn create_config_err_domain_not_found(
     Ok(())
 }
 
+#[sqlx::test]
+async fn create_config_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let response = h_create_config_dry_run(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_config_dto: GetConfigDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_config_dto.key, "database_url");
+
+    // assert not created
+    let response = h_get_config(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_config_err_exists_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+
+    let response = h_create_config_dry_run(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_already_exists");
+
+    Ok(())
+}
+
 #[sqlx::test]
 async fn get_configs_success(
     _: PgPoolOptions,
@@ -245,6 +292,42 @@ async fn delete_config_success(
     Ok(())
 }
 
+#[sqlx::test]
+async fn delete_config_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_version(&client, "configmonkey", "database_url", json!("localhost")).await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("db.internal"),
+    )
+    .await;
+
+    let response = h_delete_config_dry_run(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let deletion_dto: DeletionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(deletion_dto.configs, 1);
+    assert_eq!(deletion_dto.versions, 2);
+
+    // assert still exists
+    let response = h_get_config(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    Ok(())
+}
+
 #[sqlx::test]
 async fn delete_config_err_domain_not_found(
     _: PgPoolOptions,
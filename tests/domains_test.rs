@@ -1,8 +1,12 @@
 use configmonkey::routes::v1::{
-    domains_routes::GetDomainDto,
-    dtos::{ErrorDto, PaginatedListDto},
+    domains_routes::{GetDomainDto, ImportReportDto, PromotionReportDto},
+    dtos::{DeletionDto, ErrorDto, PaginatedListDto},
+};
+use rocket::{
+    futures::future::join_all,
+    http::{ContentType, Header, Status},
+    serde::json::{json, Value},
 };
-use rocket::http::{ContentType, Status};
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
 
 mod common;
@@ -27,6 +31,160 @@ async fn create_domain_success(
     Ok(())
 }
 
+#[sqlx::test]
+async fn create_domain_success_with_parent(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+
+    let response = h_create_child_domain(&client, "billing-staging", "billing-base").await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let domain_dto: GetDomainDto = h_parse_dto(response_body.as_str());
+    assert_eq!(domain_dto.slug, "billing-staging");
+    assert_eq!(domain_dto.parent, Some(String::from("billing-base")));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_domain_err_parent_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_child_domain(&client, "billing-staging", "billing-base").await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "parent_not_found");
+    assert_eq!(error_dto.message, "Parent domain not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_domain_err_parent_cycle(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_child_domain(&client, "billing", "billing").await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "parent_cycle");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_domain_parent_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_domain(&client, "billing-staging").await;
+
+    let response = h_set_domain_parent(&client, "billing-staging", Some("billing-base")).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let domain_dto: GetDomainDto = h_parse_dto(response_body.as_str());
+    assert_eq!(domain_dto.parent, Some(String::from("billing-base")));
+
+    let response = h_set_domain_parent(&client, "billing-staging", None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let domain_dto: GetDomainDto = h_parse_dto(response_body.as_str());
+    assert_eq!(domain_dto.parent, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_domain_parent_err_cycle(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_child_domain(&client, "billing-staging", "billing-base").await;
+    h_create_child_domain(&client, "billing-staging-eu", "billing-staging").await;
+
+    for parent in ["billing-staging-eu", "billing-base"] {
+        let response = h_set_domain_parent(&client, "billing-base", Some(parent)).await;
+
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "parent_cycle");
+        assert_eq!(
+            error_dto.message,
+            "A domain cannot inherit from itself or from any of the domains inheriting from it"
+        );
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_domain_parent_concurrent_cycle(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_domain(&client, "billing-staging").await;
+
+    // Each change alone is valid, but both together would close a cycle
+    for _ in 0..4 {
+        let responses = join_all([
+            h_set_domain_parent(&client, "billing-base", Some("billing-staging")),
+            h_set_domain_parent(&client, "billing-staging", Some("billing-base")),
+        ])
+        .await;
+
+        let statuses: Vec<Status> = responses.iter().map(|response| response.status()).collect();
+        assert_eq!(
+            statuses
+                .iter()
+                .filter(|status| **status == Status::Ok)
+                .count(),
+            1
+        );
+        assert!(statuses.contains(&Status::UnprocessableEntity));
+
+        h_set_domain_parent(&client, "billing-base", None).await;
+        h_set_domain_parent(&client, "billing-staging", None).await;
+    }
+
+    Ok(())
+}
+
 #[sqlx::test]
 async fn get_domains_success(
     _: PgPoolOptions,
@@ -105,14 +263,14 @@ async fn create_domain_err_invalid_slug(
 ) -> sqlx::Result<()> {
     let client = async_client_from_pg_connect_options(pg_connect_options).await;
 
-    let bad_slugs = vec![
+    let bad_slugs = [
         "Config Monkey",
         "!@#$%^&*(){}[]:;,configmonkey",
         "config/monkey",
     ];
 
     for bad_slug in bad_slugs.iter() {
-        let response = h_create_domain(&client, &bad_slug).await;
+        let response = h_create_domain(&client, bad_slug).await;
 
         assert_eq!(response.status(), Status::BadRequest);
         assert_eq!(response.content_type(), Some(ContentType::JSON));
@@ -195,3 +353,838 @@ async fn delete_domain_err_not_empty(
 
     Ok(())
 }
+
+#[sqlx::test]
+async fn delete_domain_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let response = h_delete_domain_dry_run(&client, "configmonkey").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let deletion_dto: DeletionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(deletion_dto.configs, 0);
+    assert_eq!(deletion_dto.versions, 0);
+
+    let response = h_get_domains(&client, None, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_domains_dto.data.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_domain_err_not_empty_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+
+    let response = h_delete_domain_dry_run(&client, "configmonkey").await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_empty");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_domain_err_has_children(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_child_domain(&client, "billing-staging", "billing-base").await;
+
+    let response = h_delete_domain(&client, "billing-base").await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "has_children");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn render_domain_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+    h_create_config(&client, "configmonkey", "unset").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1338"),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 3}),
+    )
+    .await;
+
+    let response = h_render_domain(&client, "configmonkey").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(
+        document,
+        json!({
+            "database_url": "postgres://localhost:1338",
+            "retry_policy": {"attempts": 3}
+        })
+    );
+
+    // Documents are negotiated like exports
+    let response = client
+        .get("/v1/domains/configmonkey/render")
+        .header(Header::new("Accept", "application/toml"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("application", "toml"))
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn render_domain_success_inherited(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_child_domain(&client, "billing-staging", "billing-base").await;
+    for (key, value) in [("timeout", json!(30)), ("region", json!("eu-west-1"))] {
+        h_create_config(&client, "billing-base", key).await;
+        h_create_version(&client, "billing-base", key, value).await;
+    }
+    h_create_config(&client, "billing-staging", "timeout").await;
+    h_create_version(&client, "billing-staging", "timeout", json!(5)).await;
+
+    let response = h_render_domain(&client, "billing-staging").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(document, json!({"timeout": 5, "region": "eu-west-1"}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn render_domain_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_render_domain(&client, "configmonkey").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_found");
+    assert_eq!(error_dto.message, "Domain not found");
+
+    Ok(())
+}
+
+/// Create a domain holding a mix of scalar and nested configs
+async fn h_create_export_fixture(client: &rocket::local::asynchronous::Client) {
+    h_create_domain(client, "configmonkey").await;
+    let configs = [
+        ("database_url", json!("postgres://user:p@ss$word@localhost")),
+        ("debug", json!(false)),
+        ("greeting", json!("Olá \"mundo\"\nbye")),
+        (
+            "retry-policy",
+            json!({"attempts": 3, "hosts": ["a", "b"], "fallback": null}),
+        ),
+    ];
+    for (key, value) in configs {
+        h_create_config(client, "configmonkey", key).await;
+        h_create_version(client, "configmonkey", key, value).await;
+    }
+}
+
+#[sqlx::test]
+async fn export_domain_success_json(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    for accept in [None, Some("*/*"), Some("application/json")] {
+        let response = h_export_domain(&client, "configmonkey", accept).await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let response_body = h_parse_response(response).await;
+        let document: Value = h_parse_dto(response_body.as_str());
+        assert_eq!(document["retry-policy"]["hosts"], json!(["a", "b"]));
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_success_dotenv(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    let response = h_export_domain(&client, "configmonkey", Some("text/x-dotenv")).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("text", "x-dotenv"))
+    );
+    assert_eq!(
+        h_parse_response(response).await,
+        concat!(
+            "DATABASE_URL=\"postgres://user:p@ss\\$word@localhost\"\n",
+            "DEBUG=false\n",
+            "GREETING=\"Olá \\\"mundo\\\"\\nbye\"\n",
+            "RETRY_POLICY_ATTEMPTS=3\n",
+            "RETRY_POLICY_FALLBACK=\n",
+            "RETRY_POLICY_HOSTS_0=\"a\"\n",
+            "RETRY_POLICY_HOSTS_1=\"b\"\n",
+        )
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_success_properties(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    let response = h_export_domain(&client, "configmonkey", Some("text/x-java-properties")).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("text", "x-java-properties"))
+    );
+    assert_eq!(
+        h_parse_response(response).await,
+        concat!(
+            "database_url=postgres://user:p@ss$word@localhost\n",
+            "debug=false\n",
+            "greeting=Ol\\u00E1 \"mundo\"\\nbye\n",
+            "retry-policy.attempts=3\n",
+            "retry-policy.fallback=\n",
+            "retry-policy.hosts[0]=a\n",
+            "retry-policy.hosts[1]=b\n",
+        )
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_success_toml(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    let response = h_export_domain(&client, "configmonkey", Some("application/toml")).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("application", "toml"))
+    );
+
+    let response_body = h_parse_response(response).await;
+    let document: toml::Value = toml::from_str(response_body.as_str()).expect("Valid TOML");
+    assert_eq!(document["debug"].as_bool(), Some(false));
+    assert_eq!(document["retry-policy"]["attempts"].as_integer(), Some(3));
+    assert_eq!(document["retry-policy"]["hosts"][1].as_str(), Some("b"));
+    assert!(document["retry-policy"].get("fallback").is_none());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_success_yaml(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    let response = h_export_domain(
+        &client,
+        "configmonkey",
+        Some("text/html;q=0.9, application/yaml"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.content_type(),
+        Some(ContentType::new("application", "yaml"))
+    );
+
+    let response_body = h_parse_response(response).await;
+    let document: Value = serde_yaml::from_str(response_body.as_str()).expect("Valid YAML");
+    assert_eq!(
+        document["retry-policy"],
+        json!({"attempts": 3, "hosts": ["a", "b"], "fallback": null})
+    );
+    assert_eq!(document["greeting"], json!("Olá \"mundo\"\nbye"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_err_not_acceptable(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_export_fixture(&client).await;
+
+    let response = h_export_domain(&client, "configmonkey", Some("text/html")).await;
+
+    assert_eq!(response.status(), Status::NotAcceptable);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_acceptable");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn export_domain_err_key_collision(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    for key in ["database-url", "database_url"] {
+        h_create_config(&client, "configmonkey", key).await;
+        h_create_version(&client, "configmonkey", key, json!("postgres://localhost")).await;
+    }
+
+    let response = h_export_domain(&client, "configmonkey", Some("text/x-dotenv")).await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "key_collision");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    h_create_config(&client, "configmonkey", "debug").await;
+    h_create_version(&client, "configmonkey", "timeout", json!(30)).await;
+    h_create_version(&client, "configmonkey", "debug", json!(false)).await;
+
+    let response = h_import_domain(
+        &client,
+        "configmonkey",
+        ContentType::JSON,
+        &json!({
+            "timeout": 90,
+            "debug": false,
+            "retry_policy": {"attempts": 3},
+            "Bad Key": "value"
+        })
+        .to_string(),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: ImportReportDto = h_parse_dto(response_body.as_str());
+    assert_eq!(report_dto.created, vec!["retry_policy"]);
+    assert_eq!(report_dto.updated, vec!["timeout"]);
+    assert_eq!(report_dto.unchanged, vec!["debug"]);
+    assert_eq!(report_dto.failed.len(), 1);
+    assert_eq!(report_dto.failed[0].key, "Bad Key");
+    assert_eq!(report_dto.failed[0].code, "invalid_slug");
+
+    let response = h_render_domain(&client, "configmonkey").await;
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(
+        document,
+        json!({"timeout": 90, "debug": false, "retry_policy": {"attempts": 3}})
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    h_create_version(&client, "configmonkey", "timeout", json!(30)).await;
+
+    let response = h_import_domain_dry_run(
+        &client,
+        "configmonkey",
+        ContentType::JSON,
+        &json!({"timeout": 90, "debug": true}).to_string(),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: ImportReportDto = h_parse_dto(response_body.as_str());
+    assert_eq!(report_dto.created, vec!["debug"]);
+    assert_eq!(report_dto.updated, vec!["timeout"]);
+
+    // assert nothing was imported
+    let response = h_render_domain(&client, "configmonkey").await;
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(document, json!({"timeout": 30}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_success_formats(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let documents = [
+        (
+            ContentType::new("text", "x-dotenv"),
+            concat!(
+                "# Database\n",
+                "export DATABASE_URL=\"postgres://localhost\\$1\" # primary\n",
+                "DEBUG=true\n",
+                "NAME='config monkey'\n",
+                "POOL=10 # connections\n",
+                "EMPTY=\n",
+            ),
+        ),
+        (
+            ContentType::new("application", "yaml"),
+            "DATABASE_URL: postgres://localhost$1\nDEBUG: true\nNAME: config monkey\nPOOL: 10\nEMPTY: null\n",
+        ),
+        (
+            ContentType::new("application", "toml"),
+            "DATABASE_URL = \"postgres://localhost$1\"\nDEBUG = true\nNAME = \"config monkey\"\nPOOL = 10\n",
+        ),
+    ];
+
+    for (index, (content_type, document)) in documents.into_iter().enumerate() {
+        let domain_slug = format!("configmonkey-{}", index);
+        h_create_domain(&client, domain_slug.as_str()).await;
+
+        let response = h_import_domain(&client, domain_slug.as_str(), content_type, document).await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = h_render_domain(&client, domain_slug.as_str()).await;
+        let response_body = h_parse_response(response).await;
+        let rendered: Value = h_parse_dto(response_body.as_str());
+        assert_eq!(rendered["DATABASE_URL"], json!("postgres://localhost$1"));
+        assert_eq!(rendered["DEBUG"], json!(true));
+        assert_eq!(rendered["NAME"], json!("config monkey"));
+        assert_eq!(rendered["POOL"], json!(10));
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_success_export_round_trip(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    let configs = [
+        ("DATABASE_URL", json!("postgres://user:p@ss$word@localhost")),
+        ("DEBUG", json!(false)),
+        ("GREETING", json!("Hello \"world\"\nbye")),
+        ("RATIO", json!(0.5)),
+    ];
+    for (key, value) in configs {
+        h_create_config(&client, "configmonkey", key).await;
+        h_create_version(&client, "configmonkey", key, value).await;
+    }
+
+    let response = h_export_domain(&client, "configmonkey", Some("text/x-dotenv")).await;
+    let exported = h_parse_response(response).await;
+
+    let response = h_import_domain(
+        &client,
+        "configmonkey",
+        ContentType::new("text", "x-dotenv"),
+        exported.as_str(),
+    )
+    .await;
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: ImportReportDto = h_parse_dto(response_body.as_str());
+    assert!(report_dto.created.is_empty());
+    assert!(report_dto.updated.is_empty());
+    assert_eq!(report_dto.unchanged.len(), 4);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_err_invalid_document(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let documents = [
+        (ContentType::JSON, "[1, 2, 3]"),
+        (ContentType::new("text", "x-dotenv"), "NOT A VARIABLE"),
+        (ContentType::new("application", "toml"), "key = "),
+    ];
+    for (content_type, document) in documents {
+        let response = h_import_domain(&client, "configmonkey", content_type, document).await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "invalid_document");
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_err_unsupported_format(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    for content_type in [
+        ContentType::HTML,
+        ContentType::new("text", "x-java-properties"),
+    ] {
+        let response = h_import_domain(&client, "configmonkey", content_type, "a=b").await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "unsupported_format");
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn import_domain_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_import_domain(&client, "configmonkey", ContentType::JSON, r#"{"a": 1}"#).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "staging").await;
+    h_create_domain(&client, "production").await;
+    for (key, staging_value, production_value) in [
+        ("timeout", json!(60), Some(json!(30))),
+        ("debug", json!(false), Some(json!(false))),
+        ("retries", json!(3), None),
+    ] {
+        h_create_config(&client, "staging", key).await;
+        h_create_version(&client, "staging", key, staging_value).await;
+        if let Some(production_value) = production_value {
+            h_create_config(&client, "production", key).await;
+            h_create_version(&client, "production", key, production_value).await;
+        }
+    }
+
+    let response = h_promote_domain(&client, "staging", "production", None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: PromotionReportDto = h_parse_dto(response_body.as_str());
+    assert_eq!(report_dto.from, "staging");
+    assert_eq!(report_dto.to, "production");
+    assert_eq!(report_dto.configs.len(), 3);
+
+    assert_eq!(report_dto.configs[0].key, "debug");
+    assert_eq!(report_dto.configs[0].status, "unchanged");
+    assert_eq!(report_dto.configs[0].version, Some(1));
+    assert!(report_dto.configs[0].diff.is_none());
+
+    assert_eq!(report_dto.configs[1].key, "retries");
+    assert_eq!(report_dto.configs[1].status, "created");
+    assert_eq!(report_dto.configs[1].version, Some(1));
+    assert!(report_dto.configs[1].diff.is_none());
+
+    assert_eq!(report_dto.configs[2].key, "timeout");
+    assert_eq!(report_dto.configs[2].status, "updated");
+    assert_eq!(report_dto.configs[2].version, Some(2));
+    let diff = report_dto.configs[2].diff.as_ref().unwrap();
+    let value_change = diff.value_change.as_ref().unwrap();
+    assert_eq!(value_change.from, json!(30));
+    assert_eq!(value_change.to, json!(60));
+
+    let response = h_render_domain(&client, "production").await;
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(
+        document,
+        json!({"timeout": 60, "debug": false, "retries": 3})
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_success_keys(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "staging").await;
+    h_create_domain(&client, "production").await;
+    for key in ["timeout", "debug"] {
+        h_create_config(&client, "staging", key).await;
+        h_create_version(&client, "staging", key, json!(1)).await;
+    }
+
+    let response = h_promote_domain(&client, "staging", "production", Some(vec!["timeout"])).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: PromotionReportDto = h_parse_dto(response_body.as_str());
+    assert_eq!(report_dto.configs.len(), 1);
+    assert_eq!(report_dto.configs[0].key, "timeout");
+    assert_eq!(report_dto.configs[0].status, "created");
+
+    let response = h_render_domain(&client, "production").await;
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(document, json!({"timeout": 1}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "staging").await;
+    h_create_domain(&client, "production").await;
+    h_create_config(&client, "staging", "timeout").await;
+    h_create_version(&client, "staging", "timeout", json!(60)).await;
+
+    let response = h_promote_domain_dry_run(&client, "staging", "production", None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let report_dto: PromotionReportDto = h_parse_dto(response_body.as_str());
+    assert_eq!(report_dto.configs.len(), 1);
+    assert_eq!(report_dto.configs[0].status, "created");
+
+    // nothing was written to the target domain
+    let response = h_get_config(&client, "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_err_key_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "staging").await;
+    h_create_domain(&client, "production").await;
+    h_create_config(&client, "staging", "timeout").await;
+    h_create_version(&client, "staging", "timeout", json!(60)).await;
+
+    let response = h_promote_domain(
+        &client,
+        "staging",
+        "production",
+        Some(vec!["timeout", "debug"]),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "key_not_found");
+    assert_eq!(
+        error_dto.message,
+        "One or more keys do not exist in the source domain"
+    );
+
+    // the promotion is all or nothing
+    let response = h_get_config(&client, "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_err_target_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "staging").await;
+
+    let response = h_promote_domain(&client, "staging", "production", None).await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "target_not_found");
+    assert_eq!(error_dto.message, "Target domain not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "production").await;
+
+    let response = h_promote_domain(&client, "staging", "production", None).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_found");
+
+    Ok(())
+}
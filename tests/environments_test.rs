@@ -0,0 +1,335 @@
+use configmonkey::routes::v1::{
+    dtos::{ErrorDto, PaginatedListDto},
+    environments_routes::GetEnvironmentDto,
+    versions_routes::{GetValueDto, GetVersionDto},
+};
+use rocket::{
+    http::{ContentType, Status},
+    serde::json::{json, Value},
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+#[sqlx::test]
+async fn create_environment_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+
+    let response = h_create_environment(&client, "billing", "production").await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_environment_dto: GetEnvironmentDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_environment_dto.slug, "production");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_environment_err_exists(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_environment(&client, "billing", "production").await;
+
+    let response = h_create_environment(&client, "billing", "production").await;
+
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "environment_already_exists");
+    assert_eq!(error_dto.message, "Environment already exists");
+
+    // the same slug can be used in another domain
+    h_create_domain(&client, "shipping").await;
+
+    let response = h_create_environment(&client, "shipping", "production").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_environment_err_invalid_slug(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+
+    let response = h_create_environment(&client, "billing", "prod env").await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_slug");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_environment_err_domain_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_environment(&client, "billing", "production").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_environments_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    for environment in ["development", "production", "staging"] {
+        h_create_environment(&client, "billing", environment).await;
+    }
+
+    let response = h_get_environments(&client, "billing", Some(2), None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_environments_dto: PaginatedListDto<GetEnvironmentDto> =
+        h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_environments_dto.data.len(), 2);
+    assert_eq!(get_environments_dto.data[0].slug, "development");
+    assert_eq!(get_environments_dto.data[1].slug, "production");
+    h_validate_pagination(
+        get_environments_dto.pagination,
+        2,
+        2,
+        0,
+        Some(String::from(
+            "/v1/domains/billing/environments?limit=2&offset=2",
+        )),
+        None,
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_environment_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_environment(&client, "billing", "production").await;
+    h_create_config(&client, "billing", "timeout").await;
+    h_create_environment_version(&client, "billing", "production", "timeout", json!(30)).await;
+
+    let response = h_delete_environment(&client, "billing", "production").await;
+
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response = h_get_environment_value(&client, "billing", "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    // the versions of the environment are deleted along with it
+    h_create_environment(&client, "billing", "production").await;
+
+    let response = h_get_environment_value(&client, "billing", "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "no_versions");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_environment_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+
+    let response = h_delete_environment(&client, "billing", "production").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "environment_not_found");
+    assert_eq!(error_dto.message, "Environment not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn environment_versions_success_separate_history(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_environment(&client, "billing", "production").await;
+    h_create_environment(&client, "billing", "staging").await;
+    h_create_config(&client, "billing", "timeout").await;
+
+    h_create_version(&client, "billing", "timeout", json!(10)).await;
+    h_create_environment_version(&client, "billing", "production", "timeout", json!(30)).await;
+    let response =
+        h_create_environment_version(&client, "billing", "production", "timeout", json!(60)).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 2);
+
+    h_create_environment_version(&client, "billing", "staging", "timeout", json!(5)).await;
+
+    // every environment has its own history
+    for (environment, versions) in [("production", vec![60, 30]), ("staging", vec![5])] {
+        let response = h_get_environment_versions(&client, "billing", environment, "timeout").await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response_body = h_parse_response(response).await;
+        let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+        let values: Vec<Value> = get_versions_dto
+            .data
+            .into_iter()
+            .map(|version| version.value)
+            .collect();
+        assert_eq!(
+            values,
+            versions.into_iter().map(Value::from).collect::<Vec<_>>()
+        );
+    }
+
+    let response = h_get_environment_value(&client, "billing", "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!(60));
+
+    // the domain keeps its own history, which is also the one rendered
+    let response = h_get_value(&client, "billing", "timeout").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(get_value_dto.value, json!(10));
+
+    let response = h_render_domain(&client, "billing").await;
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(document, json!({"timeout": 10}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rollback_environment_version_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_environment(&client, "billing", "production").await;
+    h_create_config(&client, "billing", "timeout").await;
+    h_create_version(&client, "billing", "timeout", json!(10)).await;
+    h_create_environment_version(&client, "billing", "production", "timeout", json!(30)).await;
+    h_create_environment_version(&client, "billing", "production", "timeout", json!(60)).await;
+
+    let response =
+        h_rollback_environment_version(&client, "billing", "production", "timeout", 1).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 3);
+    assert_eq!(get_version_dto.value, json!(30));
+    assert_eq!(get_version_dto.restored_from, Some(1));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_environment_value_err_environment_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_config(&client, "billing", "timeout").await;
+
+    let response = h_get_environment_value(&client, "billing", "production", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "environment_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_domain_success_with_environments(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing").await;
+    h_create_environment(&client, "billing", "production").await;
+
+    let response = h_delete_domain(&client, "billing").await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    Ok(())
+}
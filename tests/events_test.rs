@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use configmonkey::routes::v1::{dtos::ErrorDto, events_routes::DomainEventDto};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::LocalResponse,
+    serde::json::json,
+    tokio::{io::AsyncReadExt, time::timeout},
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+struct StreamedEvent {
+    id: String,
+    event: String,
+    data: DomainEventDto,
+}
+
+fn h_parse_event(block: &str) -> Option<StreamedEvent> {
+    let (mut id, mut event, mut data) = (None, None, None);
+    for line in block.lines() {
+        match line.split_once(':') {
+            Some(("id", value)) => id = Some(value.trim_start().to_string()),
+            Some(("event", value)) => event = Some(value.trim_start().to_string()),
+            Some(("data", value)) => data = Some(h_parse_dto(value.trim_start())),
+            // Heartbeats are comments
+            _ => continue,
+        }
+    }
+    Some(StreamedEvent {
+        id: id?,
+        event: event?,
+        data: data?,
+    })
+}
+
+/// Read events off a stream until the given number of them is received
+async fn h_read_events(response: &mut LocalResponse<'_>, count: usize) -> Vec<StreamedEvent> {
+    let mut buffer = String::new();
+    let mut events = vec![];
+    let mut chunk = [0u8; 1024];
+    while events.len() < count {
+        let read = timeout(Duration::from_secs(5), response.read(&mut chunk))
+            .await
+            .expect("an event within 5 seconds")
+            .expect("a readable stream");
+        assert!(read > 0, "the stream ended");
+        buffer.push_str(std::str::from_utf8(&chunk[..read]).unwrap());
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            events.extend(h_parse_event(block.as_str()));
+        }
+    }
+    events
+}
+
+#[sqlx::test]
+async fn get_domain_events_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+
+    let mut response = h_get_domain_events(&client, "configmonkey", None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::EventStream));
+
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    // Changes made before the stream was opened are not sent
+    let mut events = h_read_events(&mut response, 2).await;
+
+    h_delete_config(&client, "configmonkey", "max_connections").await;
+
+    events.extend(h_read_events(&mut response, 1).await);
+
+    let kinds: Vec<&str> = events.iter().map(|event| event.event.as_str()).collect();
+    assert_eq!(
+        kinds,
+        vec!["config_created", "version_created", "config_deleted"]
+    );
+    assert!(events
+        .iter()
+        .all(|event| event.data.key == "max_connections" && event.data.kind == event.event));
+    assert_eq!(events[0].data.version, None);
+    assert_eq!(events[0].data.value, None);
+    assert_eq!(events[1].data.version, Some(1));
+    assert_eq!(events[1].data.value, Some(json!(10)));
+    assert_eq!(events[1].data.environment, None);
+
+    let ids: Vec<i64> = events
+        .iter()
+        .map(|event| event.id.parse().unwrap())
+        .collect();
+    assert!(ids.windows(2).all(|ids| ids[0] < ids[1]));
+
+    // The values of deleted configs are no longer sent when replaying
+    let mut response = h_get_domain_events(&client, "configmonkey", Some("0")).await;
+
+    let events = h_read_events(&mut response, 4).await;
+    assert_eq!(events[1].data.key, "max_connections");
+    assert_eq!(events[2].data.version, Some(1));
+    assert_eq!(events[2].data.value, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_resumes_after_last_event_id(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+
+    let mut response = h_get_domain_events(&client, "configmonkey", None).await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    let events = h_read_events(&mut response, 1).await;
+    drop(response);
+
+    // Changes made while disconnected
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+    h_create_version(&client, "configmonkey", "log_level", json!("error")).await;
+
+    let mut response =
+        h_get_domain_events(&client, "configmonkey", Some(events[0].id.as_str())).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let missed = h_read_events(&mut response, 2).await;
+    assert_eq!(missed[0].data.version, Some(1));
+    assert_eq!(missed[0].data.value, Some(json!("warn")));
+    assert_eq!(missed[1].data.version, Some(2));
+    assert_eq!(missed[1].data.value, Some(json!("error")));
+
+    // Followed by the changes made from then on
+    h_create_version(&client, "configmonkey", "log_level", json!("info")).await;
+
+    let events = h_read_events(&mut response, 1).await;
+    assert_eq!(events[0].data.version, Some(3));
+
+    // Replaying from the start
+    let mut response = h_get_domain_events(&client, "configmonkey", Some("0")).await;
+
+    let events = h_read_events(&mut response, 4).await;
+    assert_eq!(events[0].event, "config_created");
+    assert_eq!(events[3].data.value, Some(json!("info")));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_ignores_other_domains(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_domain(&client, "other").await;
+
+    let mut response = h_get_domain_events(&client, "configmonkey", None).await;
+    h_create_config(&client, "other", "log_level").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+
+    let events = h_read_events(&mut response, 1).await;
+    assert_eq!(events[0].data.key, "max_connections");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_environments_and_secrets(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets(
+        pg_connect_options,
+        json!({ "master_key": "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=" }),
+    )
+    .await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_environment(&client, "configmonkey", "prod").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+
+    let mut response = h_get_domain_events(&client, "configmonkey", None).await;
+    h_create_environment_version(&client, "configmonkey", "prod", "log_level", json!("warn")).await;
+    h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+
+    let events = h_read_events(&mut response, 2).await;
+    assert_eq!(events[0].data.environment, Some(String::from("prod")));
+    assert_eq!(events[0].data.value, Some(json!("warn")));
+    assert_eq!(events[1].data.value, Some(json!("********")));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_err_invalid_last_event_id(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+
+    let response = h_get_domain_events(&client, "configmonkey", Some("latest")).await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_last_event_id");
+    assert_eq!(
+        error_dto.message,
+        "The last event id must be the id of an event received before"
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_err_domain_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_get_domain_events(&client, "configmonkey", None).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_domain_events_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_rbac(
+        pg_connect_options,
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+    h_create_domain_as(&client, "root", "configmonkey").await;
+    h_create_domain_as(&client, "root", "other").await;
+    h_create_grant(&client, "root", "alice", "reader", Some("other")).await;
+
+    let response = h_get_domain_events_as(&client, "alice", "configmonkey").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_get_domain_events_as(&client, "alice", "other").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    Ok(())
+}
@@ -0,0 +1,421 @@
+use chrono::Utc;
+use configmonkey::routes::v1::{
+    dtos::{ErrorDto, PaginatedListDto},
+    grants_routes::GetGrantDto,
+    versions_routes::GetValueDto,
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::{Client, LocalResponse},
+    serde::json::json,
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+const ADMIN: &str = "root";
+
+async fn async_client_with_roles(pg_connect_options: PgConnectOptions) -> Client {
+    async_client_with_rbac(
+        pg_connect_options,
+        json!({"enabled": true, "administrators": [ADMIN]}),
+    )
+    .await
+}
+
+async fn assert_forbidden(response: LocalResponse<'_>) {
+    assert_eq!(response.status(), Status::Forbidden);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+    assert_eq!(
+        error_dto.message,
+        "The principal is not allowed to perform this operation"
+    );
+}
+
+#[sqlx::test]
+async fn create_grant_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+
+    let response = h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_grant_dto: GetGrantDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_grant_dto.principal, "sre");
+    assert_eq!(get_grant_dto.role, "writer");
+    assert_eq!(get_grant_dto.domain, None);
+
+    let response = h_create_grant(&client, ADMIN, "billing-team", "reader", Some("billing")).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let get_grant_dto: GetGrantDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_grant_dto.principal, "billing-team");
+    assert_eq!(get_grant_dto.role, "reader");
+    assert_eq!(get_grant_dto.domain, Some(String::from("billing")));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_grant_err_exists(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+
+    let response = h_create_grant(&client, ADMIN, "sre", "admin", None).await;
+
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "grant_already_exists");
+
+    // a principal can hold another role over a domain
+    h_create_domain_as(&client, ADMIN, "billing").await;
+
+    let response = h_create_grant(&client, ADMIN, "sre", "admin", Some("billing")).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_grant_err_invalid_role(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    let response = h_create_grant(&client, ADMIN, "sre", "owner", None).await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_role");
+
+    let response = h_create_grant(&client, ADMIN, " ", "reader", None).await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_principal");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_grant_err_domain_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    let response = h_create_grant(&client, ADMIN, "sre", "reader", Some("billing")).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_grant_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_domain_as(&client, ADMIN, "shipping").await;
+    h_create_grant(&client, ADMIN, "billing-lead", "admin", Some("billing")).await;
+    h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+
+    // writers cannot grant roles
+    let response = h_create_grant(&client, "sre", "intern", "reader", Some("billing")).await;
+
+    assert_forbidden(response).await;
+
+    // administrators of a domain can only grant roles over it
+    let response = h_create_grant(
+        &client,
+        "billing-lead",
+        "billing-team",
+        "writer",
+        Some("billing"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_create_grant(
+        &client,
+        "billing-lead",
+        "billing-team",
+        "writer",
+        Some("shipping"),
+    )
+    .await;
+
+    assert_forbidden(response).await;
+
+    let response = h_create_grant(&client, "billing-lead", "billing-team", "writer", None).await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_grants_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+    h_create_grant(&client, ADMIN, "billing-lead", "admin", Some("billing")).await;
+    h_create_grant(&client, ADMIN, "billing-team", "reader", Some("billing")).await;
+
+    let response = h_get_grants(&client, "billing-lead", Some("billing")).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_grants_dto: PaginatedListDto<GetGrantDto> = h_parse_dto(response_body.as_str());
+    let grants: Vec<(String, String)> = get_grants_dto
+        .data
+        .into_iter()
+        .map(|grant| (grant.principal, grant.role))
+        .collect();
+    assert_eq!(
+        grants,
+        vec![
+            (String::from("billing-lead"), String::from("admin")),
+            (String::from("billing-team"), String::from("reader")),
+        ]
+    );
+
+    let response = h_get_grants(&client, ADMIN, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_grants_dto: PaginatedListDto<GetGrantDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_grants_dto.data.len(), 1);
+    assert_eq!(get_grants_dto.data[0].principal, "sre");
+
+    // only administrators can see who holds roles
+    let response = h_get_grants(&client, "billing-team", Some("billing")).await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_grant_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_config_as(&client, ADMIN, "billing", "timeout").await;
+    h_create_version_as(&client, ADMIN, "billing", "timeout", json!(30)).await;
+    h_create_grant(&client, ADMIN, "billing-team", "reader", Some("billing")).await;
+
+    let response = h_get_value_as(&client, "billing-team", "billing", "timeout").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = h_delete_grant(&client, ADMIN, "billing-team", Some("billing")).await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = h_get_value_as(&client, "billing-team", "billing", "timeout").await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_grant_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_grant(&client, ADMIN, "billing-team", "reader", Some("billing")).await;
+
+    // grants over a domain are not revoked over the tenant
+    let response = h_delete_grant(&client, ADMIN, "billing-team", None).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "grant_not_found");
+    assert_eq!(error_dto.message, "Grant not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn roles_success_writers_and_readers(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_domain_as(&client, ADMIN, "shipping").await;
+    h_create_config_as(&client, ADMIN, "billing", "timeout").await;
+    h_create_config_as(&client, ADMIN, "shipping", "timeout").await;
+    h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+    h_create_grant(&client, ADMIN, "billing-team", "reader", Some("billing")).await;
+
+    // writers over the tenant can write to every domain
+    for domain in ["billing", "shipping"] {
+        let response = h_create_version_as(&client, "sre", domain, "timeout", json!(30)).await;
+
+        assert_eq!(response.status(), Status::Created);
+    }
+
+    // readers can read the domain they were granted a role over, but not write to it
+    let response = h_get_value_as(&client, "billing-team", "billing", "timeout").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!(30));
+
+    let response =
+        h_create_version_as(&client, "billing-team", "billing", "timeout", json!(60)).await;
+
+    assert_forbidden(response).await;
+
+    let response = h_create_config_as(&client, "billing-team", "billing", "retries").await;
+
+    assert_forbidden(response).await;
+
+    // nor read other domains
+    let response = h_get_value_as(&client, "billing-team", "shipping", "timeout").await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn roles_err_forbidden_domain_admin(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    h_create_domain_as(&client, ADMIN, "billing").await;
+    h_create_grant(&client, ADMIN, "sre", "writer", None).await;
+    h_create_grant(&client, ADMIN, "billing-lead", "admin", Some("billing")).await;
+
+    // deleting a domain requires administering it
+    let response = h_delete_domain_as(&client, "sre", "billing").await;
+
+    assert_forbidden(response).await;
+
+    // creating domains requires administering the tenant
+    let response = h_create_domain_as(&client, "billing-lead", "invoices").await;
+
+    assert_forbidden(response).await;
+
+    let response = h_delete_domain_as(&client, "billing-lead", "billing").await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn roles_err_forbidden_anonymous(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_roles(pg_connect_options).await;
+
+    let response = h_create_domain(&client, "billing").await;
+
+    assert_forbidden(response).await;
+
+    let response = h_get_domains(&client, None, None).await;
+
+    assert_forbidden(response).await;
+
+    let response = h_create_tenant(&client, "acme").await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn roles_success_token_subject(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let secret = "configmonkey-secret";
+    let client = async_client_with_auth_and_rbac(
+        pg_connect_options,
+        json!({"enabled": true, "secrets": [secret]}),
+        json!({"enabled": true, "administrators": [ADMIN]}),
+    )
+    .await;
+    let token = |sub: &str| {
+        encode(
+            &Header::new(Algorithm::HS256),
+            &json!({"sub": sub, "exp": Utc::now().timestamp() + 3600}),
+            &EncodingKey::from_secret(secret.as_bytes()),
+        )
+        .expect("valid token")
+    };
+
+    let response = h_create_domain_with_token(&client, token(ADMIN).as_str(), "billing").await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    // requests act as the subject of their token
+    let response = h_create_domain_with_token(&client, token("sre").as_str(), "shipping").await;
+
+    assert_forbidden(response).await;
+
+    Ok(())
+}
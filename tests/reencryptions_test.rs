@@ -0,0 +1,382 @@
+use std::time::Duration;
+
+use configmonkey::routes::v1::{
+    dtos::ErrorDto, reencryptions_routes::GetReencryptionDto, versions_routes::GetValueDto,
+};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::Client,
+    serde::json::{json, Value},
+};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    types::Uuid,
+};
+
+mod common;
+
+pub use common::helpers::*;
+
+const OLD_KEY: &str = "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=";
+const NEW_KEY: &str = "YWJjZGVmZ2hpamtsbW5vcHFyc3R1dnd4eXowMTIzNDU=";
+
+fn rotated_keys() -> Value {
+    json!({
+        "master_keys": {"default": OLD_KEY, "2026": NEW_KEY},
+        "active_key": "2026"
+    })
+}
+
+async fn h_create_secrets(client: &Client, keys: &[&str]) {
+    h_create_domain(client, "configmonkey").await;
+    for key in keys {
+        h_create_config(client, "configmonkey", key).await;
+        let response = h_create_secret_version(client, "configmonkey", key, json!(key)).await;
+        assert_eq!(response.status(), Status::Created);
+    }
+}
+
+/// Poll a re-encryption until it is no longer running
+async fn h_wait_for_reencryption(client: &Client, id: &str) -> GetReencryptionDto {
+    for _ in 0..100 {
+        let response = h_get_reencryption(client, id).await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let response_body = h_parse_response(response).await;
+        let reencryption: GetReencryptionDto = h_parse_dto(response_body.as_str());
+        if reencryption.status != "running" {
+            return reencryption;
+        }
+        rocket::tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("re-encryption {} is still running", id);
+}
+
+#[sqlx::test]
+async fn start_reencryption_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client =
+        async_client_with_secrets(pg_connect_options.clone(), json!({ "master_key": OLD_KEY }))
+            .await;
+    h_create_secrets(&client, &["database_password", "api_token"]).await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    let client = async_client_with_secrets(pg_connect_options.clone(), rotated_keys()).await;
+
+    let response = h_start_reencryption(&client).await;
+
+    assert_eq!(response.status(), Status::Accepted);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let reencryption: GetReencryptionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(reencryption.key_id, "2026");
+    assert_eq!(reencryption.total, 3);
+
+    let reencryption = h_wait_for_reencryption(&client, reencryption.id.as_str()).await;
+    assert_eq!(reencryption.status, "completed");
+    assert_eq!(reencryption.processed, 3);
+    assert!(reencryption.finished_at.is_some());
+
+    // The retired key is no longer needed to reveal the values
+    let client = async_client_with_secrets(
+        pg_connect_options,
+        json!({"master_keys": {"2026": NEW_KEY}}),
+    )
+    .await;
+
+    let response = h_get_secret_value(&client, "configmonkey", "database_password").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!("database_password"));
+
+    let response = h_get_value(&client, "configmonkey", "max_connections").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!(10));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn start_reencryption_success_values_in_the_clear(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    // Values created before any master key was configured
+    let client = async_client_from_pg_connect_options(pg_connect_options.clone()).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    let client =
+        async_client_with_secrets(pg_connect_options.clone(), json!({ "master_key": OLD_KEY }))
+            .await;
+
+    let response = h_start_reencryption(&client).await;
+    let response_body = h_parse_response(response).await;
+    let reencryption: GetReencryptionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(reencryption.total, 1);
+
+    let reencryption = h_wait_for_reencryption(&client, reencryption.id.as_str()).await;
+    assert_eq!(reencryption.status, "completed");
+
+    let pool = pg_pool_options.connect_with(pg_connect_options).await?;
+    let (value, key_id): (Value, Option<String>) =
+        sqlx::query_as("select value, key_id from versions")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(key_id.as_deref(), Some("default"));
+    assert_ne!(value, json!(10));
+
+    let response = h_get_value(&client, "configmonkey", "max_connections").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!(10));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn start_reencryption_success_locked_values(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client =
+        async_client_with_secrets(pg_connect_options.clone(), json!({ "master_key": OLD_KEY }))
+            .await;
+    h_create_secrets(&client, &["database_password"]).await;
+
+    // A concurrent batch holding the only value left
+    let pool = pg_pool_options
+        .connect_with(pg_connect_options.clone())
+        .await?;
+    let mut tx = pool.begin().await?;
+    sqlx::query("select id from versions for update")
+        .execute(&mut tx)
+        .await?;
+
+    let client = async_client_with_secrets(pg_connect_options, rotated_keys()).await;
+    let response = h_start_reencryption(&client).await;
+    let response_body = h_parse_response(response).await;
+    let reencryption: GetReencryptionDto = h_parse_dto(response_body.as_str());
+
+    rocket::tokio::time::sleep(Duration::from_millis(500)).await;
+    let response = h_get_reencryption(&client, reencryption.id.as_str()).await;
+    let response_body = h_parse_response(response).await;
+    let running: GetReencryptionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(running.status, "running");
+    assert_eq!(running.processed, 0);
+
+    tx.rollback().await?;
+
+    let reencryption = h_wait_for_reencryption(&client, reencryption.id.as_str()).await;
+    assert_eq!(reencryption.status, "completed");
+    assert_eq!(reencryption.processed, 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_secret_value_err_retired_key(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client =
+        async_client_with_secrets(pg_connect_options.clone(), json!({ "master_key": OLD_KEY }))
+            .await;
+    h_create_secrets(&client, &["database_password"]).await;
+
+    let client = async_client_with_secrets(
+        pg_connect_options,
+        json!({"master_keys": {"2026": NEW_KEY}}),
+    )
+    .await;
+
+    let response = h_get_secret_value(&client, "configmonkey", "database_password").await;
+
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "undecryptable_secret");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn start_reencryption_err_in_progress(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets(pg_connect_options.clone(), rotated_keys()).await;
+
+    let pool = pg_pool_options.connect_with(pg_connect_options).await?;
+    sqlx::query("insert into reencryptions(key_id, total) values('2026', 1)")
+        .execute(&pool)
+        .await?;
+
+    let response = h_start_reencryption(&client).await;
+
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "reencryption_in_progress");
+    assert_eq!(error_dto.message, "Another re-encryption is still running");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn reencryption_resumes_on_launch(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client =
+        async_client_with_secrets(pg_connect_options.clone(), json!({ "master_key": OLD_KEY }))
+            .await;
+    h_create_secrets(&client, &["database_password", "api_token"]).await;
+
+    // A job interrupted after its first batch
+    let pool = pg_pool_options
+        .connect_with(pg_connect_options.clone())
+        .await?;
+    let id: Uuid = sqlx::query_scalar(
+        "insert into reencryptions(key_id, total, processed) values('2026', 3, 1) returning id",
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let client = async_client_with_secrets(pg_connect_options, rotated_keys()).await;
+
+    let reencryption = h_wait_for_reencryption(&client, id.to_string().as_str()).await;
+    assert_eq!(reencryption.status, "completed");
+    assert_eq!(reencryption.processed, 3);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn reencryption_fails_on_launch_with_another_key(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    // Applies the migrations
+    async_client_with_secrets(pg_connect_options.clone(), rotated_keys()).await;
+
+    let pool = pg_pool_options
+        .connect_with(pg_connect_options.clone())
+        .await?;
+    let id: Uuid = sqlx::query_scalar(
+        "insert into reencryptions(key_id, total) values('2025', 1) returning id",
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    let client = async_client_with_secrets(pg_connect_options, rotated_keys()).await;
+
+    let reencryption = h_wait_for_reencryption(&client, id.to_string().as_str()).await;
+    assert_eq!(reencryption.status, "failed");
+    assert_eq!(reencryption.processed, 0);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn start_reencryption_err_secrets_disabled(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_start_reencryption(&client).await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "secrets_disabled");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn start_reencryption_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets_and_rbac(
+        pg_connect_options,
+        rotated_keys(),
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+    h_create_grant(&client, "root", "sre", "admin", None).await;
+
+    let response = h_start_reencryption_as(&client, "sre").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_start_reencryption_as(&client, "root").await;
+
+    assert_eq!(response.status(), Status::Accepted);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_reencryption_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets(pg_connect_options, rotated_keys()).await;
+
+    for id in ["b5f1a1c2-3c53-4a43-9c3e-2b1e3a0c9d41", "not-a-uuid"] {
+        let response = h_get_reencryption(&client, id).await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "reencryption_not_found");
+        assert_eq!(error_dto.message, "Re-encryption not found");
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn secrets_err_unknown_active_key(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let result = try_async_client_with_secrets(
+        pg_connect_options,
+        json!({"master_keys": {"2026": NEW_KEY}, "active_key": "2027"}),
+    )
+    .await;
+
+    match result {
+        Err(err) => assert!(matches!(
+            err.kind(),
+            rocket::error::ErrorKind::FailedFairings(_)
+        )),
+        Ok(_) => panic!("rocket should fail to ignite with an unknown active key"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,491 @@
+use configmonkey::routes::v1::{
+    dtos::{ErrorDto, PaginatedListDto},
+    versions_routes::{GetValueDto, GetVersionDto},
+};
+use rocket::{
+    error::ErrorKind,
+    http::{ContentType, Status},
+    local::asynchronous::Client,
+    serde::json::{json, Value},
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+const MASTER_KEY: &str = "MDEyMzQ1Njc4OTAxMjM0NTY3ODkwMTIzNDU2Nzg5MDE=";
+const REDACTED: &str = "********";
+
+async fn async_client_with_master_key(pg_connect_options: PgConnectOptions) -> Client {
+    async_client_with_secrets(pg_connect_options, json!({ "master_key": MASTER_KEY })).await
+}
+
+#[sqlx::test]
+async fn create_secret_version_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+
+    let response = h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 1);
+    assert_eq!(get_version_dto.value, json!(REDACTED));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn plain_values_are_encrypted_at_rest(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options.clone()).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+
+    let response = h_create_version(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 3}),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.value, json!({"attempts": 3}));
+
+    let pool = pg_pool_options.connect_with(pg_connect_options).await?;
+    let (value, key_id): (Value, Option<String>) =
+        sqlx::query_as("select value, key_id from versions")
+            .fetch_one(&pool)
+            .await?;
+    assert_eq!(key_id.as_deref(), Some("default"));
+    assert!(value.is_string());
+    assert!(!value.as_str().unwrap().contains("attempts"));
+
+    let response = h_get_value(&client, "configmonkey", "retry_policy").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!({"attempts": 3}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_secret_version_err_secrets_disabled(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+
+    let response = h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "secrets_disabled");
+    assert_eq!(
+        error_dto.message,
+        "Secret values require a master key to be configured"
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_value_property_err_secret_property(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database",
+        json!({"host": "db-1", "password": null}),
+    )
+    .await;
+
+    let response = client
+        .put("/v1/configs/configmonkey/database/value/password")
+        .header(ContentType::JSON)
+        .body(json!({ "value": "hunter2", "secret": true }).to_string())
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "secret_property");
+    assert_eq!(
+        error_dto.message,
+        "A property cannot be a secret, the whole value has to be created as one"
+    );
+
+    let response = h_get_value(&client, "configmonkey", "database").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(
+        get_value_dto.value,
+        json!({"host": "db-1", "password": null})
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn secret_values_are_redacted(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+    h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!({"user": "admin", "password": "hunter2"}),
+    )
+    .await;
+
+    let response = h_get_value(&client, "configmonkey", "database_password").await;
+    assert_eq!(response.status(), Status::Ok);
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!(REDACTED));
+
+    let response = h_get_versions(&client, "configmonkey", "database_password", None, None).await;
+    assert_eq!(response.status(), Status::Ok);
+    let response_body = h_parse_response(response).await;
+    let versions: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(versions.data[0].value, json!(REDACTED));
+
+    let response = h_render_domain(&client, "configmonkey").await;
+    assert_eq!(response.status(), Status::Ok);
+    let response_body = h_parse_response(response).await;
+    let document: Value = h_parse_dto(response_body.as_str());
+    assert_eq!(
+        document,
+        json!({
+            "database_url": "postgres://localhost:1337",
+            "database_password": REDACTED
+        })
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_secret_value_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+    h_create_secret_version(
+        &client,
+        "configmonkey",
+        "database_password",
+        json!({"user": "admin", "password": "hunter2"}),
+    )
+    .await;
+
+    let response = h_get_secret_value(&client, "configmonkey", "database_password").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "configmonkey");
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(
+        get_value_dto.value,
+        json!({"user": "admin", "password": "hunter2"})
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_secret_value_success_not_a_secret(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+
+    let response = h_get_secret_value(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!("postgres://localhost:1337"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_environment_secret_value_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_master_key(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_environment(&client, "configmonkey", "production").await;
+    h_create_config(&client, "configmonkey", "database_password").await;
+    h_create_environment_secret_version(
+        &client,
+        "configmonkey",
+        "production",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+
+    let response =
+        h_get_environment_value(&client, "configmonkey", "production", "database_password").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!(REDACTED));
+
+    let response =
+        h_get_environment_secret_value(&client, "configmonkey", "production", "database_password")
+            .await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!("hunter2"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_secret_value_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets_and_rbac(
+        pg_connect_options,
+        json!({ "master_key": MASTER_KEY }),
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+
+    h_create_domain_as(&client, "root", "configmonkey").await;
+    h_create_config_as(&client, "root", "configmonkey", "database_password").await;
+    h_create_grant(&client, "root", "ci", "writer", Some("configmonkey")).await;
+    h_create_grant(&client, "root", "sre", "admin", Some("configmonkey")).await;
+
+    let response = h_create_version_as(
+        &client,
+        "ci",
+        "configmonkey",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_get_secret_value_as(&client, "ci", "configmonkey", "database_password").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_get_secret_value_as(&client, "sre", "configmonkey", "database_password").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_secret_value_err_forbidden_inherited(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets_and_rbac(
+        pg_connect_options,
+        json!({ "master_key": MASTER_KEY }),
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+
+    h_create_domain_as(&client, "root", "prod").await;
+    h_create_config_as(&client, "root", "prod", "database_password").await;
+    h_create_version_as(
+        &client,
+        "root",
+        "prod",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+    h_create_domain_as(&client, "root", "sandbox").await;
+    h_create_grant(&client, "root", "dev", "admin", Some("sandbox")).await;
+    h_create_grant(&client, "root", "dev", "reader", Some("prod")).await;
+
+    let response = h_set_domain_parent_as(&client, "dev", "sandbox", Some("prod")).await;
+    assert_eq!(response.status(), Status::Ok);
+
+    // admin of the child domain, but only a reader of the one holding the secret
+    let response = h_get_secret_value_as(&client, "dev", "sandbox", "database_password").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    h_create_grant(&client, "root", "sre", "admin", Some("sandbox")).await;
+    h_create_grant(&client, "root", "sre", "admin", Some("prod")).await;
+
+    let response = h_get_secret_value_as(&client, "sre", "sandbox", "database_password").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "prod");
+    assert_eq!(get_value_dto.value, json!("hunter2"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn promote_domain_err_forbidden_secret(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_secrets_and_rbac(
+        pg_connect_options,
+        json!({ "master_key": MASTER_KEY }),
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+
+    h_create_domain_as(&client, "root", "prod").await;
+    h_create_config_as(&client, "root", "prod", "database_password").await;
+    h_create_secret_version_as(
+        &client,
+        "root",
+        "prod",
+        "database_password",
+        json!("hunter2"),
+    )
+    .await;
+    h_create_domain_as(&client, "root", "sandbox").await;
+    h_create_grant(&client, "root", "dev", "reader", Some("prod")).await;
+    h_create_grant(&client, "root", "dev", "admin", Some("sandbox")).await;
+
+    // admin of the target domain, but only a reader of the one holding the secret
+    let response = h_promote_domain_as(&client, "dev", "prod", "sandbox").await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_get_secret_value_as(&client, "dev", "sandbox", "database_password").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    h_create_grant(&client, "root", "sre", "admin", Some("prod")).await;
+    h_create_grant(&client, "root", "sre", "admin", Some("sandbox")).await;
+
+    let response = h_promote_domain_as(&client, "sre", "prod", "sandbox").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = h_get_secret_value_as(&client, "sre", "sandbox", "database_password").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.value, json!("hunter2"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn secrets_err_invalid_master_key(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let result =
+        try_async_client_with_secrets(pg_connect_options, json!({ "master_key": "c2hvcnQ=" }))
+            .await;
+
+    match result {
+        Err(err) => assert!(matches!(err.kind(), ErrorKind::FailedFairings(_))),
+        Ok(_) => panic!("rocket should fail to ignite with an invalid master key"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use configmonkey::routes::v1::{
+    domains_routes::GetDomainDto,
+    dtos::{ErrorDto, PaginatedListDto},
+    tenants_routes::GetTenantDto,
+    versions_routes::GetValueDto,
+};
+use rocket::{
+    http::{ContentType, Status},
+    serde::json::json,
+};
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+#[sqlx::test]
+async fn create_tenant_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_tenant(&client, "payments").await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_tenant_dto: GetTenantDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_tenant_dto.slug, "payments");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_tenant_err_exists(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_tenant(&client, "default").await;
+
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "tenant_already_exists");
+    assert_eq!(error_dto.message, "Tenant already exists");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_tenant_err_invalid_slug(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_tenant(&client, "team payments").await;
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "invalid_slug");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_tenants_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "payments").await;
+    h_create_tenant(&client, "shipping").await;
+
+    let response = h_get_tenants(&client, Some(2), None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_tenants_dto: PaginatedListDto<GetTenantDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_tenants_dto.data.len(), 2);
+    assert_eq!(get_tenants_dto.data[0].slug, "default");
+    assert_eq!(get_tenants_dto.data[1].slug, "payments");
+    h_validate_pagination(
+        get_tenants_dto.pagination,
+        2,
+        2,
+        0,
+        Some(String::from("/v1/tenants?limit=2&offset=2")),
+        None,
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_domain_success_same_slug_in_tenants(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "payments").await;
+    h_create_domain(&client, "billing").await;
+    h_create_domain(&client, "shipping").await;
+
+    let response = h_create_tenant_domain(&client, "payments", "billing", None).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    // every tenant only sees its own domains
+    let response = h_get_tenant_domains(&client, "payments").await;
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 1);
+    assert_eq!(get_domains_dto.data[0].slug, "billing");
+
+    let response = h_get_domains(&client, None, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_domains_dto: PaginatedListDto<GetDomainDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_domains_dto.data.len(), 2);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_domain_err_tenant_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_create_tenant_domain(&client, "payments", "billing", None).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "tenant_not_found");
+    assert_eq!(error_dto.message, "Tenant not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_domain_err_parent_in_other_tenant(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "payments").await;
+    h_create_domain(&client, "base").await;
+
+    let response = h_create_tenant_domain(&client, "payments", "billing", Some("base")).await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "parent_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_domain_err_other_tenant(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "payments").await;
+    h_create_tenant_domain(&client, "payments", "billing", None).await;
+
+    let response = h_delete_domain(&client, "billing").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_found");
+
+    let response = h_delete_tenant_domain(&client, "payments", "billing").await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_success_isolated_per_tenant(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_tenant(&client, "payments").await;
+    h_create_domain(&client, "billing").await;
+    h_create_config(&client, "billing", "timeout").await;
+    h_create_version(&client, "billing", "timeout", json!(10)).await;
+
+    h_create_tenant_domain(&client, "payments", "billing", None).await;
+    h_create_tenant_config(&client, "payments", "billing", "timeout").await;
+    let response =
+        h_create_tenant_version(&client, "payments", "billing", "timeout", json!(30)).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_get_tenant_value(&client, "payments", "billing", "timeout").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(get_value_dto.value, json!(30));
+
+    let response = h_get_value(&client, "billing", "timeout").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(get_value_dto.value, json!(10));
+
+    // domains of other tenants cannot be read or written
+    let response = h_get_tenant_value(&client, "shipping", "billing", "timeout").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    let response = h_create_tenant_config(&client, "shipping", "billing", "retries").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    Ok(())
+}
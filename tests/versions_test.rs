@@ -1,12 +1,15 @@
 use configmonkey::routes::v1::{
     dtos::{ErrorDto, PaginatedListDto},
-    versions_routes::GetVersionDto,
+    versions_routes::{GetValueDto, GetVersionDto, VersionDiffDto, VersionPreviewDto},
 };
 use rocket::{
+    futures::future::join_all,
     http::{ContentType, Status},
     serde::json::json,
+    tokio::{self, time::sleep},
 };
 use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use std::time::{Duration, Instant};
 
 mod common;
 
@@ -22,11 +25,14 @@ async fn create_version_success(
     h_create_domain(&client, "configmonkey").await;
     h_create_config(&client, "configmonkey", "database_url").await;
 
-    let versions = vec![
+    let versions = [
         json!("postgres://localhost:1337"),
         json!(1.0),
         json!(1),
         json!(true),
+        json!({"retries": {"max": 3, "backoff": 1.5}, "enabled": true}),
+        json!(["db-1.internal", "db-2.internal"]),
+        json!(null),
     ];
 
     for version in versions.iter() {
@@ -44,6 +50,516 @@ async fn create_version_success(
     Ok(())
 }
 
+#[sqlx::test]
+async fn create_version_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+
+    // first version, nothing to compare with
+    let response = h_create_version_dry_run(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 3}),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let preview_dto: VersionPreviewDto = h_parse_dto(response_body.as_str());
+    assert_eq!(preview_dto.version.id, 1);
+    assert_eq!(preview_dto.version.value, json!({"attempts": 3}));
+    assert!(preview_dto.diff.is_none());
+
+    h_create_version(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 3}),
+    )
+    .await;
+
+    let response = h_create_version_dry_run(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 5}),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let preview_dto: VersionPreviewDto = h_parse_dto(response_body.as_str());
+    assert_eq!(preview_dto.version.id, 2);
+    assert_eq!(
+        preview_dto.diff.unwrap().patch,
+        Some(vec![
+            json!({"op": "replace", "path": "/attempts", "value": 5})
+        ])
+    );
+
+    // assert not created
+    let response = h_get_versions(&client, "configmonkey", "retry_policy", None, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data.len(), 1);
+    assert_eq!(get_versions_dto.data[0].value, json!({"attempts": 3}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_err_config_not_found_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let response =
+        h_create_version_dry_run(&client, "configmonkey", "database_url", json!("localhost")).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_versions_success_structured_values(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+
+    let value = json!({
+        "max_attempts": 5,
+        "hosts": ["a.internal", {"name": "b.internal", "weight": 0.5}],
+        "fallback": null
+    });
+    h_create_version(&client, "configmonkey", "retry_policy", value.clone()).await;
+
+    let response = h_get_versions(&client, "configmonkey", "retry_policy", None, None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data.len(), 1);
+    assert_eq!(get_versions_dto.data[0].value, value);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_success_author_and_message(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+
+    h_create_version(&client, "configmonkey", "request_timeout", json!(30)).await;
+    let response = h_create_version_with_message(
+        &client,
+        "alice",
+        "configmonkey",
+        "request_timeout",
+        json!(90),
+        "Slow upstream during batch imports",
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let version: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(version.author, Some(String::from("alice")));
+
+    let response = h_get_versions(&client, "configmonkey", "request_timeout", None, None).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data[0].author, Some(String::from("alice")));
+    assert_eq!(
+        get_versions_dto.data[0].message,
+        Some(String::from("Slow upstream during batch imports"))
+    );
+    assert_eq!(
+        get_versions_dto.data[1].author,
+        Some(String::from("anonymous"))
+    );
+    assert_eq!(get_versions_dto.data[1].message, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rollback_version_success_author(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+    h_create_version_with_message(
+        &client,
+        "alice",
+        "configmonkey",
+        "request_timeout",
+        json!(30),
+        "Initial timeout",
+    )
+    .await;
+
+    let response = h_rollback_version(&client, "configmonkey", "request_timeout", 1).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let version: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(version.restored_from, Some(1));
+    assert_eq!(version.author, Some(String::from("anonymous")));
+    assert_eq!(version.message, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_wait_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_version(&client, "configmonkey", "request_timeout", json!(30)).await;
+
+    let started_at = Instant::now();
+    let (response, _) = tokio::join!(
+        h_watch_value(&client, "configmonkey", "request_timeout", 1, "30s"),
+        async {
+            sleep(Duration::from_millis(300)).await;
+            // Versions of other configs do not end the wait
+            h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+            h_create_version(&client, "configmonkey", "request_timeout", json!(90)).await;
+        }
+    );
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(started_at.elapsed() < Duration::from_secs(30));
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!(90));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_wait_success_inherited(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "platform").await;
+    h_create_config(&client, "platform", "request_timeout").await;
+    h_create_version(&client, "platform", "request_timeout", json!(30)).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_set_domain_parent(&client, "configmonkey", Some("platform")).await;
+    h_create_domain(&client, "billing").await;
+    h_create_config(&client, "billing", "request_timeout").await;
+
+    let started_at = Instant::now();
+    let (response, _) = tokio::join!(
+        h_watch_value(&client, "configmonkey", "request_timeout", 1, "30s"),
+        async {
+            sleep(Duration::from_millis(300)).await;
+            // Versions of the same key in unrelated domains do not end the wait
+            h_create_version(&client, "billing", "request_timeout", json!(60)).await;
+            h_create_version(&client, "platform", "request_timeout", json!(90)).await;
+        }
+    );
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(started_at.elapsed() < Duration::from_secs(30));
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "platform");
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!(90));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_wait_success_timeout(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+    h_create_version(&client, "configmonkey", "request_timeout", json!(30)).await;
+
+    let started_at = Instant::now();
+    let response = h_watch_value(&client, "configmonkey", "request_timeout", 1, "500ms").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(started_at.elapsed() >= Duration::from_millis(500));
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+
+    // The latest value is returned right away when it already differs
+    let started_at = Instant::now();
+    let response = h_watch_value(&client, "configmonkey", "request_timeout", 0, "30s").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(started_at.elapsed() < Duration::from_secs(30));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_wait_success_first_version(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+
+    let (response, _) = tokio::join!(
+        h_watch_value(&client, "configmonkey", "request_timeout", 0, "30s"),
+        async {
+            sleep(Duration::from_millis(300)).await;
+            // Dry runs are never committed, so they do not end the wait
+            h_create_version_dry_run(&client, "configmonkey", "request_timeout", json!(10)).await;
+            h_create_version(&client, "configmonkey", "request_timeout", json!(30)).await;
+        }
+    );
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(get_value_dto.value, json!(30));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_err_invalid_wait(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "request_timeout").await;
+    h_create_version(&client, "configmonkey", "request_timeout", json!(30)).await;
+
+    for wait in ["soon", "30h", "-1s"] {
+        let response = h_watch_value(&client, "configmonkey", "request_timeout", 1, wait).await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "invalid_wait");
+        assert_eq!(
+            error_dto.message,
+            "The wait must be a duration such as 30s, 500ms or 1m"
+        );
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_success_if_match(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+
+    // "0" stands for a config without versions
+    let response = h_create_version_if_match(
+        &client,
+        "configmonkey",
+        "max_connections",
+        json!(10),
+        "\"0\"",
+    )
+    .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_get_value(&client, "configmonkey", "max_connections").await;
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    let response =
+        h_create_version_if_match(&client, "configmonkey", "max_connections", json!(20), &etag)
+            .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(version_dto.id, 2);
+
+    // any of a list of versions, or any version at all
+    let response = h_create_version_if_match(
+        &client,
+        "configmonkey",
+        "max_connections",
+        json!(30),
+        "\"1\", \"2\"",
+    )
+    .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response =
+        h_create_version_if_match(&client, "configmonkey", "max_connections", json!(40), "*").await;
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_err_version_mismatch(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+
+    let response =
+        h_create_version_if_match(&client, "configmonkey", "max_connections", json!(10), "*").await;
+    assert_eq!(response.status(), Status::PreconditionFailed);
+
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(20)).await;
+
+    for if_match in ["\"1\"", "\"0\"", "W/\"2\"", "2"] {
+        let response = h_create_version_if_match(
+            &client,
+            "configmonkey",
+            "max_connections",
+            json!(30),
+            if_match,
+        )
+        .await;
+
+        assert_eq!(response.status(), Status::PreconditionFailed);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "version_mismatch");
+        assert_eq!(
+            error_dto.message,
+            "The latest version of the config does not match the one given in If-Match"
+        );
+    }
+
+    let response = h_get_value(&client, "configmonkey", "max_connections").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!(20));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_concurrent_if_match(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    // Writers based on the same version, of which only one may win
+    let responses = join_all((0..8).map(|value| {
+        h_create_version_if_match(
+            &client,
+            "configmonkey",
+            "max_connections",
+            json!(value),
+            "\"1\"",
+        )
+    }))
+    .await;
+
+    let statuses: Vec<Status> = responses.iter().map(|response| response.status()).collect();
+    assert_eq!(
+        statuses
+            .iter()
+            .filter(|status| **status == Status::Created)
+            .count(),
+        1
+    );
+    assert!(statuses
+        .iter()
+        .all(|status| *status == Status::Created || *status == Status::PreconditionFailed));
+
+    // Writers without If-Match all succeed, one after the other
+    let responses =
+        join_all((0..8).map(|value| {
+            h_create_version(&client, "configmonkey", "max_connections", json!(value))
+        }))
+        .await;
+
+    assert!(responses
+        .iter()
+        .all(|response| response.status() == Status::Created));
+
+    let response = h_get_value(&client, "configmonkey", "max_connections").await;
+    assert_eq!(response.headers().get_one("ETag"), Some("\"10\""));
+
+    Ok(())
+}
+
 #[sqlx::test]
 async fn create_version_err_domain_not_found(
     _: PgPoolOptions,
@@ -51,55 +567,621 @@ async fn create_version_err_domain_not_found(
 ) -> sqlx::Result<()> {
     let client = async_client_from_pg_connect_options(pg_connect_options).await;
 
-    let response = h_create_version(
+    let response = h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+    assert_eq!(error_dto.message, "Domain not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_version_err_config_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let response = h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_not_found");
+    assert_eq!(error_dto.message, "Config not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_versions_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1338"),
+    )
+    .await;
+
+    // get first page
+    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data.len(), 1);
+    assert_eq!(get_versions_dto.data[0].value, "postgres://localhost:1338");
+
+    h_validate_pagination(
+        get_versions_dto.pagination,
+        1,
+        1,
+        0,
+        Some(String::from(
+            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=1",
+        )),
+        None,
+    );
+
+    // get second page
+    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(1)).await;
+
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data.len(), 1);
+    assert_eq!(get_versions_dto.data[0].value, "postgres://localhost:1337");
+
+    h_validate_pagination(
+        get_versions_dto.pagination,
+        1,
+        1,
+        1,
+        Some(String::from(
+            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=2",
+        )),
+        Some(String::from(
+            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=0",
+        )),
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_versions_err_domain_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+    assert_eq!(error_dto.message, "Domain not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_versions_err_config_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+
+    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_not_found");
+    assert_eq!(error_dto.message, "Config not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_version_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    for timeout in [30, 60, 90] {
+        h_create_version(&client, "configmonkey", "timeout", json!(timeout)).await;
+    }
+
+    let response = h_get_version(&client, "configmonkey", "timeout", 2).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 2);
+    assert_eq!(get_version_dto.value, json!(60));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_version_err_version_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    h_create_version(&client, "configmonkey", "timeout", json!(30)).await;
+
+    let response = h_get_version(&client, "configmonkey", "timeout", 2).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "version_not_found");
+    assert_eq!(error_dto.message, "Version not found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn diff_versions_success_scalar(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    h_create_version(&client, "configmonkey", "timeout", json!(30)).await;
+    h_create_version(&client, "configmonkey", "timeout", json!(90)).await;
+    h_create_version(&client, "configmonkey", "timeout", json!("90s")).await;
+
+    let response = h_diff_versions(&client, "configmonkey", "timeout", 1, 2).await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let diff_dto: VersionDiffDto = h_parse_dto(response_body.as_str());
+    assert_eq!(diff_dto.from, 1);
+    assert_eq!(diff_dto.to, 2);
+    assert!(diff_dto.diff.type_change.is_none());
+    assert!(diff_dto.diff.patch.is_none());
+    let value_change = diff_dto.diff.value_change.expect("Value change");
+    assert_eq!(value_change.from, json!(30));
+    assert_eq!(value_change.to, json!(90));
+
+    let response = h_diff_versions(&client, "configmonkey", "timeout", 2, 3).await;
+
+    let response_body = h_parse_response(response).await;
+    let diff_dto: VersionDiffDto = h_parse_dto(response_body.as_str());
+    let type_change = diff_dto.diff.type_change.expect("Type change");
+    assert_eq!(type_change.from, "integer");
+    assert_eq!(type_change.to, "string");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn diff_versions_success_structured(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "settings",
+        json!({"hosts": ["db-1", "db-2", "db-3"], "pool": 10, "debug": true}),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "settings",
+        json!({"hosts": ["db-1", "db-4"], "pool": 20, "a/b": 1}),
+    )
+    .await;
+
+    let response = h_diff_versions(&client, "configmonkey", "settings", 1, 2).await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let diff_dto: VersionDiffDto = h_parse_dto(response_body.as_str());
+    assert!(diff_dto.diff.type_change.is_none());
+    assert!(diff_dto.diff.value_change.is_none());
+    assert_eq!(
+        diff_dto.diff.patch.expect("Patch"),
+        vec![
+            json!({"op": "remove", "path": "/debug"}),
+            json!({"op": "replace", "path": "/hosts/1", "value": "db-4"}),
+            json!({"op": "remove", "path": "/hosts/2"}),
+            json!({"op": "replace", "path": "/pool", "value": 20}),
+            json!({"op": "add", "path": "/a~1b", "value": 1}),
+        ]
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn diff_versions_err_version_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "timeout").await;
+    h_create_version(&client, "configmonkey", "timeout", json!(30)).await;
+
+    let response = h_diff_versions(&client, "configmonkey", "timeout", 1, 2).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "version_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rollback_version_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 3}),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "retry_policy",
+        json!({"attempts": 0}),
+    )
+    .await;
+
+    let response = h_rollback_version(&client, "configmonkey", "retry_policy", 1).await;
+
+    assert_eq!(response.status(), Status::Created);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 3);
+    assert_eq!(get_version_dto.restored_from, Some(1));
+    assert_eq!(get_version_dto.value, json!({"attempts": 3}));
+
+    // History is kept and the restored version becomes the latest
+    let response = h_get_versions(&client, "configmonkey", "retry_policy", None, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(get_versions_dto.data.len(), 3);
+    assert_eq!(get_versions_dto.data[0].restored_from, Some(1));
+    assert_eq!(get_versions_dto.data[1].restored_from, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rollback_version_err_version_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "retry_policy").await;
+
+    let response = h_rollback_version(&client, "configmonkey", "retry_policy", 1).await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "version_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rollback_version_err_latest_version_unreadable(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options.clone()).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+    h_create_version(&client, "configmonkey", "log_level", json!("debug")).await;
+
+    // The latest value is under a key that is no longer configured
+    let pool = pg_pool_options.connect_with(pg_connect_options).await?;
+    sqlx::query("update versions set key_id = 'retired' where version = 2")
+        .execute(&pool)
+        .await?;
+
+    let response = h_rollback_version(&client, "configmonkey", "log_level", 1).await;
+
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let count: i64 = sqlx::query_scalar("select count(*) from versions")
+        .fetch_one(&pool)
+        .await?;
+    assert_eq!(count, 2);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "database_url").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1337"),
+    )
+    .await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "database_url",
+        json!("postgres://localhost:1338"),
+    )
+    .await;
+
+    let response = h_get_value(&client, "configmonkey", "database_url").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    assert_eq!(response.headers().get_one("ETag"), Some("\"2\""));
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "configmonkey");
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!("postgres://localhost:1338"));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_success_inherited(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "billing-base").await;
+    h_create_child_domain(&client, "billing-staging", "billing-base").await;
+    h_create_child_domain(&client, "billing-staging-eu", "billing-staging").await;
+    h_create_config(&client, "billing-base", "timeout").await;
+    h_create_version(&client, "billing-base", "timeout", json!(30)).await;
+    // configs without versions do not hide the inherited value
+    h_create_config(&client, "billing-staging", "timeout").await;
+
+    let response = h_get_value(&client, "billing-staging-eu", "timeout").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    // inherited values cannot be changed from the domain inheriting them
+    assert_eq!(response.headers().get_one("ETag"), None);
+
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "billing-base");
+    assert_eq!(get_value_dto.version, 1);
+    assert_eq!(get_value_dto.value, json!(30));
+
+    // the closest domain with a version of its own wins
+    h_create_version(&client, "billing-staging", "timeout", json!(5)).await;
+
+    let response = h_get_value(&client, "billing-staging-eu", "timeout").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.domain, "billing-staging");
+    assert_eq!(get_value_dto.value, json!(5));
+
+    let response = h_get_value(&client, "billing-staging-eu", "region").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_err_not_found(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    // Domain missing
+    let response = h_get_value(&client, "configmonkey", "database_url").await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    // Config missing
+    h_create_domain(&client, "configmonkey").await;
+    let response = h_get_value(&client, "configmonkey", "database_url").await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "config_not_found");
+
+    // Config without versions
+    h_create_config(&client, "configmonkey", "database_url").await;
+    let response = h_get_value(&client, "configmonkey", "database_url").await;
+    assert_eq!(response.status(), Status::NotFound);
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "no_versions");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_property_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_config(&client, "configmonkey", "settings_text").await;
+
+    let document = json!({
+        "database": {"hosts": ["db-1", "db-2"], "pool": 10},
+        "feature/flags": {"dark_mode": true}
+    });
+    h_create_version(&client, "configmonkey", "settings", document.clone()).await;
+    h_create_version(
         &client,
         "configmonkey",
-        "database_url",
-        json!("postgres://localhost:1337"),
+        "settings_text",
+        json!(document.to_string()),
     )
     .await;
 
-    assert_eq!(response.status(), Status::NotFound);
-    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let cases = [
+        ("database/pool", json!(10)),
+        ("database/hosts/1", json!("db-2")),
+        ("database", json!({"hosts": ["db-1", "db-2"], "pool": 10})),
+        ("feature~1flags/dark_mode", json!(true)),
+    ];
 
-    let response_body = h_parse_response(response).await;
-    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
-    assert_eq!(error_dto.code, "domain_not_found");
-    assert_eq!(error_dto.message, "Domain not found");
+    for key in ["settings", "settings_text"] {
+        for (path, expected) in cases.iter() {
+            let response = h_get_value_property(&client, "configmonkey", key, path).await;
+
+            assert_eq!(response.status(), Status::Ok);
+            assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+            let response_body = h_parse_response(response).await;
+            let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+            assert_eq!(get_value_dto.version, 1);
+            assert_eq!(&get_value_dto.value, expected);
+        }
+    }
 
     Ok(())
 }
 
 #[sqlx::test]
-async fn create_version_err_config_not_found(
+async fn get_value_property_err_property_not_found(
     _: PgPoolOptions,
     pg_connect_options: PgConnectOptions,
 ) -> sqlx::Result<()> {
     let client = async_client_from_pg_connect_options(pg_connect_options).await;
 
     h_create_domain(&client, "configmonkey").await;
-
-    let response = h_create_version(
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(
         &client,
         "configmonkey",
-        "database_url",
-        json!("postgres://localhost:1337"),
+        "settings",
+        json!({"hosts": ["db-1"]}),
     )
     .await;
 
-    assert_eq!(response.status(), Status::NotFound);
-    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    for path in ["port", "hosts/1", "hosts/01", "hosts/0/name"] {
+        let response = h_get_value_property(&client, "configmonkey", "settings", path).await;
 
-    let response_body = h_parse_response(response).await;
-    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
-    assert_eq!(error_dto.code, "config_not_found");
-    assert_eq!(error_dto.message, "Config not found");
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "property_not_found");
+        assert_eq!(error_dto.message, "Property not found");
+    }
 
     Ok(())
 }
 
 #[sqlx::test]
-async fn get_versions_success(
+async fn get_value_property_err_not_a_document(
     _: PgPoolOptions,
     pg_connect_options: PgConnectOptions,
 ) -> sqlx::Result<()> {
@@ -114,97 +1196,343 @@ async fn get_versions_success(
         json!("postgres://localhost:1337"),
     )
     .await;
+
+    let response = h_get_value_property(&client, "configmonkey", "database_url", "host").await;
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "not_a_document");
+    assert_eq!(
+        error_dto.message,
+        "The config value is not a JSON object or array"
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_value_property_err_no_versions(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+
+    let response = h_get_value_property(&client, "configmonkey", "settings", "port").await;
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "no_versions");
+    assert_eq!(
+        error_dto.message,
+        "The config does not have any versions yet"
+    );
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_value_property_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
     h_create_version(
         &client,
         "configmonkey",
-        "database_url",
-        json!("postgres://localhost:1338"),
+        "settings",
+        json!({"database": {"hosts": ["db-1"], "pool": 10}}),
     )
     .await;
 
-    // get first page
-    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+    let updates = vec![
+        ("database/pool", json!(20)),
+        ("database/hosts/-", json!("db-2")),
+        ("database/timeout", json!({"connect": 5})),
+    ];
+    for (path, value) in updates {
+        let response = h_set_value_property(&client, "configmonkey", "settings", path, value).await;
+        assert_eq!(response.status(), Status::Created);
+    }
 
+    let response = h_get_versions(&client, "configmonkey", "settings", Some(1), Some(0)).await;
     let response_body = h_parse_response(response).await;
     let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
 
-    assert_eq!(get_versions_dto.data.len(), 1);
-    assert_eq!(get_versions_dto.data[0].value, "postgres://localhost:1338");
-
-    h_validate_pagination(
-        get_versions_dto.pagination,
-        1,
-        1,
-        0,
-        Some(String::from(
-            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=1",
-        )),
-        None,
+    assert_eq!(get_versions_dto.data[0].id, 4);
+    assert_eq!(
+        get_versions_dto.data[0].value,
+        json!({"database": {"hosts": ["db-1", "db-2"], "pool": 20, "timeout": {"connect": 5}}})
     );
 
-    // get second page
-    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(1)).await;
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_value_property_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(&client, "configmonkey", "settings", json!({"pool": 10})).await;
+
+    let response =
+        h_set_value_property_dry_run(&client, "configmonkey", "settings", "pool", json!(20)).await;
+
+    assert_eq!(response.status(), Status::Ok);
 
+    let response_body = h_parse_response(response).await;
+    let preview_dto: VersionPreviewDto = h_parse_dto(response_body.as_str());
+    assert_eq!(preview_dto.version.id, 2);
+    assert_eq!(preview_dto.version.value, json!({"pool": 20}));
+    assert_eq!(
+        preview_dto.diff.unwrap().patch,
+        Some(vec![json!({"op": "replace", "path": "/pool", "value": 20})])
+    );
+
+    // assert not created
+    let response = h_get_versions(&client, "configmonkey", "settings", None, None).await;
     let response_body = h_parse_response(response).await;
     let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
 
     assert_eq!(get_versions_dto.data.len(), 1);
-    assert_eq!(get_versions_dto.data[0].value, "postgres://localhost:1337");
+    assert_eq!(get_versions_dto.data[0].value, json!({"pool": 10}));
 
-    h_validate_pagination(
-        get_versions_dto.pagination,
-        1,
-        1,
-        1,
-        Some(String::from(
-            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=2",
-        )),
-        Some(String::from(
-            "/v1/configs/configmonkey/database_url/versions?limit=1&offset=0",
-        )),
-    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn set_value_property_success_text_document(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "settings",
+        json!(r#"{"debug":false}"#),
+    )
+    .await;
+
+    let response =
+        h_set_value_property(&client, "configmonkey", "settings", "debug", json!(true)).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 2);
+    assert_eq!(get_version_dto.value, json!(r#"{"debug":true}"#));
 
     Ok(())
 }
 
 #[sqlx::test]
-async fn get_versions_err_domain_not_found(
+async fn set_value_property_err_property_not_found(
     _: PgPoolOptions,
     pg_connect_options: PgConnectOptions,
 ) -> sqlx::Result<()> {
     let client = async_client_from_pg_connect_options(pg_connect_options).await;
 
-    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(&client, "configmonkey", "settings", json!({"hosts": []})).await;
+
+    for path in ["database/pool", "hosts/1"] {
+        let response =
+            h_set_value_property(&client, "configmonkey", "settings", path, json!(1)).await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "property_not_found");
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_value_property_success(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "settings",
+        json!({"hosts": ["db-1", "db-2"], "debug": true}),
+    )
+    .await;
 
+    let response = h_delete_value_property(&client, "configmonkey", "settings", "debug").await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response = h_delete_value_property(&client, "configmonkey", "settings", "hosts/0").await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 3);
+    assert_eq!(get_version_dto.value, json!({"hosts": ["db-2"]}));
+
+    let response = h_delete_value_property(&client, "configmonkey", "settings", "debug").await;
     assert_eq!(response.status(), Status::NotFound);
-    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn value_property_success_if_match(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(&client, "configmonkey", "settings", json!({"pool": 10})).await;
+
+    let response = h_get_value(&client, "configmonkey", "settings").await;
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    let response = h_set_value_property_if_match(
+        &client,
+        "configmonkey",
+        "settings",
+        "debug",
+        json!(true),
+        &etag,
+    )
+    .await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response = h_get_value(&client, "configmonkey", "settings").await;
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+
+    let response =
+        h_delete_value_property_if_match(&client, "configmonkey", "settings", "debug", &etag).await;
+    assert_eq!(response.status(), Status::Ok);
 
     let response_body = h_parse_response(response).await;
-    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
-    assert_eq!(error_dto.code, "domain_not_found");
-    assert_eq!(error_dto.message, "Domain not found");
+    let get_version_dto: GetVersionDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_version_dto.id, 3);
+    assert_eq!(get_version_dto.value, json!({"pool": 10}));
 
     Ok(())
 }
 
 #[sqlx::test]
-async fn get_versions_err_config_not_found(
+async fn value_property_err_version_mismatch(
     _: PgPoolOptions,
     pg_connect_options: PgConnectOptions,
 ) -> sqlx::Result<()> {
     let client = async_client_from_pg_connect_options(pg_connect_options).await;
 
     h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(&client, "configmonkey", "settings", json!({"pool": 10})).await;
+    h_create_version(&client, "configmonkey", "settings", json!({"pool": 20})).await;
 
-    let response = h_get_versions(&client, "configmonkey", "database_url", Some(1), Some(0)).await;
+    let response = h_set_value_property_if_match(
+        &client,
+        "configmonkey",
+        "settings",
+        "pool",
+        json!(30),
+        "\"1\"",
+    )
+    .await;
 
-    assert_eq!(response.status(), Status::NotFound);
-    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    assert_eq!(response.status(), Status::PreconditionFailed);
 
     let response_body = h_parse_response(response).await;
     let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
-    assert_eq!(error_dto.code, "config_not_found");
-    assert_eq!(error_dto.message, "Config not found");
+    assert_eq!(error_dto.code, "version_mismatch");
+
+    let response =
+        h_delete_value_property_if_match(&client, "configmonkey", "settings", "pool", "\"1\"")
+            .await;
+
+    assert_eq!(response.status(), Status::PreconditionFailed);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "version_mismatch");
+
+    // assert not created
+    let response = h_get_value(&client, "configmonkey", "settings").await;
+    let response_body = h_parse_response(response).await;
+    let get_value_dto: GetValueDto = h_parse_dto(response_body.as_str());
+    assert_eq!(get_value_dto.version, 2);
+    assert_eq!(get_value_dto.value, json!({"pool": 20}));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn delete_value_property_success_dry_run(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "settings").await;
+    h_create_version(
+        &client,
+        "configmonkey",
+        "settings",
+        json!({"hosts": ["db-1"], "debug": true}),
+    )
+    .await;
+
+    let response =
+        h_delete_value_property_dry_run(&client, "configmonkey", "settings", "debug").await;
+
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    let preview_dto: VersionPreviewDto = h_parse_dto(response_body.as_str());
+    assert_eq!(preview_dto.version.id, 2);
+    assert_eq!(preview_dto.version.value, json!({"hosts": ["db-1"]}));
+    assert_eq!(
+        preview_dto.diff.unwrap().patch,
+        Some(vec![json!({"op": "remove", "path": "/debug"})])
+    );
+
+    // assert not created
+    let response = h_get_versions(&client, "configmonkey", "settings", None, None).await;
+    let response_body = h_parse_response(response).await;
+    let get_versions_dto: PaginatedListDto<GetVersionDto> = h_parse_dto(response_body.as_str());
+
+    assert_eq!(get_versions_dto.data.len(), 1);
+    assert_eq!(
+        get_versions_dto.data[0].value,
+        json!({"hosts": ["db-1"], "debug": true})
+    );
 
     Ok(())
 }
@@ -0,0 +1,548 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use configmonkey::routes::v1::{
+    dtos::{ErrorDto, PaginatedListDto},
+    webhooks_routes::{GetWebhookDeliveryDto, GetWebhookDto},
+};
+use hmac::{Hmac, Mac};
+use rocket::{
+    http::{ContentType, Status},
+    local::asynchronous::Client,
+    serde::json::{json, Value},
+    tokio::{
+        self,
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+        sync::mpsc::{self, UnboundedReceiver},
+        time::{sleep, timeout},
+    },
+};
+use sha2::Sha256;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+mod common;
+
+pub use common::helpers::*;
+
+/// A request received by an endpoint
+struct ReceivedRequest {
+    /// Headers, by lowercase name
+    headers: HashMap<String, String>,
+    body: String,
+}
+
+/// Start an HTTP endpoint answering requests with the given statuses in turn, then with 200,
+/// returning its url along with the requests it receives
+async fn h_start_endpoint(statuses: Vec<u16>) -> (String, UnboundedReceiver<ReceivedRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hooks", listener.local_addr().unwrap());
+    let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut request = vec![];
+            let mut chunk = [0u8; 4096];
+            let (head, body_start) = loop {
+                let read = stream.read(&mut chunk).await.unwrap_or(0);
+                if read == 0 {
+                    break (String::new(), 0);
+                }
+                request.extend_from_slice(&chunk[..read]);
+                if let Some(end) = request.windows(4).position(|window| window == b"\r\n\r\n") {
+                    break (
+                        String::from_utf8_lossy(&request[..end]).to_string(),
+                        end + 4,
+                    );
+                }
+            };
+            let headers: HashMap<String, String> = head
+                .lines()
+                .skip(1)
+                .filter_map(|line| line.split_once(':'))
+                .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+                .collect();
+            let length: usize = headers
+                .get("content-length")
+                .and_then(|length| length.parse().ok())
+                .unwrap_or(0);
+            while request.len() < body_start + length {
+                match stream.read(&mut chunk).await {
+                    Ok(read) if read > 0 => request.extend_from_slice(&chunk[..read]),
+                    _ => break,
+                }
+            }
+            let body = String::from_utf8_lossy(&request[body_start..]).to_string();
+
+            let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+            let response = format!(
+                "HTTP/1.1 {} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+            let _ = sender.send(ReceivedRequest { headers, body });
+        }
+    });
+
+    (url, receiver)
+}
+
+async fn h_receive(requests: &mut UnboundedReceiver<ReceivedRequest>) -> ReceivedRequest {
+    timeout(Duration::from_secs(10), requests.recv())
+        .await
+        .expect("a request within 10 seconds")
+        .expect("a running endpoint")
+}
+
+async fn h_webhook(client: &Client, domain_slug: &str, webhook: Value) -> GetWebhookDto {
+    let response = h_create_webhook(client, domain_slug, webhook).await;
+    assert_eq!(response.status(), Status::Created);
+
+    let response_body = h_parse_response(response).await;
+    h_parse_dto(response_body.as_str())
+}
+
+async fn h_deliveries(
+    client: &Client,
+    domain_slug: &str,
+    id: &str,
+) -> PaginatedListDto<GetWebhookDeliveryDto> {
+    let response = h_get_webhook_deliveries(client, domain_slug, id).await;
+    assert_eq!(response.status(), Status::Ok);
+
+    let response_body = h_parse_response(response).await;
+    h_parse_dto(response_body.as_str())
+}
+
+/// Poll the deliveries of a webhook until the latest one is no longer pending
+async fn h_wait_for_delivery(
+    client: &Client,
+    domain_slug: &str,
+    id: &str,
+) -> GetWebhookDeliveryDto {
+    for _ in 0..100 {
+        let mut deliveries = h_deliveries(client, domain_slug, id).await;
+        if deliveries
+            .data
+            .first()
+            .is_some_and(|delivery| delivery.status != "pending")
+        {
+            return deliveries.data.remove(0);
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    panic!("webhook {} still has a pending delivery", id);
+}
+
+fn h_sign(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[sqlx::test]
+async fn webhook_delivers_signed_payload(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let (url, mut requests) = h_start_endpoint(vec![]).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": url, "secret": "s3cret" }),
+    )
+    .await;
+
+    assert_eq!(webhook.url, url);
+    assert_eq!(webhook.events, vec!["version_created", "config_deleted"]);
+    assert_eq!(webhook.keys, None);
+
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    let request = h_receive(&mut requests).await;
+    assert_eq!(
+        request.headers.get("content-type"),
+        Some(&String::from("application/json"))
+    );
+    assert_eq!(
+        request.headers.get("x-configmonkey-event"),
+        Some(&String::from("version_created"))
+    );
+    assert_eq!(
+        request.headers.get("x-configmonkey-signature"),
+        Some(&h_sign("s3cret", request.body.as_str()))
+    );
+
+    let payload: Value = h_parse_dto(request.body.as_str());
+    assert_eq!(payload["event"], json!("version_created"));
+    assert_eq!(payload["domain"], json!("configmonkey"));
+    assert_eq!(payload["key"], json!("max_connections"));
+    assert_eq!(payload["version"], json!(1));
+    assert_eq!(payload["value"], json!(10));
+    assert_eq!(
+        request.headers.get("x-configmonkey-delivery"),
+        payload["id"].as_str().map(str::to_string).as_ref()
+    );
+
+    let delivery = h_wait_for_delivery(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.status, "delivered");
+    assert_eq!(delivery.event, "version_created");
+    assert_eq!(delivery.key, "max_connections");
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(200));
+    assert_eq!(delivery.error, None);
+    assert!(delivery.delivered_at.is_some());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn webhook_retries_failed_deliveries(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_webhooks(pg_connect_options, json!({ "backoff_ms": 50 })).await;
+    let (url, mut requests) = h_start_endpoint(vec![500, 503]).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": url, "secret": "s3cret" }),
+    )
+    .await;
+
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+
+    // The same delivery is attempted again until the endpoint accepts it
+    let mut delivery_ids = vec![];
+    for _ in 0..3 {
+        let request = h_receive(&mut requests).await;
+        delivery_ids.push(request.headers["x-configmonkey-delivery"].clone());
+    }
+    assert!(delivery_ids.iter().all(|id| *id == delivery_ids[0]));
+
+    let delivery = h_wait_for_delivery(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.status, "delivered");
+    assert_eq!(delivery.attempts, 3);
+    assert_eq!(delivery.response_status, Some(200));
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn webhook_gives_up_after_max_attempts(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_webhooks(
+        pg_connect_options,
+        json!({ "backoff_ms": 50, "max_attempts": 2 }),
+    )
+    .await;
+    let (url, _requests) = h_start_endpoint(vec![500, 500, 500]).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": url, "secret": "s3cret" }),
+    )
+    .await;
+
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+
+    let delivery = h_wait_for_delivery(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.status, "failed");
+    assert_eq!(delivery.attempts, 2);
+    assert_eq!(delivery.response_status, Some(500));
+    assert!(delivery.error.is_some());
+    assert_eq!(delivery.next_attempt_at, None);
+    assert_eq!(delivery.delivered_at, None);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn webhook_filters_events_and_keys(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    let (url, mut requests) = h_start_endpoint(vec![]).await;
+    h_create_domain(&client, "configmonkey").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": url, "secret": "s3cret", "events": ["config_deleted"], "keys": ["log_level"] }),
+    )
+    .await;
+
+    assert_eq!(webhook.events, vec!["config_deleted"]);
+    assert_eq!(webhook.keys, Some(vec![String::from("log_level")]));
+
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    h_delete_config(&client, "configmonkey", "max_connections").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+    h_delete_config(&client, "configmonkey", "log_level").await;
+
+    let request = h_receive(&mut requests).await;
+    let payload: Value = h_parse_dto(request.body.as_str());
+    assert_eq!(payload["event"], json!("config_deleted"));
+    assert_eq!(payload["key"], json!("log_level"));
+    assert_eq!(payload["version"], Value::Null);
+
+    let deliveries = h_deliveries(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(deliveries.data.len(), 1);
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn webhook_delivers_pending_deliveries_from_outbox(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    // Deliveries are retried long after failing
+    let webhooks = json!({ "backoff_ms": 3_600_000 });
+    let client = async_client_with_webhooks(pg_connect_options.clone(), webhooks.clone()).await;
+    let (url, mut requests) = h_start_endpoint(vec![]).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": "http://127.0.0.1:1/hooks", "secret": "s3cret" }),
+    )
+    .await;
+
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+
+    let delivery = h_deliveries(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.data.len(), 1);
+
+    // The endpoint moves and the retry becomes due, as if the app had stopped for long
+    let pool = pg_pool_options
+        .connect_with(pg_connect_options.clone())
+        .await?;
+    sqlx::query("update webhooks set url = $1")
+        .bind(url.as_str())
+        .execute(&pool)
+        .await?;
+    sqlx::query("update webhook_deliveries set next_attempt_at = now()")
+        .execute(&pool)
+        .await?;
+
+    let client = async_client_with_webhooks(pg_connect_options, webhooks).await;
+
+    let request = h_receive(&mut requests).await;
+    let payload: Value = h_parse_dto(request.body.as_str());
+    assert_eq!(payload["value"], json!("warn"));
+
+    let delivery = h_wait_for_delivery(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.status, "delivered");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn webhook_fails_deliveries_that_cannot_be_decrypted(
+    pg_pool_options: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let webhooks = json!({ "backoff_ms": 3_600_000 });
+    let client = async_client_with_webhooks(pg_connect_options.clone(), webhooks.clone()).await;
+    let (url, mut requests) = h_start_endpoint(vec![]).await;
+    h_create_domain(&client, "configmonkey").await;
+    h_create_config(&client, "configmonkey", "log_level").await;
+    h_create_config(&client, "configmonkey", "max_connections").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": "http://127.0.0.1:1/hooks", "secret": "s3cret" }),
+    )
+    .await;
+
+    h_create_version(&client, "configmonkey", "log_level", json!("warn")).await;
+    h_create_version(&client, "configmonkey", "max_connections", json!(10)).await;
+
+    // One of the values is under a key that is no longer configured
+    let pool = pg_pool_options
+        .connect_with(pg_connect_options.clone())
+        .await?;
+    sqlx::query(
+        "update versions v set key_id = 'retired' from configs c \
+        where c.id = v.config_id and c.key = 'log_level'",
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query("update webhooks set url = $1")
+        .bind(url.as_str())
+        .execute(&pool)
+        .await?;
+    sqlx::query("update webhook_deliveries set next_attempt_at = now()")
+        .execute(&pool)
+        .await?;
+
+    let client = async_client_with_webhooks(pg_connect_options, webhooks).await;
+
+    let request = h_receive(&mut requests).await;
+    let payload: Value = h_parse_dto(request.body.as_str());
+    assert_eq!(payload["key"], json!("max_connections"));
+
+    let delivery = h_wait_for_delivery(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(delivery.key, "max_connections");
+    assert_eq!(delivery.status, "delivered");
+
+    let deliveries = h_deliveries(&client, "configmonkey", webhook.id.as_str()).await;
+    assert_eq!(deliveries.data[1].key, "log_level");
+    assert_eq!(deliveries.data[1].status, "failed");
+    assert!(deliveries.data[1].error.is_some());
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn get_webhooks_and_delete_webhook(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+    let webhook = h_webhook(
+        &client,
+        "configmonkey",
+        json!({ "url": "https://cd.example.com/hooks", "secret": "s3cret" }),
+    )
+    .await;
+
+    let response = h_get_webhooks(&client, "configmonkey").await;
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+
+    let response_body = h_parse_response(response).await;
+    // The secret is never shown
+    assert!(!response_body.contains("s3cret"));
+    let webhooks: PaginatedListDto<GetWebhookDto> = h_parse_dto(response_body.as_str());
+    assert_eq!(webhooks.data.len(), 1);
+    assert_eq!(webhooks.data[0].id, webhook.id);
+    assert_eq!(webhooks.data[0].url, "https://cd.example.com/hooks");
+
+    let response = h_delete_webhook(&client, "configmonkey", webhook.id.as_str()).await;
+
+    assert_eq!(response.status(), Status::NoContent);
+
+    for id in [webhook.id.as_str(), "not-a-uuid"] {
+        let response = h_delete_webhook(&client, "configmonkey", id).await;
+
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, "webhook_not_found");
+        assert_eq!(error_dto.message, "Webhook not found");
+
+        let response = h_get_webhook_deliveries(&client, "configmonkey", id).await;
+
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_webhook_err_invalid(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_from_pg_connect_options(pg_connect_options).await;
+    h_create_domain(&client, "configmonkey").await;
+
+    for (webhook, code) in [
+        (
+            json!({ "url": "ftp://example.com", "secret": "s" }),
+            "invalid_url",
+        ),
+        (json!({ "url": "/hooks", "secret": "s" }), "invalid_url"),
+        (
+            json!({ "url": "https://example.com", "secret": "s", "events": [] }),
+            "invalid_events",
+        ),
+        (
+            json!({ "url": "https://example.com", "secret": "s", "events": ["domain_deleted"] }),
+            "invalid_events",
+        ),
+        (
+            json!({ "url": "https://example.com", "secret": "s", "keys": [] }),
+            "invalid_keys",
+        ),
+        (
+            json!({ "url": "https://example.com", "secret": "" }),
+            "invalid_secret",
+        ),
+    ] {
+        let response = h_create_webhook(&client, "configmonkey", webhook).await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response_body = h_parse_response(response).await;
+        let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+        assert_eq!(error_dto.code, code);
+    }
+
+    let response = h_create_webhook(
+        &client,
+        "other",
+        json!({ "url": "https://example.com", "secret": "s" }),
+    )
+    .await;
+
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "domain_not_found");
+
+    Ok(())
+}
+
+#[sqlx::test]
+async fn create_webhook_err_forbidden(
+    _: PgPoolOptions,
+    pg_connect_options: PgConnectOptions,
+) -> sqlx::Result<()> {
+    let client = async_client_with_rbac(
+        pg_connect_options,
+        json!({"enabled": true, "administrators": ["root"]}),
+    )
+    .await;
+    h_create_domain_as(&client, "root", "configmonkey").await;
+    h_create_grant(&client, "root", "alice", "writer", Some("configmonkey")).await;
+    h_create_grant(&client, "root", "bob", "admin", Some("configmonkey")).await;
+    let webhook = json!({ "url": "https://example.com", "secret": "s" });
+
+    let response = h_create_webhook_as(&client, "alice", "configmonkey", webhook.clone()).await;
+
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let response_body = h_parse_response(response).await;
+    let error_dto: ErrorDto = h_parse_dto(response_body.as_str());
+    assert_eq!(error_dto.code, "forbidden");
+
+    let response = h_create_webhook_as(&client, "bob", "configmonkey", webhook).await;
+
+    assert_eq!(response.status(), Status::Created);
+
+    Ok(())
+}